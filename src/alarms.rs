@@ -0,0 +1,1054 @@
+// Alarm limits checked by the UI against the latest node values of every site,
+// and the alerts (notifications, beeps) announced for new alarms and stale nodes.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use bson::DateTime;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+use crate::dashboards::node_is_on;
+use crate::{
+    active_backend, age_text, cache_key, cached_list, format_timestamp, is_stale, load_session,
+    load_session_for, local_storage_get, local_storage_set, revalidate, revalidate_backend,
+    site_path, use_node_cache, use_role, use_session, use_ui_settings, value_with_unit, RapiStruct,
+    Role,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AlarmLevel {
+    HighHigh,
+    High,
+    Low,
+    LowLow,
+    State,
+}
+
+impl AlarmLevel {
+    fn label(&self) -> &'static str {
+        match self {
+            AlarmLevel::HighHigh => "HIHI",
+            AlarmLevel::High => "HI",
+            AlarmLevel::Low => "LO",
+            AlarmLevel::LowLow => "LOLO",
+            AlarmLevel::State => "STATE",
+        }
+    }
+}
+
+// The limit the value of a node crosses, high-high and low-low win over high and low.
+// Returns the level and the limit as text.
+fn alarm_level(node: &RapiStruct) -> Option<(AlarmLevel, String)> {
+    if !node.node_alarm_state.is_empty() {
+        let alarm_while_on = node_is_on(&node.node_alarm_state);
+        if node_is_on(&node.node_val) == alarm_while_on {
+            let state = if alarm_while_on { "on" } else { "off" };
+            return Some((AlarmLevel::State, state.to_string()));
+        }
+    }
+
+    let value = node.node_val.trim().parse::<f64>().ok()?;
+    let checks = [
+        (AlarmLevel::HighHigh, &node.node_alarm_hh, true),
+        (AlarmLevel::LowLow, &node.node_alarm_ll, false),
+        (AlarmLevel::High, &node.node_alarm_h, true),
+        (AlarmLevel::Low, &node.node_alarm_l, false),
+    ];
+    checks.into_iter().find_map(|(level, limit, high)| {
+        let limit_value = limit.trim().parse::<f64>().ok()?;
+        let crossed = if high {
+            value >= limit_value
+        } else {
+            value <= limit_value
+        };
+        crossed.then(|| (level, limit.trim().to_string()))
+    })
+}
+
+// Whether the value of `node` is still at `level`, allowing for the deadband
+fn alarm_holds(node: &RapiStruct, level: AlarmLevel) -> bool {
+    let (limit, high) = match level {
+        AlarmLevel::State => return matches!(alarm_level(node), Some((AlarmLevel::State, _))),
+        AlarmLevel::HighHigh => (&node.node_alarm_hh, true),
+        AlarmLevel::High => (&node.node_alarm_h, true),
+        AlarmLevel::Low => (&node.node_alarm_l, false),
+        AlarmLevel::LowLow => (&node.node_alarm_ll, false),
+    };
+    let (Ok(value), Ok(limit)) = (
+        node.node_val.trim().parse::<f64>(),
+        limit.trim().parse::<f64>(),
+    ) else {
+        return false;
+    };
+    let band = limit.abs() * ALARM_DEADBAND;
+    if high {
+        value >= limit - band
+    } else {
+        value <= limit + band
+    }
+}
+
+fn is_critical(level: AlarmLevel) -> bool {
+    matches!(level, AlarmLevel::HighHigh | AlarmLevel::LowLow)
+}
+
+// "HIHI 30, HI 25, LO 15" for the detail page
+pub fn alarm_limits_text(node: &RapiStruct) -> String {
+    let mut parts: Vec<String> = [
+        (AlarmLevel::HighHigh, &node.node_alarm_hh),
+        (AlarmLevel::High, &node.node_alarm_h),
+        (AlarmLevel::Low, &node.node_alarm_l),
+        (AlarmLevel::LowLow, &node.node_alarm_ll),
+    ]
+    .into_iter()
+    .filter(|(_, limit)| !limit.is_empty())
+    .map(|(level, limit)| format!("{} {limit}", level.label()))
+    .collect();
+    match node.node_alarm_state.as_str() {
+        "" => {}
+        state if node_is_on(state) => parts.push("alarm while on".to_string()),
+        _ => parts.push("alarm while off".to_string()),
+    }
+    if parts.is_empty() {
+        "none".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+// An alarm raised by the UI, kept until it is both cleared and acknowledged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlarmRecord {
+    pub site: String,
+    pub node_uid: String,
+    pub node_name: String,
+    pub level: AlarmLevel,
+    // The value that raised the alarm and the limit it crossed
+    pub value: String,
+    pub limit: String,
+    pub raised_at: DateTime,
+    // None while the value is still past the limit
+    pub cleared_at: Option<DateTime>,
+    pub acked_by: Option<String>,
+}
+
+const ALARMS_KEY: &str = "dcui_alarms";
+// Fired on the window whenever the alarm list is written
+const ALARMS_CHANGED_EVENT: &str = "dcui-alarms-changed";
+pub const ALARM_POLL_SECS: u64 = 10;
+// Older cached node lists are not evaluated, they would raise alarms from the past
+const ALARM_DATA_MAX_AGE_SECS: i64 = 60;
+// An alarm clears or drops to a lower level only once the value is back past
+// its limit by this share of the limit, a value hovering at the limit raises it once
+const ALARM_DEADBAND: f64 = 0.02;
+// Cleared alarms waiting for an acknowledgement are dropped oldest first beyond this
+const MAX_ALARM_RECORDS: usize = 500;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Alarms(pub RwSignal<Vec<AlarmRecord>>);
+
+fn use_alarms(cx: Scope) -> RwSignal<Vec<AlarmRecord>> {
+    use_context::<Alarms>(cx)
+        .expect("alarms are provided by App")
+        .0
+}
+
+pub fn load_alarms() -> Vec<AlarmRecord> {
+    local_storage_get(ALARMS_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn store_alarms(alarms: &[AlarmRecord]) {
+    if let Ok(raw) = serde_json::to_string(alarms) {
+        local_storage_set(ALARMS_KEY, &raw);
+    }
+    if let Ok(event) = web_sys::Event::new(ALARMS_CHANGED_EVENT) {
+        let _ = window().dispatch_event(&event);
+    }
+}
+
+// Raises and clears the alarms of `site` from its latest node list. A level change
+// clears the old alarm and raises a new one, clearing and dropping to a lower level
+// wait for the deadband. Cleared and acknowledged alarms are dropped.
+fn evaluate_alarms(alarms: &mut Vec<AlarmRecord>, site: &str, nodes: &[RapiStruct], now: DateTime) {
+    for node in nodes {
+        let open = alarms
+            .iter_mut()
+            .find(|a| a.site == site && a.node_uid == node.node_uid && a.cleared_at.is_none());
+        let level = alarm_level(node);
+        let settling = open.as_ref().is_some_and(|open| {
+            let lower = match &level {
+                None => true,
+                Some((level, _)) => is_critical(open.level) && !is_critical(*level),
+            };
+            lower && alarm_holds(node, open.level)
+        });
+        match (level, open) {
+            (_, Some(_)) if settling => {}
+            (Some((level, _)), Some(open)) if open.level == level => {}
+            (Some((level, limit)), open) => {
+                if let Some(open) = open {
+                    open.cleared_at = Some(now);
+                }
+                alarms.push(AlarmRecord {
+                    site: site.to_string(),
+                    node_uid: node.node_uid.clone(),
+                    node_name: node.node_name.clone(),
+                    level,
+                    value: value_with_unit(node),
+                    limit,
+                    raised_at: now,
+                    cleared_at: None,
+                    acked_by: None,
+                });
+            }
+            (None, Some(open)) => open.cleared_at = Some(now),
+            (None, None) => {}
+        }
+    }
+
+    // Deleted nodes cannot be in alarm
+    for alarm in alarms.iter_mut() {
+        if alarm.site == site
+            && alarm.cleared_at.is_none()
+            && !nodes.iter().any(|node| node.node_uid == alarm.node_uid)
+        {
+            alarm.cleared_at = Some(now);
+        }
+    }
+    alarms.retain(|a| a.cleared_at.is_none() || a.acked_by.is_none());
+    cap_alarms(alarms);
+}
+
+// Open alarms are bounded by the number of nodes, only cleared ones are dropped
+fn cap_alarms(alarms: &mut Vec<AlarmRecord>) {
+    let mut cleared: Vec<DateTime> = alarms.iter().filter_map(|a| a.cleared_at).collect();
+    let excess = alarms
+        .len()
+        .saturating_sub(MAX_ALARM_RECORDS)
+        .min(cleared.len());
+    if excess == 0 {
+        return;
+    }
+    cleared.sort();
+    let cutoff = cleared[excess - 1];
+    let mut dropped = 0;
+    alarms.retain(|a| {
+        let drop = dropped < excess && a.cleared_at.is_some_and(|at| at <= cutoff);
+        dropped += usize::from(drop);
+        !drop
+    });
+}
+
+// Acknowledges the alarms `which` picks, as the logged in user
+fn acknowledge_alarms(which: impl Fn(&AlarmRecord) -> bool) {
+    let user = load_session()
+        .map(|s| s.username)
+        .unwrap_or_else(|| "anonymous".to_string());
+    let mut alarms = load_alarms();
+    for alarm in alarms
+        .iter_mut()
+        .filter(|a| a.acked_by.is_none() && which(a))
+    {
+        alarm.acked_by = Some(user.clone());
+    }
+    alarms.retain(|a| a.cleared_at.is_none() || a.acked_by.is_none());
+    store_alarms(&alarms);
+}
+
+// Ordered loudest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AlertSeverity {
+    Critical,
+    Warning,
+    Stale,
+}
+
+impl AlertSeverity {
+    const ALL: [AlertSeverity; 3] = [
+        AlertSeverity::Critical,
+        AlertSeverity::Warning,
+        AlertSeverity::Stale,
+    ];
+
+    fn of(level: AlarmLevel) -> Self {
+        match level {
+            AlarmLevel::HighHigh | AlarmLevel::LowLow => AlertSeverity::Critical,
+            AlarmLevel::High | AlarmLevel::Low | AlarmLevel::State => AlertSeverity::Warning,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            AlertSeverity::Critical => "Critical (HIHI, LOLO)",
+            AlertSeverity::Warning => "Warning (HI, LO, state)",
+            AlertSeverity::Stale => "Stale node",
+        }
+    }
+}
+
+// How alerts of one severity are announced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertChannels {
+    pub notify: bool,
+    pub sound: bool,
+}
+
+// Everything is off until the user opts in on the settings page
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertSettings {
+    pub critical: AlertChannels,
+    pub warning: AlertChannels,
+    pub stale: AlertChannels,
+    pub muted: bool,
+    pub snoozed_until: Option<DateTime>,
+}
+
+impl AlertSettings {
+    fn channels(&self, severity: AlertSeverity) -> AlertChannels {
+        match severity {
+            AlertSeverity::Critical => self.critical,
+            AlertSeverity::Warning => self.warning,
+            AlertSeverity::Stale => self.stale,
+        }
+    }
+
+    fn channels_mut(&mut self, severity: AlertSeverity) -> &mut AlertChannels {
+        match severity {
+            AlertSeverity::Critical => &mut self.critical,
+            AlertSeverity::Warning => &mut self.warning,
+            AlertSeverity::Stale => &mut self.stale,
+        }
+    }
+
+    fn snoozed(&self, now: DateTime) -> bool {
+        self.snoozed_until.is_some_and(|until| until > now)
+    }
+}
+
+// One announced (or silenced) alert, newest first in the history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRecord {
+    pub at: DateTime,
+    pub site: String,
+    pub node_uid: String,
+    pub severity: AlertSeverity,
+    pub text: String,
+    // What was done with it: "Notification, sound", "Muted", ...
+    pub outcome: String,
+}
+
+const ALERT_HISTORY_KEY: &str = "dcui_alert_history";
+const ALERT_HISTORY_CHANGED_EVENT: &str = "dcui-alert-history-changed";
+const ALERT_HISTORY_LEN: usize = 100;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AlertHistory(pub RwSignal<Vec<AlertRecord>>);
+
+fn use_alert_history(cx: Scope) -> RwSignal<Vec<AlertRecord>> {
+    use_context::<AlertHistory>(cx)
+        .expect("alert history is provided by App")
+        .0
+}
+
+pub fn load_alert_history() -> Vec<AlertRecord> {
+    local_storage_get(ALERT_HISTORY_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn store_alert_history(history: &[AlertRecord]) {
+    if let Ok(raw) = serde_json::to_string(history) {
+        local_storage_set(ALERT_HISTORY_KEY, &raw);
+    }
+    if let Ok(event) = web_sys::Event::new(ALERT_HISTORY_CHANGED_EVENT) {
+        let _ = window().dispatch_event(&event);
+    }
+}
+
+pub fn alarm_alert(alarm: &AlarmRecord) -> AlertRecord {
+    AlertRecord {
+        at: alarm.raised_at,
+        site: alarm.site.clone(),
+        node_uid: alarm.node_uid.clone(),
+        severity: AlertSeverity::of(alarm.level),
+        text: format!(
+            "{} {}: {} (limit {})",
+            alarm.node_name,
+            alarm.level.label(),
+            alarm.value,
+            alarm.limit
+        ),
+        outcome: String::new(),
+    }
+}
+
+fn stale_alert(site: &str, node: &RapiStruct, now: DateTime) -> AlertRecord {
+    AlertRecord {
+        at: now,
+        site: site.to_string(),
+        node_uid: node.node_uid.clone(),
+        severity: AlertSeverity::Stale,
+        text: format!(
+            "{} is stale, last update {}",
+            node.node_name,
+            age_text(&node.node_last_update, &now)
+        ),
+        outcome: String::new(),
+    }
+}
+
+// Browsers without the Notification API (iOS Safari in a tab) throw on any use of it
+fn notifications_supported() -> bool {
+    js_sys::Reflect::has(&window(), &wasm_bindgen::JsValue::from_str("Notification"))
+        .unwrap_or(false)
+}
+
+fn notification_permission() -> Option<web_sys::NotificationPermission> {
+    notifications_supported().then(web_sys::Notification::permission)
+}
+
+fn request_notification_permission(on_done: impl FnOnce() + 'static) {
+    if !notifications_supported() {
+        return;
+    }
+    if let Ok(promise) = web_sys::Notification::request_permission() {
+        spawn_local(async move {
+            let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+            on_done();
+        });
+    }
+}
+
+fn show_notification(alert: &AlertRecord) -> bool {
+    if notification_permission() != Some(web_sys::NotificationPermission::Granted) {
+        return false;
+    }
+    let options = web_sys::NotificationOptions::new();
+    options.set_body(&alert.text);
+    // A newer alert of the same node replaces the older one
+    options.set_tag(&format!("{}/{}", alert.site, alert.node_uid));
+    web_sys::Notification::new_with_options(
+        &format!("{} alarm on {}", alert_title(alert.severity), alert.site),
+        &options,
+    )
+    .is_ok()
+}
+
+fn alert_title(severity: AlertSeverity) -> &'static str {
+    match severity {
+        AlertSeverity::Critical => "Critical",
+        AlertSeverity::Warning => "Warning",
+        AlertSeverity::Stale => "Stale",
+    }
+}
+
+thread_local! {
+    // Created on first use, browsers keep it suspended until a click on the page
+    static AUDIO: RefCell<Option<web_sys::AudioContext>> = const { RefCell::new(None) };
+}
+
+// Short beeps, more and higher ones the more severe the alert
+fn play_alert_sound(severity: AlertSeverity) -> bool {
+    let (frequency, beeps) = match severity {
+        AlertSeverity::Critical => (880.0, 3),
+        AlertSeverity::Warning => (660.0, 2),
+        AlertSeverity::Stale => (440.0, 1),
+    };
+    AUDIO.with(|audio| {
+        let mut audio = audio.borrow_mut();
+        if audio.is_none() {
+            *audio = web_sys::AudioContext::new().ok();
+        }
+        let Some(context) = audio.as_ref() else {
+            return false;
+        };
+        let _ = context.resume();
+        let start = context.current_time();
+        (0..beeps).all(|beep| {
+            let at = start + beep as f64 * 0.3;
+            let play = || -> Result<(), wasm_bindgen::JsValue> {
+                let oscillator = context.create_oscillator()?;
+                let gain = context.create_gain()?;
+                oscillator.frequency().set_value(frequency);
+                gain.gain().set_value(0.2);
+                oscillator.connect_with_audio_node(&gain)?;
+                gain.connect_with_audio_node(&context.destination())?;
+                oscillator.start_with_when(at)?;
+                oscillator.stop_with_when(at + 0.2)
+            };
+            play().is_ok()
+        })
+    })
+}
+
+// Notifies and beeps as the settings say and writes every alert to the history
+pub fn announce(settings: &AlertSettings, mut alerts: Vec<AlertRecord>, now: DateTime) {
+    let mut history = load_alert_history();
+    // The loudest alert of a batch beeps once instead of all of them at the same time
+    alerts.sort_by_key(|alert| alert.severity);
+    let mut sounded = false;
+    for mut alert in alerts {
+        let channels = settings.channels(alert.severity);
+        alert.outcome = if settings.muted {
+            "Muted".to_string()
+        } else if settings.snoozed(now) {
+            "Snoozed".to_string()
+        } else if !channels.notify && !channels.sound {
+            "Off".to_string()
+        } else {
+            let mut done = Vec::new();
+            if channels.notify {
+                done.push(if show_notification(&alert) {
+                    "Notification"
+                } else {
+                    "Notification blocked"
+                });
+            }
+            if channels.sound && !sounded {
+                sounded = play_alert_sound(alert.severity);
+                done.push(if sounded { "Sound" } else { "Sound failed" });
+            }
+            done.join(", ")
+        };
+        history.insert(0, alert);
+    }
+    history.truncate(ALERT_HISTORY_LEN);
+    store_alert_history(&history);
+}
+
+// Per-severity notification and sound switches on the settings page
+#[component]
+pub fn AlertSettingsSection(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+    let (permission, set_permission) = create_signal(cx, notification_permission());
+
+    let permission_text = move || match permission.get() {
+        None => "This browser does not support notifications",
+        Some(web_sys::NotificationPermission::Granted) => "Notifications are allowed",
+        Some(web_sys::NotificationPermission::Denied) => {
+            "Notifications are blocked in the browser settings"
+        }
+        Some(_) => "Notifications are not allowed yet",
+    };
+    let can_ask = move || permission.get() == Some(web_sys::NotificationPermission::Default);
+
+    let rows = AlertSeverity::ALL
+        .into_iter()
+        .map(|severity| {
+            let channels = move || settings.with(|s| s.alerts.channels(severity));
+            view! { cx,
+                <tr class="alert_setting border-b">
+                    <td class="p-2">{severity.label()}</td>
+                    <td class="p-2">
+                        <input type="checkbox"
+                        class="alert_notify"
+                        prop:checked=move || channels().notify
+                        on:change=move |ev| {
+                            let on = event_target_checked(&ev);
+                            settings.update(|s| s.alerts.channels_mut(severity).notify = on);
+                        }
+                        />
+                    </td>
+                    <td class="p-2">
+                        <input type="checkbox"
+                        class="alert_sound"
+                        prop:checked=move || channels().sound
+                        on:change=move |ev| {
+                            let on = event_target_checked(&ev);
+                            settings.update(|s| s.alerts.channels_mut(severity).sound = on);
+                        }
+                        />
+                    </td>
+                    <td class="p-2">
+                        <button class="underline hover:text-gray-500" on:click=move |_| {
+                            play_alert_sound(severity);
+                        }>
+                        "Test sound"
+                        </button>
+                    </td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! { cx,
+        <h3 class="font-bold text-lg mb-6">"Alarm alerts"</h3>
+        <p class="mb-4">"New alarms and nodes turning stale can show a browser notification and beep while this page is open."</p>
+        <p class="alert_permission mb-4">
+            {permission_text}
+            <button
+            class="bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-1 px-3 ml-2 rounded"
+            class:hidden=move || !can_ask()
+            on:click=move |_| request_notification_permission(move || set_permission.set(notification_permission()))
+            >
+            "Allow notifications"
+            </button>
+        </p>
+        <table class="w-full text-left text-sm mb-6">
+            <thead>
+                <tr class="border-b font-bold">
+                    <th class="p-2">"Severity"</th>
+                    <th class="p-2">"Notification"</th>
+                    <th class="p-2">"Sound"</th>
+                    <th class="p-2"></th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
+
+// Mute and snooze switches above the alarm list
+#[component]
+fn AlertControls(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+
+    let state = move || {
+        let alerts = settings.with(|s| s.alerts.clone());
+        match alerts.snoozed_until {
+            _ if alerts.muted => "Alerts are muted".to_string(),
+            Some(until) if alerts.snoozed(DateTime::now()) => {
+                format!(
+                    "Alerts are snoozed until {}",
+                    format_timestamp(&until, &settings.get())
+                )
+            }
+            _ => "Alerts are on".to_string(),
+        }
+    };
+    let silenced = move || settings.with(|s| s.alerts.muted || s.alerts.snoozed(DateTime::now()));
+    let snooze = move |minutes: i64| {
+        let until = DateTime::from_millis(DateTime::now().timestamp_millis() + minutes * 60_000);
+        settings.update(|s| s.alerts.snoozed_until = Some(until));
+    };
+    let button =
+        "bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-1 px-3 mr-2 rounded";
+
+    view! { cx,
+        <div class="alert_controls flex flex-wrap items-center mb-4">
+            <span class="alert_state mr-4">{state}</span>
+            <button class=button class:hidden=silenced on:click=move |_| settings.update(|s| s.alerts.muted = true)>
+            "Mute"
+            </button>
+            <button class=button class:hidden=silenced on:click=move |_| snooze(15)>"Snooze 15 min"</button>
+            <button class=button class:hidden=silenced on:click=move |_| snooze(60)>"Snooze 1 h"</button>
+            <button
+            class=button
+            class:hidden=move || !silenced()
+            on:click=move |_| settings.update(|s| {
+                s.alerts.muted = false;
+                s.alerts.snoozed_until = None;
+            })
+            >
+            "Resume alerts"
+            </button>
+            <A class="underline ml-auto" href="/settings">"Alert settings"</A>
+        </div>
+    }
+}
+
+// The last alerts and what was done with them
+#[component]
+fn AlertHistoryPanel(cx: Scope) -> impl IntoView {
+    let history = use_alert_history(cx);
+    let settings = use_ui_settings(cx);
+
+    let rows = move || {
+        history
+            .get()
+            .into_iter()
+            .map(|alert| {
+                view! { cx,
+                    <tr class="alert_history_row border-b">
+                        <td class="p-2">{format_timestamp(&alert.at, &settings.get())}</td>
+                        <td class="p-2">{alert.site}</td>
+                        <td class="p-2">{alert_title(alert.severity)}</td>
+                        <td class="p-2">{alert.text}</td>
+                        <td class="p-2">{alert.outcome}</td>
+                    </tr>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    view! { cx,
+        <div class="alert_history m-5 p-5 rounded overflow-hidden shadow-lg border dark:text-gray-300">
+            <h2 class="font-bold text-xl mb-2">"Notification history"</h2>
+            <Show
+            when=move || history.with(|h| !h.is_empty())
+            fallback=|cx| view! { cx, <p>"No alerts yet."</p> }
+            >
+                <button
+                class="bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-1 px-3 rounded mb-4"
+                on:click=move |_| store_alert_history(&[])
+                >
+                "Clear history"
+                </button>
+                <table class="w-full text-left text-sm">
+                    <thead>
+                        <tr class="border-b font-bold">
+                            <th class="p-2">"Time"</th>
+                            <th class="p-2">"Site"</th>
+                            <th class="p-2">"Severity"</th>
+                            <th class="p-2">"Alert"</th>
+                            <th class="p-2">"Announced"</th>
+                        </tr>
+                    </thead>
+                    <tbody>{rows}</tbody>
+                </table>
+            </Show>
+        </div>
+    }
+}
+
+// Evaluates the alarm limits on every fresh node list in the cache and keeps
+// the node list of every site with a session fresh
+#[component]
+pub fn AlarmWatcher(cx: Scope) -> impl IntoView {
+    let alarms = use_alarms(cx);
+    let history = use_alert_history(cx);
+    let cache = use_node_cache(cx);
+    let settings = use_ui_settings(cx);
+    let session = use_session(cx);
+    // Stale uids per site from the last evaluation, a site seen for the first time announces nothing
+    let stale_seen = store_value(cx, HashMap::<String, HashSet<String>>::new());
+
+    window_event_listener_untyped(ALARMS_CHANGED_EVENT, move |_| alarms.set(load_alarms()));
+    window_event_listener_untyped(ALERT_HISTORY_CHANGED_EVENT, move |_| {
+        history.set(load_alert_history())
+    });
+
+    create_effect(cx, move |_| {
+        let sites: Vec<String> =
+            settings.with(|s| s.backends.iter().map(|b| b.id.clone()).collect());
+        let stale_after_secs = settings.with_untracked(|s| s.stale_after_secs);
+        let now = DateTime::now();
+        let before = load_alarms();
+        let mut updated = before.clone();
+        let mut alerts = Vec::new();
+        cache.entries.with(|entries| {
+            for site in sites {
+                let key = cache_key(&site, "bucket");
+                let fresh = entries.get(&key).is_some_and(|cached| {
+                    now.timestamp_millis() - cached.fetched_at.timestamp_millis()
+                        < ALARM_DATA_MAX_AGE_SECS * 1000
+                });
+                let Some(nodes) = cached_list::<RapiStruct>(entries, &key).filter(|_| fresh) else {
+                    continue;
+                };
+                evaluate_alarms(&mut updated, &site, &nodes, now);
+
+                let stale: HashSet<String> = nodes
+                    .iter()
+                    .filter(|node| is_stale(node, stale_after_secs))
+                    .map(|node| node.node_uid.clone())
+                    .collect();
+                let previous = stale_seen
+                    .try_update_value(|seen| seen.insert(site.clone(), stale.clone()))
+                    .flatten();
+                if let Some(previous) = previous {
+                    alerts.extend(
+                        nodes
+                            .iter()
+                            .filter(|node| {
+                                stale.contains(&node.node_uid) && !previous.contains(&node.node_uid)
+                            })
+                            .map(|node| stale_alert(&site, node, now)),
+                    );
+                }
+            }
+        });
+        alerts.extend(
+            updated
+                .iter()
+                .filter(|alarm| !before.contains(alarm) && alarm.cleared_at.is_none())
+                .map(alarm_alert),
+        );
+        if alarms.with_untracked(|current| *current != updated) {
+            store_alarms(&updated);
+        }
+        if !alerts.is_empty() {
+            let alert_settings = settings.with_untracked(|s| s.alerts.clone());
+            announce(&alert_settings, alerts, now);
+        }
+    });
+
+    let poll = move || {
+        if session.with_untracked(|s| s.is_some()) {
+            revalidate("bucket");
+        }
+        let active = active_backend().id;
+        let others = settings.with_untracked(|s| s.backends.clone());
+        for backend in others.into_iter().filter(|b| b.id != active) {
+            if load_session_for(&backend.id).is_some() {
+                revalidate_backend(backend, "bucket");
+            }
+        }
+    };
+    poll();
+    if let Ok(handle) =
+        set_interval_with_handle(poll, std::time::Duration::from_secs(ALARM_POLL_SECS))
+    {
+        on_cleanup(cx, move || handle.clear());
+    }
+}
+
+// Active and unacknowledged alarms of every site, newest first
+#[component]
+pub fn AlarmsComponent(cx: Scope) -> impl IntoView {
+    let alarms = use_alarms(cx);
+    let settings = use_ui_settings(cx);
+    let cache = use_node_cache(cx);
+    let role = use_role(cx);
+    let can_ack = move || role.get() >= Role::Operator;
+
+    let rows = move || {
+        let now = DateTime::now();
+        // When the node list of each site was last read, older ones no longer update its alarms
+        let fetched_at = move |site: &str| {
+            cache
+                .entries
+                .with(|entries| {
+                    entries
+                        .get(&cache_key(site, "bucket"))
+                        .map(|cached| cached.fetched_at)
+                })
+                .filter(|at| at.timestamp_millis() > 0)
+        };
+        let mut list = alarms.get();
+        list.sort_by_key(|a| std::cmp::Reverse(a.raised_at));
+        list.into_iter()
+            .map(|alarm| {
+                let ts = |at: &DateTime| format_timestamp(at, &settings.get());
+                let raised = ts(&alarm.raised_at);
+                let state = match (&alarm.cleared_at, fetched_at(&alarm.site)) {
+                    (Some(at), _) => format!("Cleared {}", ts(at)),
+                    (None, Some(at))
+                        if now.timestamp_millis() - at.timestamp_millis() < ALARM_DATA_MAX_AGE_SECS * 1000 =>
+                    {
+                        "Active".to_string()
+                    }
+                    (None, Some(at)) => format!("Active, stale: no data since {}", ts(&at)),
+                    (None, None) => "Active, stale: no data from the site".to_string(),
+                };
+                let active = alarm.cleared_at.is_none();
+                let acked = alarm.acked_by.clone();
+                let href = site_path(&alarm.site, &format!("/node/{}", alarm.node_uid));
+                let key = (alarm.site.clone(), alarm.node_uid.clone(), alarm.raised_at);
+                view! { cx,
+                    <tr class="alarm_row border-b" class:font-bold=acked.is_none() class:text-red-600=active>
+                        <td class="p-2">{alarm.site.clone()}</td>
+                        <td class="p-2"><A class="underline" href=href>{alarm.node_name.clone()}</A></td>
+                        <td class="p-2">{alarm.level.label()}</td>
+                        <td class="p-2">{alarm.value.clone()}</td>
+                        <td class="p-2">{alarm.limit.clone()}</td>
+                        <td class="p-2">{raised}</td>
+                        <td class="p-2">{state}</td>
+                        <td class="p-2">
+                            {match acked {
+                                Some(user) => format!("Acknowledged by {user}").into_view(cx),
+                                None => view! { cx,
+                                    <Show when=can_ack fallback=|_| "Unacknowledged">
+                                        <button
+                                        class="alarm_ack bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-1 px-3 rounded"
+                                        on:click={
+                                            let key = key.clone();
+                                            move |_| {
+                                                let key = key.clone();
+                                                acknowledge_alarms(move |a| (a.site.clone(), a.node_uid.clone(), a.raised_at) == key)
+                                            }
+                                        }
+                                        >
+                                        "Acknowledge"
+                                        </button>
+                                    </Show>
+                                }
+                                .into_view(cx),
+                            }}
+                        </td>
+                    </tr>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let unacked = move || alarms.with(|a| a.iter().filter(|a| a.acked_by.is_none()).count());
+    let show_ack_all = move || can_ack() && unacked() != 0;
+
+    view! { cx,
+        <div class="m-5 p-5 rounded overflow-hidden shadow-lg border dark:text-gray-300">
+            <h2 class="font-bold text-xl mb-2">"Alarms"</h2>
+            <p class="text-sm text-gray-500 mb-4">
+                "Limits are set on the node detail pages and checked in the browser against the latest values."
+            </p>
+            <AlertControls/>
+            <Show when=move || alarms.with(|a| a.is_empty()) fallback=|_| ()>
+                <p>"No active or unacknowledged alarms"</p>
+            </Show>
+            <Show when=show_ack_all fallback=|_| ()>
+                <button
+                class="alarm_ack_all bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-2 px-4 rounded mb-4"
+                on:click=move |_| acknowledge_alarms(|_| true)
+                >
+                {move || format!("Acknowledge all ({})", unacked())}
+                </button>
+            </Show>
+            <table class="w-full text-left text-sm">
+                <thead>
+                    <tr class="border-b font-bold">
+                        <th class="p-2">"Site"</th>
+                        <th class="p-2">"Node"</th>
+                        <th class="p-2">"Level"</th>
+                        <th class="p-2">"Value"</th>
+                        <th class="p-2">"Limit"</th>
+                        <th class="p-2">"Raised"</th>
+                        <th class="p-2">"State"</th>
+                        <th class="p-2">"Acknowledged"</th>
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        </div>
+        <AlertHistoryPanel/>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit_tests::test_nodes;
+
+    #[test]
+    fn alarm_evaluation_raises_changes_and_clears() {
+        let mut node = test_nodes()[0].clone();
+        node.node_alarm_hh = "30".to_string();
+        node.node_alarm_h = "25".to_string();
+        node.node_alarm_l = "15".to_string();
+        let mut alarms = Vec::new();
+
+        evaluate_alarms(
+            &mut alarms,
+            "demo",
+            std::slice::from_ref(&node),
+            DateTime::from_millis(1_000),
+        );
+        assert!(alarms.is_empty());
+
+        node.node_val = "26".to_string();
+        evaluate_alarms(
+            &mut alarms,
+            "demo",
+            std::slice::from_ref(&node),
+            DateTime::from_millis(2_000),
+        );
+        assert_eq!(alarms.len(), 1);
+        assert_eq!(alarms[0].level, AlarmLevel::High);
+        assert_eq!(alarms[0].value, "26 degC");
+        assert_eq!(alarms[0].limit, "25");
+
+        // Same level again changes nothing, a higher one replaces it
+        evaluate_alarms(
+            &mut alarms,
+            "demo",
+            std::slice::from_ref(&node),
+            DateTime::from_millis(3_000),
+        );
+        assert_eq!(alarms.len(), 1);
+        node.node_val = "31".to_string();
+        evaluate_alarms(
+            &mut alarms,
+            "demo",
+            std::slice::from_ref(&node),
+            DateTime::from_millis(4_000),
+        );
+        assert_eq!(alarms.len(), 2);
+        assert!(alarms[0].cleared_at.is_some());
+        assert_eq!(alarms[1].level, AlarmLevel::HighHigh);
+
+        // Cleared alarms stay until acknowledged
+        node.node_val = "20".to_string();
+        alarms[0].acked_by = Some("tester".to_string());
+        evaluate_alarms(
+            &mut alarms,
+            "demo",
+            std::slice::from_ref(&node),
+            DateTime::from_millis(5_000),
+        );
+        assert_eq!(alarms.len(), 1);
+        assert_eq!(alarms[0].cleared_at, Some(DateTime::from_millis(5_000)));
+
+        node.node_alarm_state = "0".to_string();
+        node.node_val = "0".to_string();
+        assert_eq!(
+            alarm_level(&node),
+            Some((AlarmLevel::State, "off".to_string()))
+        );
+    }
+
+    #[test]
+    fn alarms_clear_only_past_the_deadband() {
+        let mut node = test_nodes()[0].clone();
+        node.node_alarm_hh = "30".to_string();
+        node.node_alarm_h = "25".to_string();
+        let mut alarms = Vec::new();
+        let mut evaluate_at = |value: &str, at: i64| {
+            node.node_val = value.to_string();
+            evaluate_alarms(
+                &mut alarms,
+                "demo",
+                std::slice::from_ref(&node),
+                DateTime::from_millis(at),
+            );
+            alarms
+                .iter()
+                .filter(|a| a.cleared_at.is_none())
+                .map(|a| a.level)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(evaluate_at("25.1", 1), [AlarmLevel::High]);
+        // 2% of 25 is 0.5, hovering just below the limit keeps the alarm
+        assert_eq!(evaluate_at("24.8", 2), [AlarmLevel::High]);
+        assert_eq!(evaluate_at("25.2", 3), [AlarmLevel::High]);
+        assert_eq!(evaluate_at("24.4", 4), []);
+
+        // A higher level is raised right away, dropping back waits for the deadband
+        assert_eq!(evaluate_at("30", 5), [AlarmLevel::HighHigh]);
+        assert_eq!(evaluate_at("29.5", 6), [AlarmLevel::HighHigh]);
+        assert_eq!(evaluate_at("29", 7), [AlarmLevel::High]);
+        assert_eq!(alarms.len(), 3);
+    }
+
+    #[test]
+    fn stored_alarms_drop_the_oldest_cleared_first() {
+        let record = |uid: usize, cleared_at: Option<i64>| AlarmRecord {
+            site: "demo".to_string(),
+            node_uid: format!("uid-{uid}"),
+            node_name: format!("node_{uid}"),
+            level: AlarmLevel::High,
+            value: "26".to_string(),
+            limit: "25".to_string(),
+            raised_at: DateTime::from_millis(0),
+            cleared_at: cleared_at.map(DateTime::from_millis),
+            acked_by: None,
+        };
+        let mut alarms: Vec<AlarmRecord> = (0..MAX_ALARM_RECORDS)
+            .map(|uid| record(uid, Some(1_000 + uid as i64)))
+            .collect();
+        alarms.insert(0, record(MAX_ALARM_RECORDS, None));
+        alarms.push(record(MAX_ALARM_RECORDS + 1, Some(1)));
+
+        cap_alarms(&mut alarms);
+        assert_eq!(alarms.len(), MAX_ALARM_RECORDS);
+        assert!(alarms[0].cleared_at.is_none());
+        assert!(!alarms
+            .iter()
+            .any(|a| a.cleared_at == Some(DateTime::from_millis(1))));
+        assert!(!alarms.iter().any(|a| a.node_uid == "uid-0"));
+    }
+}
@@ -0,0 +1,553 @@
+// Calculated nodes: values the UI computes in the browser from expressions over
+// other nodes of a site (see expr.rs), kept in the browser.
+
+use std::collections::BTreeMap;
+
+use leptos::html::Textarea;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache_key, cached_list, expr, local_storage_get, revalidate, site_path, use_active_site,
+    use_node_cache, use_role, RapiStruct, Role, ShowSingleDataSpan, WizardInput,
+};
+
+// A node whose value the UI computes from other nodes of its site
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalcNode {
+    // "calc-1", ..., referenced in expressions like a node uid
+    pub id: String,
+    pub site: String,
+    pub name: String,
+    pub expression: String,
+    #[serde(default)]
+    pub unit: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+pub const CALC_NODES_KEY: &str = "dcui_calc_nodes";
+// Suggestions shown while typing a node reference
+const CALC_SUGGESTIONS: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CalcNodes(pub RwSignal<Vec<CalcNode>>);
+
+pub fn use_calc_nodes(cx: Scope) -> RwSignal<Vec<CalcNode>> {
+    use_context::<CalcNodes>(cx)
+        .expect("calculated nodes are provided by App")
+        .0
+}
+
+pub fn load_calc_nodes() -> Vec<CalcNode> {
+    local_storage_get(CALC_NODES_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn next_calc_id(calcs: &[CalcNode]) -> String {
+    let last = calcs
+        .iter()
+        .filter_map(|calc| calc.id.strip_prefix("calc-")?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    format!("calc-{}", last + 1)
+}
+
+// Node values as numbers, on/off states as 1 and 0
+fn node_number(node: &RapiStruct) -> Result<f64, String> {
+    let value = node.node_val.trim();
+    match value.to_lowercase().as_str() {
+        "true" | "on" => Ok(1.0),
+        "false" | "off" => Ok(0.0),
+        _ => value.parse::<f64>().map_err(|_| {
+            format!(
+                "the value of {} is not a number: \"{value}\"",
+                node.node_uid
+            )
+        }),
+    }
+}
+
+// "0.30000000000000004" -> "0.3"
+fn format_calc_value(value: f64) -> String {
+    let rounded = (value * 1e6).round() / 1e6;
+    format!("{rounded}")
+}
+
+// The value or error of every calculated node of a site, by id
+fn evaluate_calc_nodes(
+    calcs: &[CalcNode],
+    nodes: &[RapiStruct],
+) -> BTreeMap<String, Result<f64, String>> {
+    let mut results = BTreeMap::new();
+    let mut exprs = BTreeMap::new();
+    for calc in calcs {
+        match expr::parse(&calc.expression) {
+            Ok(parsed) => {
+                exprs.insert(calc.id.clone(), parsed);
+            }
+            Err(err) => {
+                results.insert(calc.id.clone(), Err(err.to_string()));
+            }
+        }
+    }
+    let value_of = |uid: &str| -> Result<f64, String> {
+        if calcs.iter().any(|calc| calc.id == uid) {
+            return Err(format!("{uid} has an error"));
+        }
+        nodes
+            .iter()
+            .find(|node| node.node_uid == uid)
+            .map(node_number)
+            .unwrap_or_else(|| Err(format!("unknown node {uid}")))
+    };
+    results.extend(expr::evaluate_all(&exprs, &value_of));
+    results
+}
+
+// Reasons a calculated node cannot be saved: syntax errors, references to
+// nodes that do not exist and cycles. Errors of the values themselves
+// (division by zero, a node that is not a number) do not block it.
+fn calc_problems(draft: &CalcNode, calcs: &[CalcNode], nodes: &[RapiStruct]) -> Vec<String> {
+    if draft.name.trim().is_empty() {
+        return vec!["The name is required".to_string()];
+    }
+    let parsed = match expr::parse(&draft.expression) {
+        Ok(parsed) => parsed,
+        Err(err) => return vec![err.to_string()],
+    };
+    let mut problems: Vec<String> = parsed
+        .refs()
+        .into_iter()
+        .filter(|uid| {
+            !nodes.iter().any(|node| &node.node_uid == uid)
+                && !calcs
+                    .iter()
+                    .any(|calc| &calc.id == uid && calc.id != draft.id)
+                && *uid != draft.id
+        })
+        .map(|uid| format!("Unknown node {{{uid}}}"))
+        .collect();
+
+    let mut with_draft: Vec<CalcNode> = calcs
+        .iter()
+        .filter(|calc| calc.id != draft.id)
+        .cloned()
+        .collect();
+    with_draft.push(draft.clone());
+    if let Some(Err(err)) = evaluate_calc_nodes(&with_draft, nodes).remove(&draft.id) {
+        if err.starts_with("cycle") {
+            problems.push(format!("The expression refers to itself, {err}"));
+        }
+    }
+    problems
+}
+
+// The text typed after an unclosed "{" left of the caret, with the position of the "{"
+fn pending_reference(before_caret: &str) -> Option<(usize, String)> {
+    let brace = before_caret.rfind('{')?;
+    let typed = &before_caret[brace + 1..];
+    (!typed.contains('}')).then(|| (brace, typed.trim().to_lowercase()))
+}
+
+// Value, expression, dependencies and errors of the calculated nodes of the
+// active site, with an editor for engineers
+#[component]
+pub fn CalculatedNodes(cx: Scope) -> impl IntoView {
+    let cache = use_node_cache(cx);
+    let site = use_active_site(cx);
+    let calcs = use_calc_nodes(cx);
+    let role = use_role(cx);
+    let can_edit = move || role.get() >= Role::Engineer;
+
+    create_effect(cx, move |_| {
+        site.track();
+        revalidate("bucket");
+    });
+
+    let nodes = move || {
+        let key = cache_key(&site.get(), "bucket");
+        cache
+            .entries
+            .with(|entries| cached_list::<RapiStruct>(entries, &key))
+            .unwrap_or_default()
+    };
+    let site_calcs = move || {
+        calcs.with(|calcs| {
+            calcs
+                .iter()
+                .filter(|c| c.site == site.get())
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    };
+    let results = create_memo(cx, move |_| evaluate_calc_nodes(&site_calcs(), &nodes()));
+
+    // Id of the node in the editor, "" for a new one
+    let editing = create_rw_signal(cx, None::<String>);
+    let name = create_rw_signal(cx, String::new());
+    let unit = create_rw_signal(cx, String::new());
+    let description = create_rw_signal(cx, String::new());
+    let expression = create_rw_signal(cx, String::new());
+
+    let open_editor = move |calc: Option<CalcNode>| {
+        let calc = calc.unwrap_or_else(|| CalcNode {
+            id: String::new(),
+            site: site.get_untracked(),
+            name: String::new(),
+            expression: String::new(),
+            unit: String::new(),
+            description: String::new(),
+        });
+        name.set(calc.name);
+        unit.set(calc.unit);
+        description.set(calc.description);
+        expression.set(calc.expression);
+        editing.set(Some(calc.id));
+    };
+
+    let all_calcs = calcs;
+    let cards = move || {
+        let nodes = nodes();
+        let calcs = site_calcs();
+        let results = results.get();
+        let site = site.get();
+        let label = |uid: &str| -> String {
+            nodes
+                .iter()
+                .find(|node| node.node_uid == uid)
+                .map(|node| node.node_name.clone())
+                .or_else(|| {
+                    calcs
+                        .iter()
+                        .find(|calc| calc.id == uid)
+                        .map(|calc| calc.name.clone())
+                })
+                .unwrap_or_else(|| uid.to_string())
+        };
+        calcs
+            .iter()
+            .map(|calc| {
+                let value = match results.get(&calc.id) {
+                    Some(Ok(value)) if calc.unit.is_empty() => format_calc_value(*value),
+                    Some(Ok(value)) => format!("{} {}", format_calc_value(*value), calc.unit),
+                    _ => "-".to_string(),
+                };
+                let error = results.get(&calc.id).and_then(|result| result.clone().err());
+                let depends_on = expr::parse(&calc.expression)
+                    .map(|parsed| parsed.refs().into_iter().collect::<Vec<_>>())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|uid| {
+                        let text = format!("{} ({uid})", label(&uid));
+                        let href = if calcs.iter().any(|c| c.id == uid) {
+                            format!("#{uid}")
+                        } else {
+                            site_path(&site, &format!("/node/{uid}"))
+                        };
+                        view! { cx, <li><A class="underline" href=href>{text}</A></li> }
+                    })
+                    .collect::<Vec<_>>();
+                let used_by = calcs
+                    .iter()
+                    .filter(|other| {
+                        expr::parse(&other.expression).is_ok_and(|parsed| parsed.refs().contains(&calc.id))
+                    })
+                    .map(|other| other.name.clone())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let edit = calc.clone();
+                let id = calc.id.clone();
+                view! { cx,
+                    <div id=calc.id.clone() class="calc_node m-2 p-5 rounded overflow-hidden shadow-lg border dark:text-gray-300">
+                        <h3 class="font-bold text-lg">{calc.name.clone()}</h3>
+                        <p class="italic mb-2">{calc.description.clone()}</p>
+                        <ShowSingleDataSpan row_desc="Uid: ".to_string() data_to_show=calc.id.clone()/>
+                        <ShowSingleDataSpan row_desc="Value: ".to_string() data_to_show=value/>
+                        <p class="font-mono text-sm my-2">{calc.expression.clone()}</p>
+                        {error.map(|error| view! { cx, <p class="calc_error text-red-600">{error}</p> })}
+                        <h4 class="font-bold mt-2">"Depends on"</h4>
+                        <ul class="calc_dependencies list-disc ml-6">{depends_on}</ul>
+                        {(!used_by.is_empty()).then(|| view! { cx, <p class="mt-2">{format!("Used by: {used_by}")}</p> })}
+                        <Show when=can_edit fallback=|_| ()>
+                            <button
+                            class="underline hover:text-gray-500 mt-2 mr-4"
+                            on:click={
+                                let edit = edit.clone();
+                                move |_| open_editor(Some(edit.clone()))
+                            }
+                            >
+                            "Edit"
+                            </button>
+                            <button
+                            class="calc_delete underline hover:text-gray-500 mt-2"
+                            on:click={
+                                let id = id.clone();
+                                move |_| all_calcs.update(|calcs| calcs.retain(|calc| calc.id != id))
+                            }
+                            >
+                            "Delete"
+                            </button>
+                        </Show>
+                    </div>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    view! { cx,
+        <div class="m-5 dark:text-gray-300">
+            <h2 class="font-bold text-xl mb-2">"Calculated nodes"</h2>
+            <p class="text-sm text-gray-500 mb-4">
+                "Values computed in the browser from other nodes of this site. They are not stored on the backend."
+            </p>
+            <Show when=move || can_edit() && editing.with(|e| e.is_none()) fallback=|_| ()>
+                <button
+                class="calc_new bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-2 px-4 rounded mb-4"
+                on:click=move |_| open_editor(None)
+                >
+                "New calculated node"
+                </button>
+            </Show>
+            <Show when=move || editing.with(|e| e.is_some()) fallback=|_| ()>
+                <CalcNodeEditor editing name unit description expression nodes=Signal::derive(cx, nodes)/>
+            </Show>
+            <Show when=move || site_calcs().is_empty() fallback=|_| ()>
+                <p>"No calculated nodes on this site yet."</p>
+            </Show>
+            <div class="grid grid-cols-1 lg:grid-cols-2">{cards}</div>
+        </div>
+    }
+}
+
+// Name, unit, description and expression of a calculated node, with completion
+// of node references and a live check of the expression
+#[component]
+fn CalcNodeEditor(
+    cx: Scope,
+    editing: RwSignal<Option<String>>,
+    name: RwSignal<String>,
+    unit: RwSignal<String>,
+    description: RwSignal<String>,
+    expression: RwSignal<String>,
+    nodes: Signal<Vec<RapiStruct>>,
+) -> impl IntoView {
+    let calcs = use_calc_nodes(cx);
+    let site = use_active_site(cx);
+    let textarea: NodeRef<Textarea> = create_node_ref(cx);
+    // Text left of the caret, drives the suggestions
+    let before_caret = create_rw_signal(cx, String::new());
+
+    let draft = move || CalcNode {
+        id: editing
+            .get()
+            .filter(|id| !id.is_empty())
+            .unwrap_or_default(),
+        site: site.get(),
+        name: name.get(),
+        expression: expression.get(),
+        unit: unit.get(),
+        description: description.get(),
+    };
+    let site_calcs = move || {
+        calcs.with(|calcs| {
+            calcs
+                .iter()
+                .filter(|c| c.site == site.get())
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    };
+    let problems = move || calc_problems(&draft(), &site_calcs(), &nodes.get());
+    let preview = move || {
+        let mut draft = draft();
+        if draft.id.is_empty() {
+            draft.id = next_calc_id(&calcs.get());
+        }
+        let mut with_draft: Vec<CalcNode> = site_calcs()
+            .into_iter()
+            .filter(|c| c.id != draft.id)
+            .collect();
+        with_draft.push(draft.clone());
+        match evaluate_calc_nodes(&with_draft, &nodes.get()).remove(&draft.id) {
+            Some(Ok(value)) => format!("Current value: {}", format_calc_value(value)),
+            Some(Err(err)) => format!("Current value: error, {err}"),
+            None => String::new(),
+        }
+    };
+
+    let suggestions = move || {
+        let (_, typed) = pending_reference(&before_caret.get())?;
+        let own_id = editing.get().unwrap_or_default();
+        let mut found: Vec<(String, String)> = nodes
+            .get()
+            .into_iter()
+            .map(|node| (node.node_uid, node.node_name))
+            .chain(
+                site_calcs()
+                    .into_iter()
+                    .filter(|c| c.id != own_id)
+                    .map(|c| (c.id, c.name)),
+            )
+            .filter(|(uid, name)| {
+                uid.to_lowercase().contains(&typed) || name.to_lowercase().contains(&typed)
+            })
+            .collect();
+        found.truncate(CALC_SUGGESTIONS);
+        Some(found)
+    };
+
+    let track_caret = move || {
+        if let Some(area) = textarea.get() {
+            let value = area.value();
+            let caret = area
+                .selection_start()
+                .ok()
+                .flatten()
+                .unwrap_or(value.len() as u32) as usize;
+            let before: Vec<u16> = value.encode_utf16().take(caret).collect();
+            before_caret.set(String::from_utf16_lossy(&before));
+            expression.set(value);
+        }
+    };
+
+    // Replaces the typed "{abc" with "{uid}" and puts the caret behind it
+    let complete = move |uid: String| {
+        let Some(area) = textarea.get() else {
+            return;
+        };
+        let before = before_caret.get_untracked();
+        let Some((brace, _)) = pending_reference(&before) else {
+            return;
+        };
+        let value = area.value();
+        let rest = value.get(before.len()..).unwrap_or_default();
+        // Skip a "}" the user already typed
+        let rest = rest.strip_prefix('}').unwrap_or(rest);
+        let inserted = format!("{}{{{uid}}}", &before[..brace]);
+        area.set_value(&format!("{inserted}{rest}"));
+        let caret = inserted.encode_utf16().count() as u32;
+        let _ = area.set_selection_range(caret, caret);
+        let _ = area.focus();
+        before_caret.set(String::new());
+        expression.set(area.value());
+    };
+
+    let on_save = move |_| {
+        if !problems().is_empty() {
+            return;
+        }
+        let mut calc = draft();
+        calc.name = calc.name.trim().to_string();
+        calc.expression = calc.expression.trim().to_string();
+        calcs.update(|calcs| {
+            if calc.id.is_empty() {
+                calc.id = next_calc_id(calcs);
+                calcs.push(calc);
+            } else if let Some(existing) = calcs.iter_mut().find(|c| c.id == calc.id) {
+                *existing = calc;
+            }
+        });
+        editing.set(None);
+    };
+
+    view! { cx,
+        <div class="calc_editor m-2 p-5 max-w-2xl rounded overflow-hidden shadow-lg border">
+            <WizardInput label="Name: " value=name/>
+            <WizardInput label="Unit: " value=unit/>
+            <WizardInput label="Description: " value=description/>
+            <label class="block font-bold mb-1">"Expression"</label>
+            <p class="text-sm text-gray-500 mb-2">
+                "Node uids in braces, e.g. ({a} + {b}) * 0.001 or {pump_1} && {pump_2}. Type { to search nodes by name or uid. "
+                "Functions: abs, min, max, sum, avg, round, sqrt, if(condition, then, else)."
+            </p>
+            <textarea
+            class="calc_expression font-mono dark:bg-gray-700 border-2 border-gray-200 rounded w-full py-2 px-4 dark:text-gray-300"
+            rows="3"
+            node_ref=textarea
+            prop:value=move || expression.get()
+            on:input=move |_| track_caret()
+            on:click=move |_| track_caret()
+            on:keyup=move |_| track_caret()
+            ></textarea>
+            {move || suggestions().map(|found| view! { cx,
+                <ul class="calc_suggestions border rounded mb-2">
+                    {found.into_iter().map(|(uid, label)| {
+                        let text = format!("{label} ({uid})");
+                        view! { cx,
+                            <li>
+                                <button
+                                class="calc_suggestion w-full text-left px-2 hover:bg-gray-300 dark:hover:bg-gray-600"
+                                on:click=move |_| complete(uid.clone())
+                                >
+                                {text}
+                                </button>
+                            </li>
+                        }
+                    }).collect::<Vec<_>>()}
+                </ul>
+            })}
+            <ul class="calc_problems text-red-600 mb-2">
+                {move || problems().into_iter().map(|problem| view! { cx, <li>{problem}</li> }).collect::<Vec<_>>()}
+            </ul>
+            <p class="calc_preview mb-2">{preview}</p>
+            <button
+            class="calc_save bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-2 px-4 rounded mr-2 disabled:opacity-50"
+            prop:disabled=move || !problems().is_empty()
+            on:click=on_save
+            >
+            "Save"
+            </button>
+            <button
+            class="bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-2 px-4 rounded"
+            on:click=move |_| editing.set(None)
+            >
+            "Cancel"
+            </button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit_tests::test_nodes;
+
+    #[test]
+    fn calculated_nodes_evaluate_and_report_problems() {
+        let nodes = test_nodes();
+        let calc = |id: &str, expression: &str| CalcNode {
+            id: id.to_string(),
+            site: "demo".to_string(),
+            name: id.to_string(),
+            expression: expression.to_string(),
+            unit: String::new(),
+            description: String::new(),
+        };
+        let calcs = vec![
+            calc("calc-1", "{uid-1} * 2 + {uid-2}"),
+            calc("calc-2", "{calc-1} + {gone}"),
+        ];
+
+        let results = evaluate_calc_nodes(&calcs, &nodes);
+        assert_eq!(
+            format_calc_value(*results["calc-1"].as_ref().unwrap()),
+            "43.8"
+        );
+        assert_eq!(results["calc-2"], Err("unknown node gone".to_string()));
+        assert_eq!(
+            calc_problems(&calcs[1], &calcs, &nodes),
+            ["Unknown node {gone}"]
+        );
+        assert!(
+            calc_problems(&calc("calc-1", "{calc-2} - 1"), &calcs, &nodes)
+                .iter()
+                .any(|problem| problem.starts_with("The expression refers to itself, cycle"))
+        );
+        assert_eq!(next_calc_id(&calcs), "calc-3");
+        assert_eq!(pending_reference("abs({Uid"), Some((4, "uid".to_string())));
+        assert_eq!(pending_reference("{uid-1} + 1"), None);
+    }
+}
@@ -0,0 +1,832 @@
+// User dashboards: widgets pinned to rAPI nodes of one site on a 12 column grid,
+// kept in the browser and exported/imported as JSON.
+
+use std::collections::HashMap;
+
+use leptos::ev::SubmitEvent;
+use leptos::html::{Input, Select};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+use crate::alarms::ALARM_POLL_SECS;
+use crate::{
+    active_backend, audited_post_now, cache_key, fetch_node, local_storage_get, rapi_to_map,
+    revalidate_backend, schema, site_path, unsent_reason, use_active_site, use_capability,
+    use_node_cache, use_role, use_ui_settings, AuditAction, AuditTarget, Backend, FormInputCust,
+    FormSubmitButton, RapiStruct, RequiresRole, Role,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WidgetKind {
+    Value,
+    Gauge,
+    OnOff,
+    Sparkline,
+    Setpoint,
+}
+
+impl WidgetKind {
+    const ALL: [WidgetKind; 5] = [
+        WidgetKind::Value,
+        WidgetKind::Gauge,
+        WidgetKind::OnOff,
+        WidgetKind::Sparkline,
+        WidgetKind::Setpoint,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            WidgetKind::Value => "Value tile",
+            WidgetKind::Gauge => "Gauge",
+            WidgetKind::OnOff => "On/off indicator",
+            WidgetKind::Sparkline => "Sparkline",
+            WidgetKind::Setpoint => "Setpoint input",
+        }
+    }
+
+    fn from_label(label: &str) -> Self {
+        WidgetKind::ALL
+            .into_iter()
+            .find(|kind| kind.label() == label)
+            .unwrap_or(WidgetKind::Value)
+    }
+}
+
+// A widget pinned to a rAPI node, placed on a 12 column grid
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DashboardWidget {
+    pub kind: WidgetKind,
+    pub node_uid: String,
+    pub title: String,
+    pub col: u32,
+    pub row: u32,
+    pub width: u32,
+    pub height: u32,
+    // Range used by the gauge
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserDashboard {
+    pub id: u32,
+    pub name: String,
+    // Backend id the widgets read from and write to, boards saved before
+    // this existed are pinned to the active site when loaded
+    #[serde(default)]
+    pub site: String,
+    pub widgets: Vec<DashboardWidget>,
+}
+
+impl UserDashboard {
+    // First row below every widget, new widgets go there
+    fn next_free_row(&self) -> u32 {
+        self.widgets
+            .iter()
+            .map(|w| w.row + w.height)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+const DASHBOARD_COLUMNS: u32 = 12;
+const DASHBOARD_MAX_HEIGHT: u32 = 12;
+pub const USER_DASHBOARDS_KEY: &str = "dcui_dashboards";
+const SPARKLINE_SAMPLES: usize = 60;
+
+pub fn load_user_dashboards() -> Vec<UserDashboard> {
+    let mut boards: Vec<UserDashboard> = local_storage_get(USER_DASHBOARDS_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    for board in boards.iter_mut().filter(|b| b.site.is_empty()) {
+        board.site = active_backend().id;
+    }
+    boards
+}
+
+fn use_user_dashboards(cx: Scope) -> RwSignal<Vec<UserDashboard>> {
+    use_context::<RwSignal<Vec<UserDashboard>>>(cx).expect("dashboards to be provided by App")
+}
+
+// Accepts either a single exported dashboard or a list of them. Widgets are
+// clamped onto the grid, a gauge range with min above max is rejected.
+fn parse_dashboard_import(raw: &str) -> Result<Vec<UserDashboard>, String> {
+    let mut boards = serde_json::from_str::<Vec<UserDashboard>>(raw)
+        .or_else(|_| serde_json::from_str::<UserDashboard>(raw).map(|board| vec![board]))
+        .map_err(|err| err.to_string())?;
+    for board in boards.iter_mut() {
+        for widget in board.widgets.iter_mut() {
+            if widget.min > widget.max || widget.min.is_nan() || widget.max.is_nan() {
+                return Err(format!(
+                    "widget {} on {} has min {} above max {}",
+                    widget.node_uid, board.name, widget.min, widget.max
+                ));
+            }
+            widget.width = widget.width.clamp(1, DASHBOARD_COLUMNS);
+            widget.col = widget.col.min(DASHBOARD_COLUMNS - widget.width);
+            widget.height = widget.height.clamp(1, DASHBOARD_MAX_HEIGHT);
+        }
+    }
+    Ok(boards)
+}
+
+pub fn node_is_on(value: &str) -> bool {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "on" => true,
+        other => other.parse::<f64>().map(|v| v != 0.0).unwrap_or(false),
+    }
+}
+
+// Writes a value from a widget. The node is read again first, a widget's copy
+// may miss metadata or alarm limits changed since it was polled.
+pub async fn write_node_value(backend: &Backend, uid: &str, value: String) -> Result<(), String> {
+    let node = fetch_node(backend, uid).await?;
+    let mut map = rapi_to_map(&node);
+    map.insert("node_val", value);
+
+    let resp = audited_post_now(
+        &format!("{}/u", backend.url),
+        map,
+        AuditAction::Update,
+        AuditTarget::RapiNode,
+        serde_json::to_value(&node).ok(),
+    )
+    .await;
+    match resp.as_str() {
+        "200" => Ok(()),
+        _ => Err(unsent_reason(&resp)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WidgetDrag {
+    Move(usize),
+    Resize(usize),
+}
+
+#[component]
+pub fn UserDashboardList(cx: Scope) -> impl IntoView {
+    let dashboards = use_user_dashboards(cx);
+    let site = use_active_site(cx);
+    let input_element_name: NodeRef<Input> = create_node_ref(cx);
+    let (import_text, set_import_text) = create_signal(cx, String::new());
+    let (import_msg, set_import_msg) = create_signal(cx, String::new());
+
+    let on_create = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let input = input_element_name.get().expect("<input> to exist");
+        let name = input.value().trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        dashboards.update(|boards| {
+            let id = boards.iter().map(|b| b.id + 1).max().unwrap_or(1);
+            boards.push(UserDashboard {
+                id,
+                name,
+                site: site.get_untracked(),
+                widgets: Vec::new(),
+            });
+        });
+        input.set_value("");
+    };
+
+    let on_import = move |_| {
+        match parse_dashboard_import(&import_text.get()) {
+            Ok(imported) => {
+                let count = imported.len();
+                dashboards.update(|boards| {
+                    for mut board in imported {
+                        if board.site.is_empty() {
+                            board.site = site.get_untracked();
+                        }
+                        // Same name on the same site replaces the old layout, everything else is added
+                        if let Some(existing) = boards
+                            .iter_mut()
+                            .find(|b| b.name == board.name && b.site == board.site)
+                        {
+                            existing.widgets = board.widgets;
+                        } else {
+                            board.id = boards.iter().map(|b| b.id + 1).max().unwrap_or(1);
+                            boards.push(board);
+                        }
+                    }
+                });
+                set_import_text.set(String::new());
+                set_import_msg.set(format!("Imported {count} dashboard(s)"));
+            }
+            Err(err) => set_import_msg.set(format!("Invalid dashboard: {err}")),
+        }
+    };
+
+    let export_href = move || {
+        let raw =
+            dashboards.with(|boards| serde_json::to_string_pretty(boards).unwrap_or_default());
+        format!(
+            "data:application/json;charset=utf-8,{}",
+            js_sys::encode_uri_component(&raw)
+        )
+    };
+
+    view! { cx,
+        <div class="user_dashboards m-5 dark:text-gray-300">
+            <div class="p-5 max-w-lg rounded overflow-hidden shadow-lg border">
+                <h3 class="font-bold text-lg mb-4">"Dashboards"</h3>
+                <For
+                    each=move || {
+                        let site = site.get();
+                        dashboards.with(|boards| boards.iter().filter(|b| b.site == site).cloned().collect::<Vec<_>>())
+                    }
+                    key=|board| board.id
+                    view=move |cx, board| {
+                        let id = board.id;
+                        view! { cx,
+                            <div class="flex items-center mb-2">
+                                <A class="underline hover:text-gray-500" href=site_path(&board.site, &format!("/boards/{id}"))>{board.name}</A>
+                                <span class="ml-2 text-sm">{format!("({} widgets)", board.widgets.len())}</span>
+                                <RequiresRole role=Role::Engineer>
+                                    <button
+                                    class="dashboard_delete ml-auto bg-red-600 text-gray-200 hover:bg-red-900 font-bold py-1 px-2 rounded"
+                                    on:click=move |_| dashboards.update(|boards| boards.retain(|b| b.id != id))
+                                    >
+                                    "Delete"
+                                    </button>
+                                </RequiresRole>
+                            </div>
+                        }
+                    }
+                />
+                <RequiresRole role=Role::Engineer>
+                    <form class="w-full max-w-sm mt-4" on:submit=on_create>
+                        <FormInputCust node_ref_cust=input_element_name
+                        label_text="New dashboard name: ".to_string()
+                        id_name="dashboard_name_input".to_string() />
+                        <FormSubmitButton />
+                    </form>
+                </RequiresRole>
+            </div>
+
+            <div class="mt-5 p-5 max-w-lg rounded overflow-hidden shadow-lg border">
+                <h3 class="font-bold text-lg mb-4">"Export / import"</h3>
+                <a class="inline-block bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-2 px-4 rounded mb-4"
+                href=export_href download="dashboards.json">
+                "Export all as JSON"
+                </a>
+                // Importing replaces the layout of boards with the same name
+                <RequiresRole role=Role::Engineer>
+                    <textarea
+                    class="dark:bg-gray-700 border-2 border-gray-200 rounded w-full h-40 p-2 font-mono text-sm"
+                    placeholder="Paste exported dashboard JSON here"
+                    prop:value=move || import_text.get()
+                    on:input=move |ev| set_import_text.set(event_target_value(&ev))
+                    ></textarea>
+                    <button
+                    class="bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-2 px-4 rounded mt-2"
+                    on:click=on_import
+                    >
+                    "Import"
+                    </button>
+                    <p class="mt-2">{move || import_msg.get()}</p>
+                </RequiresRole>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn UserDashboardView(cx: Scope) -> impl IntoView {
+    let params = use_params_map(cx);
+    let board_id = move || {
+        params.with(|params| {
+            params
+                .get("board")
+                .and_then(|id| id.parse::<u32>().ok())
+                .unwrap_or_default()
+        })
+    };
+
+    let dashboards = use_user_dashboards(cx);
+    let board = create_memo(cx, move |_| {
+        dashboards.with(|boards| boards.iter().find(|b| b.id == board_id()).cloned())
+    });
+    let update_board = move |f: &dyn Fn(&mut UserDashboard)| {
+        dashboards.update(|boards| {
+            if let Some(board) = boards.iter_mut().find(|b| b.id == board_id()) {
+                f(board);
+            }
+        })
+    };
+
+    // The site the board belongs to, None if it was removed from the settings
+    let settings = use_ui_settings(cx);
+    let backend = create_memo(cx, move |_| {
+        let site = board.with(|b| b.as_ref().map(|b| b.site.clone()))?;
+        settings.with(|s| s.backends.iter().find(|b| b.id == site).cloned())
+    });
+
+    // Live rAPI values from the node cache of the board's site, refreshed by the
+    // same revalidation the data node pages and the alarm watcher use
+    let cache = use_node_cache(cx);
+    let raw = create_memo(cx, move |_| {
+        let key = cache_key(
+            &board.with(|b| b.as_ref().map(|b| b.site.clone()))?,
+            "bucket",
+        );
+        cache
+            .entries
+            .with(|entries| entries.get(&key).map(|cached| cached.raw.clone()))
+    });
+    let nodes = create_rw_signal(cx, HashMap::<String, RapiStruct>::new());
+    let history = create_rw_signal(cx, HashMap::<String, Vec<f64>>::new());
+    create_effect(cx, move |_| {
+        let fetched: Vec<RapiStruct> = raw
+            .with(|raw| raw.as_deref().map(|raw| schema::decode_list(raw).items))
+            .unwrap_or_default();
+        history.update(|history| {
+            for node in fetched.iter() {
+                if let Ok(value) = node.node_val.trim().parse::<f64>() {
+                    let samples = history.entry(node.node_uid.clone()).or_default();
+                    samples.push(value);
+                    if samples.len() > SPARKLINE_SAMPLES {
+                        samples.remove(0);
+                    }
+                }
+            }
+        });
+        nodes.set(
+            fetched
+                .into_iter()
+                .map(|node| (node.node_uid.clone(), node))
+                .collect(),
+        );
+    });
+    let poll = move || {
+        if let Some(backend) = backend.get_untracked() {
+            revalidate_backend(backend, "bucket");
+        }
+    };
+    poll();
+    if let Ok(handle) =
+        set_interval_with_handle(poll, std::time::Duration::from_secs(ALARM_POLL_SECS))
+    {
+        on_cleanup(cx, move || handle.clear());
+    }
+
+    // Changing the layout is for engineers, like everything that changes a board
+    let role = use_role(cx);
+    let (edit_mode, set_editing) = create_signal(cx, false);
+    let editing = Signal::derive(cx, move || edit_mode.get() && role.get() >= Role::Engineer);
+    let drag = create_rw_signal(cx, None::<WidgetDrag>);
+
+    let on_drop_cell = move |col: u32, row: u32| {
+        let Some(action) = drag.get_untracked() else {
+            return;
+        };
+        update_board(&|board| match action {
+            WidgetDrag::Move(idx) => {
+                if let Some(w) = board.widgets.get_mut(idx) {
+                    w.col = col.min(DASHBOARD_COLUMNS.saturating_sub(w.width));
+                    w.row = row;
+                }
+            }
+            WidgetDrag::Resize(idx) => {
+                if let Some(w) = board.widgets.get_mut(idx) {
+                    w.width = (col + 1).saturating_sub(w.col).max(1);
+                    w.height = (row + 1)
+                        .saturating_sub(w.row)
+                        .clamp(1, DASHBOARD_MAX_HEIGHT);
+                }
+            }
+        });
+        drag.set(None);
+    };
+
+    // Empty cells behind the widgets, only shown while editing
+    let drop_cells = move || {
+        let rows = board.with(|b| b.as_ref().map(|b| b.next_free_row()).unwrap_or(0)) + 2;
+        (0..rows)
+            .flat_map(|row| (0..DASHBOARD_COLUMNS).map(move |col| (col, row)))
+            .map(|(col, row)| {
+                view! { cx,
+                    <div
+                    class="border border-dashed border-gray-400 rounded"
+                    style=format!("grid-column: {} / span 1; grid-row: {} / span 1;", col + 1, row + 1)
+                    on:dragover=|ev: ev::DragEvent| ev.prevent_default()
+                    on:drop=move |ev: ev::DragEvent| {
+                        ev.prevent_default();
+                        on_drop_cell(col, row);
+                    }
+                    ></div>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let widgets = move || {
+        board
+            .get()
+            .map(|board| board.widgets)
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, widget)| {
+                let style = format!(
+                    "grid-column: {} / span {}; grid-row: {} / span {}; z-index: 1;",
+                    widget.col + 1,
+                    widget.width,
+                    widget.row + 1,
+                    widget.height
+                );
+                let uid = widget.node_uid.clone();
+                let node = Signal::derive(cx, move || nodes.with(|nodes| nodes.get(&uid).cloned()));
+                let uid = widget.node_uid.clone();
+                let samples = Signal::derive(cx, move || {
+                    history.with(|history| history.get(&uid).cloned().unwrap_or_default())
+                });
+
+                view! { cx,
+                    <div
+                    class="relative m-1 p-3 rounded overflow-hidden shadow-lg border dark:bg-gray-800 bg-neutral-50"
+                    style=style
+                    draggable=move || editing.get().to_string()
+                    on:dragstart=move |ev: ev::DragEvent| {
+                        if let Some(dt) = ev.data_transfer() {
+                            let _ = dt.set_data("text/plain", "widget");
+                        }
+                        drag.set(Some(WidgetDrag::Move(idx)));
+                    }
+                    >
+                        <WidgetBody widget node samples backend/>
+                        <Show when=move || editing.get() fallback=|_| ()>
+                            <button
+                            class="absolute top-1 right-1 text-red-500 font-bold"
+                            title="Remove widget"
+                            on:click=move |_| update_board(&|board| {
+                                if idx < board.widgets.len() {
+                                    board.widgets.remove(idx);
+                                }
+                            })
+                            >
+                            "x"
+                            </button>
+                            <div
+                            class="absolute bottom-0 right-0 w-4 h-4 bg-gray-500 cursor-se-resize"
+                            title="Drag onto a cell to resize"
+                            draggable="true"
+                            on:dragstart=move |ev: ev::DragEvent| {
+                                ev.stop_propagation();
+                                if let Some(dt) = ev.data_transfer() {
+                                    let _ = dt.set_data("text/plain", "resize");
+                                }
+                                drag.set(Some(WidgetDrag::Resize(idx)));
+                            }
+                            ></div>
+                        </Show>
+                    </div>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    view! { cx,
+        <div class="user_dashboard p-5 dark:text-gray-300">
+            <Show
+            when=move || board.with(|b| b.is_some())
+            fallback=|cx| view! { cx, <p>"Dashboard not found."</p> }
+            >
+                <div class="flex items-center mb-4">
+                    <h1 class="text-2xl font-bold">{move || board.get().map(|b| b.name).unwrap_or_default()}</h1>
+                    <span class="ml-4">
+                        {move || match backend.get() {
+                            Some(backend) => format!("Site: {}", backend.name),
+                            None => format!(
+                                "Site {} is not configured",
+                                board.get().map(|b| b.site).unwrap_or_default()
+                            ),
+                        }}
+                    </span>
+                    <RequiresRole role=Role::Engineer>
+                        <button
+                        class="edit_layout ml-auto bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-2 px-4 rounded"
+                        on:click=move |_| set_editing.update(|e| *e = !*e)
+                        >
+                        {move || if editing.get() { "Done" } else { "Edit layout" }}
+                        </button>
+                    </RequiresRole>
+                </div>
+                <Show when=move || editing.get() fallback=|_| ()>
+                    <NewWidgetForm nodes on_add=move |widget: DashboardWidget| update_board(&|board| {
+                        let mut widget = widget.clone();
+                        widget.row = board.next_free_row();
+                        board.widgets.push(widget);
+                    })/>
+                </Show>
+                <div class="grid grid-cols-12 gap-1" style="grid-auto-rows: 6rem;">
+                    <Show when=move || editing.get() fallback=|_| ()>
+                        {drop_cells}
+                    </Show>
+                    {widgets}
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+fn NewWidgetForm<F>(
+    cx: Scope,
+    nodes: RwSignal<HashMap<String, RapiStruct>>,
+    on_add: F,
+) -> impl IntoView
+where
+    F: Fn(DashboardWidget) + 'static,
+{
+    let select_element_kind: NodeRef<Select> = create_node_ref(cx);
+    let input_element_uid: NodeRef<Input> = create_node_ref(cx);
+    let input_element_title: NodeRef<Input> = create_node_ref(cx);
+    let input_element_min: NodeRef<Input> = create_node_ref(cx);
+    let input_element_max: NodeRef<Input> = create_node_ref(cx);
+
+    let on_submit = move |ev: SubmitEvent| {
+        ev.prevent_default();
+
+        let node_uid = input_element_uid.get().expect("<input> to exist").value();
+        if node_uid.trim().is_empty() {
+            return;
+        }
+        let kind = WidgetKind::from_label(
+            &select_element_kind
+                .get()
+                .expect("<select> to exist")
+                .value(),
+        );
+        let min = input_element_min
+            .get()
+            .expect("<input> to exist")
+            .value()
+            .parse()
+            .unwrap_or(0.0);
+        let max = input_element_max
+            .get()
+            .expect("<input> to exist")
+            .value()
+            .parse()
+            .unwrap_or(100.0);
+        if min > max {
+            return;
+        }
+
+        on_add(DashboardWidget {
+            kind,
+            node_uid: node_uid.trim().to_string(),
+            title: input_element_title.get().expect("<input> to exist").value(),
+            col: 0,
+            row: 0,
+            width: 3,
+            height: if kind == WidgetKind::Sparkline { 2 } else { 1 },
+            min,
+            max,
+        });
+    };
+
+    view! { cx,
+        <div class="new_widget_form mb-4 p-5 max-w-lg rounded overflow-hidden shadow-lg border">
+            <form class="w-full max-w-sm" on:submit=on_submit>
+                <div class="md:flex md:items-center mb-6">
+                    <div class="md:w-1/3">
+                        <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4" for="widget_kind_select">
+                            "Widget: "
+                        </label>
+                    </div>
+                    <div class="md:w-2/3">
+                        <select
+                        id="widget_kind_select"
+                        class="dark:bg-gray-700 appearance-none dark:text-gray-300 border-2 border-gray-200 rounded w-full py-2 px-4 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                        node_ref=select_element_kind
+                        >
+                        {WidgetKind::ALL.into_iter().map(|kind| view! { cx, <option value=kind.label()>{kind.label()}</option> }).collect::<Vec<_>>()}
+                        </select>
+                    </div>
+                </div>
+
+                <div class="md:flex md:items-center mb-6">
+                    <div class="md:w-1/3">
+                        <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4" for="widget_uid_input">
+                            "Data node uid: "
+                        </label>
+                    </div>
+                    <div class="md:w-2/3">
+                        <input type="text" list="widget_uid_list"
+                        id="widget_uid_input"
+                        class="dark:bg-gray-700 appearance-none border-2 border-gray-200 rounded w-full py-2 px-4 dark:text-gray-300 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                        node_ref=input_element_uid
+                        />
+                        <datalist id="widget_uid_list">
+                            {move || nodes.with(|nodes| nodes.values().map(|node| view! { cx,
+                                <option value=node.node_uid.clone()>{node.node_name.clone()}</option>
+                            }).collect::<Vec<_>>())}
+                        </datalist>
+                    </div>
+                </div>
+
+                <FormInputCust node_ref_cust=input_element_title
+                label_text="Title: ".to_string()
+                id_name="widget_title_input".to_string() />
+
+                <FormInputCust node_ref_cust=input_element_min
+                label_text="Gauge min: ".to_string()
+                id_name="widget_min_input".to_string() />
+
+                <FormInputCust node_ref_cust=input_element_max
+                label_text="Gauge max: ".to_string()
+                id_name="widget_max_input".to_string() />
+
+                <FormSubmitButton />
+            </form>
+        </div>
+    }
+}
+
+#[component]
+fn WidgetBody(
+    cx: Scope,
+    widget: DashboardWidget,
+    node: Signal<Option<RapiStruct>>,
+    samples: Signal<Vec<f64>>,
+    #[prop(into)] backend: Signal<Option<Backend>>,
+) -> impl IntoView {
+    let title = if widget.title.is_empty() {
+        widget.node_uid.clone()
+    } else {
+        widget.title.clone()
+    };
+    let value = move || {
+        node.get()
+            .map(|n| n.node_val)
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    let body = match widget.kind {
+        WidgetKind::Value => view! { cx,
+            <span class="block text-3xl font-bold">{value}</span>
+        }
+        .into_view(cx),
+        WidgetKind::Gauge => {
+            let (min, max) = (widget.min, widget.max);
+            let dash = move || {
+                let v = node
+                    .get()
+                    .and_then(|n| n.node_val.trim().parse::<f64>().ok())
+                    .unwrap_or(min);
+                let ratio = if max > min {
+                    ((v - min) / (max - min)).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                // Half circle with r=40 is ~125.66 long
+                format!("{} 200", ratio * 125.66)
+            };
+            view! { cx,
+                <svg viewBox="0 0 100 55" class="w-full h-16">
+                    <path d="M10 50 A40 40 0 0 1 90 50" fill="none" stroke="#9ca3af" stroke-width="8"/>
+                    <path d="M10 50 A40 40 0 0 1 90 50" fill="none" stroke="#2563eb" stroke-width="8" stroke-dasharray=dash/>
+                    <text x="50" y="48" text-anchor="middle" font-size="14" fill="currentColor">{value}</text>
+                </svg>
+            }
+            .into_view(cx)
+        }
+        WidgetKind::OnOff => {
+            let on = move || node.get().map(|n| node_is_on(&n.node_val)).unwrap_or(false);
+            view! { cx,
+                <div class="flex items-center">
+                    <span class="inline-block w-8 h-8 rounded-full bg-gray-400" class:bg-green-500=on></span>
+                    <span class="ml-3 text-xl font-bold">{move || if on() { "ON" } else { "OFF" }}</span>
+                </div>
+            }
+            .into_view(cx)
+        }
+        WidgetKind::Sparkline => {
+            let points = move || {
+                let samples = samples.get();
+                let lo = samples.iter().cloned().fold(f64::INFINITY, f64::min);
+                let hi = samples.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                let span = if hi > lo { hi - lo } else { 1.0 };
+                let step = 100.0 / (SPARKLINE_SAMPLES - 1) as f64;
+                samples
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        format!(
+                            "{:.1},{:.1}",
+                            i as f64 * step,
+                            38.0 - (v - lo) / span * 36.0
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            view! { cx,
+                <span class="block text-xl font-bold">{value}</span>
+                <svg viewBox="0 0 100 40" preserveAspectRatio="none" class="w-full h-24">
+                    <polyline points=points fill="none" stroke="#2563eb" stroke-width="1.5"/>
+                </svg>
+            }
+            .into_view(cx)
+        }
+        WidgetKind::Setpoint => {
+            let input_element_setpoint: NodeRef<Input> = create_node_ref(cx);
+            let (status, set_status) = create_signal(cx, String::new());
+            let role = use_role(cx);
+            let can_write = use_capability(cx, "write");
+            let writable = move || {
+                role.get() >= Role::Operator
+                    && can_write.get()
+                    && backend.with(|b| b.is_some())
+                    && node
+                        .get()
+                        .map(|n| n.node_rw_direction.contains('w'))
+                        .unwrap_or(false)
+            };
+            let on_submit = move |ev: SubmitEvent| {
+                ev.prevent_default();
+                let Some(current) = node.get_untracked() else {
+                    return;
+                };
+                let Some(backend) = backend.get_untracked() else {
+                    return;
+                };
+                if !writable() {
+                    return;
+                }
+                let value = input_element_setpoint
+                    .get()
+                    .expect("<input> to exist")
+                    .value();
+                spawn_local(async move {
+                    set_status.set(
+                        match write_node_value(&backend, &current.node_uid, value).await {
+                            Ok(()) => "Written".to_string(),
+                            Err(err) => err,
+                        },
+                    );
+                });
+            };
+            view! { cx,
+                <span class="block text-sm">"Current: "{value}</span>
+                <form class="flex items-center" on:submit=on_submit>
+                    <input type="text"
+                    class="dark:bg-gray-700 border-2 border-gray-200 rounded w-24 py-1 px-2"
+                    node_ref=input_element_setpoint
+                    prop:disabled=move || !writable()
+                    />
+                    <input type="submit" value="Set"
+                    class="ml-2 bg-green-500 hover:bg-green-400 text-white font-bold py-1 px-2 rounded"
+                    prop:disabled=move || !writable()
+                    />
+                </form>
+                <span class="block text-sm">{move || status.get()}</span>
+            }
+            .into_view(cx)
+        }
+    };
+
+    view! { cx,
+        <span class="block text-sm font-medium uppercase truncate" title=widget.node_uid.clone()>{title}</span>
+        {body}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dashboard_import_clamps_widgets_onto_the_grid() {
+        let raw = r#"{"id":1,"name":"Hall","widgets":[
+            {"kind":"Value","node_uid":"a","title":"","col":20,"row":0,"width":40,"height":0,"min":0.0,"max":1.0},
+            {"kind":"Gauge","node_uid":"b","title":"","col":11,"row":1,"width":3,"height":99,"min":0.0,"max":100.0}
+        ]}"#;
+        let boards = parse_dashboard_import(raw).unwrap();
+        assert_eq!(boards[0].site, "");
+        let wide = &boards[0].widgets[0];
+        assert_eq!(
+            (wide.col, wide.width, wide.height),
+            (0, DASHBOARD_COLUMNS, 1)
+        );
+        let tall = &boards[0].widgets[1];
+        assert_eq!(
+            (tall.col, tall.width, tall.height),
+            (9, 3, DASHBOARD_MAX_HEIGHT)
+        );
+    }
+
+    #[test]
+    fn dashboard_import_rejects_inverted_gauge_range() {
+        let raw = r#"[{"id":1,"name":"Hall","site":"local","widgets":[
+            {"kind":"Gauge","node_uid":"b","title":"","col":0,"row":0,"width":3,"height":1,"min":10.0,"max":5.0}
+        ]}]"#;
+        let err = parse_dashboard_import(raw).unwrap_err();
+        assert!(err.contains("min 10 above max 5"), "{err}");
+    }
+}
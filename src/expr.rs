@@ -0,0 +1,543 @@
+// Expressions of calculated nodes, e.g. `({uid-a} + {uid-b}) * 0.001` or
+// `{pump_1} && {pump_2}`. Node values are referenced by uid in braces, booleans
+// are 1 and 0. Supported: numbers, true/false, + - * / %, comparisons,
+// && || !, parentheses and the functions below.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+const FUNCTIONS: &[&str] = &["abs", "min", "max", "sum", "avg", "round", "sqrt", "if"];
+// Parentheses, signs and function calls nest at most this deep, the parser
+// recurses on each level and would otherwise run out of stack
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    // A node uid
+    Ref(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+// `at` is the character offset the parser stopped at
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub at: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (column {})", self.message, self.at + 1)
+    }
+}
+
+pub fn parse(source: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser {
+        chars: source.chars().collect(),
+        pos: 0,
+        depth: 0,
+    };
+    let expr = parser.or()?;
+    parser.skip_space();
+    match parser.peek() {
+        None => Ok(expr),
+        Some(c) => Err(parser.error(format!("unexpected '{c}'"))),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+// One method per precedence level, lowest first
+impl Parser {
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            at: self.pos,
+            message,
+        }
+    }
+
+    // Runs `inner` one nesting level deeper
+    fn nested(&mut self, inner: fn(&mut Self) -> Result<Expr, ParseError>) -> Result<Expr, ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(format!("nested more than {MAX_DEPTH} levels deep")));
+        }
+        self.depth += 1;
+        let result = inner(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // Consumes `token` if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        let matches = token
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c));
+        if matches {
+            self.pos += token.chars().count();
+        }
+        matches
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        next: fn(&mut Self) -> Result<Expr, ParseError>,
+    ) -> Result<Expr, ParseError> {
+        let mut left = next(self)?;
+        'outer: loop {
+            for (token, op) in ops {
+                if self.eat(token) {
+                    let right = next(self)?;
+                    left = Expr::Binary(*op, Box::new(left), Box::new(right));
+                    continue 'outer;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        self.binary(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        // Two character operators before their one character prefixes
+        let ops = [
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<=", BinaryOp::Le),
+            (">=", BinaryOp::Ge),
+            ("<", BinaryOp::Lt),
+            (">", BinaryOp::Gt),
+        ];
+        self.binary(&ops, Self::sum)
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let ops = [
+            ("*", BinaryOp::Mul),
+            ("/", BinaryOp::Div),
+            ("%", BinaryOp::Rem),
+        ];
+        self.binary(&ops, Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.nested(Self::unary)?)));
+        }
+        // `!=` is never at the start of an operand
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.nested(Self::unary)?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        self.skip_space();
+        let start = self.pos;
+        match self.peek() {
+            None => Err(self.error("expression ends too early".to_string())),
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.nested(Self::or)?;
+                if !self.eat(")") {
+                    return Err(self.error("missing ')'".to_string()));
+                }
+                Ok(inner)
+            }
+            Some('{') => {
+                self.pos += 1;
+                let uid: String = self.take_while(|c| c != '}' && c != '{');
+                if self.peek() != Some('}') {
+                    return Err(ParseError {
+                        at: start,
+                        message: "missing '}' after the node uid".to_string(),
+                    });
+                }
+                self.pos += 1;
+                let uid = uid.trim().to_string();
+                if uid.is_empty() {
+                    return Err(ParseError {
+                        at: start,
+                        message: "empty node reference".to_string(),
+                    });
+                }
+                Ok(Expr::Ref(uid))
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                number.parse().map(Expr::Number).map_err(|_| ParseError {
+                    at: start,
+                    message: format!("'{number}' is not a number"),
+                })
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                match name.as_str() {
+                    "true" => return Ok(Expr::Number(1.0)),
+                    "false" => return Ok(Expr::Number(0.0)),
+                    _ => {}
+                }
+                if !FUNCTIONS.contains(&name.as_str()) {
+                    return Err(ParseError {
+                        at: start,
+                        message: format!("unknown function '{name}', node uids go in braces"),
+                    });
+                }
+                if !self.eat("(") {
+                    return Err(self.error(format!("'(' expected after {name}")));
+                }
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.nested(Self::or)?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return Err(self.error("',' or ')' expected".to_string()));
+                        }
+                    }
+                }
+                check_arity(&name, args.len())
+                    .map_err(|message| ParseError { at: start, message })?;
+                Ok(Expr::Call(name, args))
+            }
+            Some(c) => Err(self.error(format!("unexpected '{c}'"))),
+        }
+    }
+
+    fn take_while(&mut self, keep: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&keep) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
+
+fn check_arity(name: &str, count: usize) -> Result<(), String> {
+    let ok = match name {
+        "abs" | "round" | "sqrt" => count == 1,
+        "if" => count == 3,
+        _ => count >= 1,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("wrong number of arguments for {name}"))
+    }
+}
+
+fn truth(value: f64) -> bool {
+    value != 0.0
+}
+
+fn flag(value: bool) -> f64 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+impl Expr {
+    // Every node uid the expression reads
+    pub fn refs(&self) -> BTreeSet<String> {
+        let mut refs = BTreeSet::new();
+        self.collect_refs(&mut refs);
+        refs
+    }
+
+    fn collect_refs(&self, refs: &mut BTreeSet<String>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Ref(uid) => {
+                refs.insert(uid.clone());
+            }
+            Expr::Neg(inner) | Expr::Not(inner) => inner.collect_refs(refs),
+            Expr::Binary(_, left, right) => {
+                left.collect_refs(refs);
+                right.collect_refs(refs);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.collect_refs(refs)),
+        }
+    }
+
+    pub fn eval(&self, lookup: &mut dyn FnMut(&str) -> Result<f64, String>) -> Result<f64, String> {
+        let value = match self {
+            Expr::Number(value) => *value,
+            Expr::Ref(uid) => lookup(uid)?,
+            Expr::Neg(inner) => -inner.eval(lookup)?,
+            Expr::Not(inner) => flag(!truth(inner.eval(lookup)?)),
+            // Only the taken branch is evaluated, so `if` can guard a division
+            Expr::Call(name, args) if name == "if" => {
+                if truth(args[0].eval(lookup)?) {
+                    args[1].eval(lookup)?
+                } else {
+                    args[2].eval(lookup)?
+                }
+            }
+            Expr::Call(name, args) => {
+                let values = args
+                    .iter()
+                    .map(|arg| arg.eval(lookup))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                match name.as_str() {
+                    "abs" => values[0].abs(),
+                    "round" => values[0].round(),
+                    "sqrt" => values[0].sqrt(),
+                    "min" => values.iter().copied().fold(f64::INFINITY, f64::min),
+                    "max" => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                    "sum" => values.iter().sum(),
+                    "avg" => values.iter().sum::<f64>() / values.len() as f64,
+                    _ => return Err(format!("unknown function '{name}'")),
+                }
+            }
+            Expr::Binary(op, left, right) => {
+                let left = left.eval(lookup)?;
+                let right = right.eval(lookup)?;
+                match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Sub => left - right,
+                    BinaryOp::Mul => left * right,
+                    BinaryOp::Div | BinaryOp::Rem if right == 0.0 => {
+                        return Err("division by zero".to_string())
+                    }
+                    BinaryOp::Div => left / right,
+                    BinaryOp::Rem => left % right,
+                    BinaryOp::Eq => flag(left == right),
+                    BinaryOp::Ne => flag(left != right),
+                    BinaryOp::Lt => flag(left < right),
+                    BinaryOp::Le => flag(left <= right),
+                    BinaryOp::Gt => flag(left > right),
+                    BinaryOp::Ge => flag(left >= right),
+                    BinaryOp::And => flag(truth(left) && truth(right)),
+                    BinaryOp::Or => flag(truth(left) || truth(right)),
+                }
+            }
+        };
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err("the result is not a finite number".to_string())
+        }
+    }
+}
+
+// Evaluates expressions that may read each other. Uids without an expression
+// are read with `value_of`. A cycle or a failing input fails every expression
+// that depends on it.
+pub fn evaluate_all(
+    exprs: &BTreeMap<String, Expr>,
+    value_of: &dyn Fn(&str) -> Result<f64, String>,
+) -> BTreeMap<String, Result<f64, String>> {
+    let mut results = BTreeMap::new();
+    for id in exprs.keys() {
+        let mut path = Vec::new();
+        let _ = evaluate_one(id, exprs, value_of, &mut path, &mut results);
+    }
+    results
+}
+
+fn evaluate_one(
+    id: &str,
+    exprs: &BTreeMap<String, Expr>,
+    value_of: &dyn Fn(&str) -> Result<f64, String>,
+    path: &mut Vec<String>,
+    results: &mut BTreeMap<String, Result<f64, String>>,
+) -> Result<f64, String> {
+    if let Some(result) = results.get(id) {
+        return result.clone();
+    }
+    let Some(expr) = exprs.get(id) else {
+        return value_of(id);
+    };
+    if let Some(start) = path.iter().position(|seen| seen == id) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(id.to_string());
+        return Err(format!("cycle: {}", cycle.join(" -> ")));
+    }
+
+    path.push(id.to_string());
+    let result = expr.eval(&mut |uid| {
+        evaluate_one(uid, exprs, value_of, path, results).map_err(|err| {
+            if err.starts_with("cycle") || !exprs.contains_key(uid) {
+                err
+            } else {
+                format!("{uid} has an error: {err}")
+            }
+        })
+    });
+    path.pop();
+    results.insert(id.to_string(), result.clone());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str) -> Result<f64, String> {
+        let values = [("a", 2.0), ("b", 3.0), ("mock-000001", 21.5)];
+        parse(source).map_err(|e| e.to_string())?.eval(&mut |uid| {
+            values
+                .iter()
+                .find(|(name, _)| *name == uid)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| format!("unknown node {uid}"))
+        })
+    }
+
+    #[test]
+    fn arithmetic_follows_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(eval("-{a} - -1"), Ok(-1.0));
+        assert_eq!(eval("{a} + {b} + {mock-000001}"), Ok(26.5));
+        assert_eq!(eval("{ mock-000001 } * 2 + 32"), Ok(75.0));
+        assert_eq!(eval("7 % 4"), Ok(3.0));
+    }
+
+    #[test]
+    fn booleans_are_one_and_zero() {
+        assert_eq!(eval("{a} > 1 && {b} <= 3"), Ok(1.0));
+        assert_eq!(eval("{a} == {b} || false"), Ok(0.0));
+        assert_eq!(eval("!{a}"), Ok(0.0));
+        assert_eq!(eval("{a} != 2"), Ok(0.0));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(eval("max({a}, {b}, 1)"), Ok(3.0));
+        assert_eq!(eval("avg({a}, {b})"), Ok(2.5));
+        assert_eq!(eval("round(abs(-2.6))"), Ok(3.0));
+        // The branch not taken is not evaluated
+        assert_eq!(eval("if({a} > 5, 1 / 0, sum({a}, {b}))"), Ok(5.0));
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_problem() {
+        let error = |source: &str| parse(source).unwrap_err();
+        assert_eq!(error("1 +").at, 3);
+        assert_eq!(error("({a} + 1").message, "missing ')'");
+        assert_eq!(error("2 * {a").message, "missing '}' after the node uid");
+        assert_eq!(
+            error("foo(1)").message,
+            "unknown function 'foo', node uids go in braces"
+        );
+        assert_eq!(
+            error("abs(1, 2)").message,
+            "wrong number of arguments for abs"
+        );
+        assert_eq!(error("1 2").to_string(), "unexpected '2' (column 3)");
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(MAX_DEPTH)), Ok(1.0));
+        let error = parse(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(error.message, "nested more than 64 levels deep");
+        assert_eq!(error.at, MAX_DEPTH + 1);
+
+        assert!(parse(&"-".repeat(100_000)).is_err());
+        assert!(parse(&format!("{}1", "!".repeat(100_000))).is_err());
+        assert!(parse(&format!("{}1", "abs(".repeat(10_000))).is_err());
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        assert_eq!(eval("{a} / ({b} - 3)"), Err("division by zero".to_string()));
+        assert_eq!(eval("{c} + 1"), Err("unknown node c".to_string()));
+        assert_eq!(
+            eval("sqrt(-1)"),
+            Err("the result is not a finite number".to_string())
+        );
+    }
+
+    #[test]
+    fn refs_lists_every_uid_once() {
+        let refs = parse("{a} + max({b}, {a})").unwrap().refs();
+        assert_eq!(refs.into_iter().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn evaluate_all_follows_dependencies_and_reports_cycles() {
+        let exprs: BTreeMap<String, Expr> = [
+            ("total", "{part} + {raw}"),
+            ("part", "{raw} * 2"),
+            ("loop_a", "{loop_b} + 1"),
+            ("loop_b", "{loop_a} + 1"),
+            ("uses_loop", "{loop_a}"),
+            ("missing", "{gone}"),
+        ]
+        .into_iter()
+        .map(|(id, source)| (id.to_string(), parse(source).unwrap()))
+        .collect();
+        let value_of = |uid: &str| match uid {
+            "raw" => Ok(10.0),
+            _ => Err(format!("unknown node {uid}")),
+        };
+
+        let results = evaluate_all(&exprs, &value_of);
+        assert_eq!(results["part"], Ok(20.0));
+        assert_eq!(results["total"], Ok(30.0));
+        assert_eq!(
+            results["loop_a"],
+            Err("cycle: loop_a -> loop_b -> loop_a".to_string())
+        );
+        assert_eq!(results["loop_b"], results["loop_a"]);
+        assert_eq!(
+            results["uses_loop"],
+            Err("cycle: loop_a -> loop_b -> loop_a".to_string())
+        );
+        assert_eq!(results["missing"], Err("unknown node gone".to_string()));
+    }
+}
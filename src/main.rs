@@ -1,28 +1,77 @@
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
 
 use bson::DateTime;
+use futures::StreamExt;
 use leptos::ev::SubmitEvent;
 use leptos::html::{Input, Select};
 use leptos::svg::Svg;
 use leptos::*;
 use leptos_router::*;
-use reqwest;
 use reqwest::header::{
-    HeaderMap, HeaderValue, ACCEPT, ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE, USER_AGENT,
+    HeaderMap, HeaderValue, ACCEPT, ACCESS_CONTROL_ALLOW_ORIGIN, AUTHORIZATION, CONTENT_TYPE,
+    USER_AGENT,
 };
 use serde::{Deserialize, Serialize};
-use serde_json;
 
-#[derive(Debug, Serialize, Deserialize)]
+use alarms::{
+    alarm_limits_text, load_alarms, load_alert_history, AlarmWatcher, Alarms, AlarmsComponent,
+    AlertHistory, AlertSettings, AlertSettingsSection,
+};
+use calc::{load_calc_nodes, use_calc_nodes, CalcNodes, CalculatedNodes, CALC_NODES_KEY};
+use dashboards::{load_user_dashboards, UserDashboardList, UserDashboardView, USER_DASHBOARDS_KEY};
+
+mod alarms;
+mod calc;
+mod dashboards;
+mod expr;
+mod mock;
+mod schema;
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct RapiStruct {
     node_val: String,
     node_last_update: DateTime,
     node_name: String,
     node_rw_direction: String,
     node_uid: String,
+    // Folder of the node, "site/area/device/signal"
+    #[serde(default)]
+    node_path: String,
+    // Comma separated
+    #[serde(default)]
+    node_tags: String,
+    // What the signal is, for the people reading the list
+    #[serde(default)]
+    node_description: String,
+    // Engineering unit of the value, e.g. "degC"
+    #[serde(default)]
+    node_unit: String,
+    // Device the value comes from
+    #[serde(default)]
+    node_device: String,
+    #[serde(default)]
+    node_owner: String,
+    #[serde(default)]
+    node_notes: String,
+    // Alarm limits, "" for none
+    #[serde(default)]
+    node_alarm_hh: String,
+    #[serde(default)]
+    node_alarm_h: String,
+    #[serde(default)]
+    node_alarm_l: String,
+    #[serde(default)]
+    node_alarm_ll: String,
+    // "1" alarms while the node is on, "0" while it is off, "" never
+    #[serde(default)]
+    node_alarm_state: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 
 struct MqttStruct {
     mqtt_lock_to_uid: String,
@@ -31,7 +80,7 @@ struct MqttStruct {
     mqtt_topic_modif: String,
     mqtt_rw: String,
 }
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 
 struct ModbusStruct {
     mb_lock_to_uid: String,
@@ -47,745 +96,5691 @@ struct NewUidGet {
     uid: String,
 }
 
-async fn get_all_node_data(node_name: &str) -> String {
-    let mut get_url = "http://127.0.0.1:8000/getall/".to_string();
-    get_url.push_str(&node_name);
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum TimeZoneMode {
+    Local,
+    Utc,
+}
 
-    let resp = reqwest::get(get_url).await.unwrap().text().await.unwrap();
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum TimeFormat {
+    Iso,
+    Locale,
+}
 
-    resp
+// User preferences, kept in the browser's localStorage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct UiSettings {
+    time_zone: TimeZoneMode,
+    time_format: TimeFormat,
+    // A rAPI node not updated for this many seconds counts as stale
+    stale_after_secs: i64,
+    // The concentrators this UI can talk to, the first one is the default
+    backends: Vec<Backend>,
+    alerts: AlertSettings,
 }
 
-async fn crt_new_uid(count: i32) -> String {
-    let mut _to_ret = String::from("");
-    if count == 1 {
-        let get_url = "http://127.0.0.1:8000/c/".to_string();
+// One data concentrator, `id` is used in the /site/:site routes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Backend {
+    id: String,
+    name: String,
+    url: String,
+}
 
-        let resp: NewUidGet = reqwest::get(get_url).await.unwrap().json().await.unwrap();
-        _to_ret = resp.uid;
-    } else {
-        // Not the best method but might do the trick for now
-        _to_ret = String::from("");
+fn default_backend() -> Backend {
+    Backend {
+        id: "local".to_string(),
+        name: "Local".to_string(),
+        url: "http://127.0.0.1:8000".to_string(),
     }
-
-    _to_ret
 }
 
-async fn post_data(url: &str, hmap: HashMap<&str, String>) -> String {
-    fn construct_headers() -> HeaderMap {
-        let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_static("reqwest"));
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
-        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
-        headers
+impl Default for UiSettings {
+    fn default() -> Self {
+        UiSettings {
+            time_zone: TimeZoneMode::Local,
+            time_format: TimeFormat::Locale,
+            stale_after_secs: 300,
+            backends: if cfg!(feature = "mock") {
+                vec![mock::demo_backend()]
+            } else {
+                vec![default_backend()]
+            },
+            alerts: AlertSettings::default(),
+        }
     }
+}
 
-    let form_url = format!("{url}");
-    let rw_client = reqwest::Client::new();
-    let res = rw_client
-        .post(form_url)
-        .json(&hmap)
-        .headers(construct_headers())
-        .send()
-        .await;
-
-    let mut ret_val = String::new();
+const UI_SETTINGS_KEY: &str = "dcui_settings";
 
-    match res {
-        Ok(resp_in) => {
-            log!("{resp_in:?}");
-            if resp_in.status() == 200 {
-                ret_val = String::from("200");
-            }
-        }
-        Err(err) => {
-            log!("{err:?}");
-            ret_val = String::from("500");
-        }
-    };
-    ret_val
+fn local_storage_get(key: &str) -> Option<String> {
+    window()
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(key).ok().flatten())
 }
 
-#[component]
-fn NavComponent(
-    cx: Scope,
-    href: String,
-    text_to_show: String,
-    svg_to_use: HtmlElement<Svg>,
-) -> impl IntoView {
-    view! {
-        cx,
-        <a class="flex items-center my-2 px-4 py-2 dark:text-gray-300 transition-colors duration-300 transform rounded-md dark:bg-gray-800 bg-gray-300 " href={href}>
-        {svg_to_use}
-        <span class="mx-4 font-medium">{text_to_show}</span>
-        </a>
+fn local_storage_set(key: &str, value: &str) {
+    if let Some(storage) = window().local_storage().ok().flatten() {
+        if let Err(err) = storage.set_item(key, value) {
+            log!("Could not write {key} to localStorage: {err:?}");
+        }
     }
 }
 
-#[component]
-fn App(cx: Scope) -> impl IntoView {
-    // Svg icon for the home menu point
-    let home_svg = view! {cx,
-    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
-    <path stroke-linecap="round" stroke-linejoin="round" d="M2.25 12l8.954-8.955c.44-.439 1.152-.439 1.591 0L21.75 12M4.5 9.75v10.125c0 .621.504 1.125 1.125 1.125H9.75v-4.875c0-.621.504-1.125 1.125-1.125h2.25c.621 0 1.125.504 1.125 1.125V21h4.125c.621 0 1.125-.504 1.125-1.125V9.75M8.25 21h8.25" />
-    </svg>};
-
-    // Svg icon for the home menu point
-    let data_svg = view! {cx,
-    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
-    <path d="M22.485,10.975,12,17.267,1.515,10.975A1,1,0,1,0,.486,12.69l11,6.6a1,1,0,0,0,1.03,0l11-6.6a1,1,0,1,0-1.029-1.715Z"/>
-    <path d="M22.485,15.543,12,21.834,1.515,15.543A1,1,0,1,0,.486,17.258l11,6.6a1,1,0,0,0,1.03,0l11-6.6a1,1,0,1,0-1.029-1.715Z"/>
-    <path d="M12,14.773a2.976,2.976,0,0,1-1.531-.425L.485,8.357a1,1,0,0,1,0-1.714L10.469.652a2.973,2.973,0,0,1,3.062,0l9.984,5.991a1,1,0,0,1,0,1.714l-9.984,5.991A2.976,2.976,0,0,1,12,14.773ZM2.944,7.5,11.5,12.633a.974.974,0,0,0,1,0L21.056,7.5,12.5,2.367a.974.974,0,0,0-1,0h0Z"/>
-    </svg>};
+fn load_ui_settings() -> UiSettings {
+    local_storage_get(UI_SETTINGS_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
 
-    view! { cx,
-        <Router>
+fn use_ui_settings(cx: Scope) -> RwSignal<UiSettings> {
+    use_context::<RwSignal<UiSettings>>(cx).expect("UiSettings to be provided by App")
+}
 
-        <main>
-        <div class="dark:bg-gray-800 min-h-screen">
+const ACTIVE_SITE_KEY: &str = "dcui_site";
 
-        <aside class="flex flex-col w-64 float-left h-screen px-4 py-8 overflow-y-auto bg-gray-200 border-r rtl:border-r-0 rtl:border-l dark:bg-gray-900 dark:border-gray-700 border sticky top-0">
+thread_local! {
+    // Backend all requests go to, changed by the site switcher and the /site/:site routes
+    static ACTIVE_BACKEND: RefCell<Option<Backend>> = const { RefCell::new(None) };
+}
 
-        <div class="relative mt-6">
-            <span class="mx-2 font-medium dark:text-gray-300">"DATA CONCENTRATOR UI"</span>
+fn active_backend() -> Backend {
+    if let Some(backend) = ACTIVE_BACKEND.with(|b| b.borrow().clone()) {
+        return backend;
+    }
+    let backends = load_ui_settings().backends;
+    let site = local_storage_get(ACTIVE_SITE_KEY);
+    let backend = backends
+        .iter()
+        .find(|b| Some(&b.id) == site.as_ref())
+        .or(backends.first())
+        .cloned()
+        .unwrap_or_else(default_backend);
+    ACTIVE_BACKEND.with(|b| *b.borrow_mut() = Some(backend.clone()));
+    backend
+}
 
-        </div>
+// Full url of a backend endpoint, e.g. api_url("/c/")
+fn api_url(path: &str) -> String {
+    format!("{}{path}", active_backend().url)
+}
 
-        <div class="flex flex-col justify-between flex-1 mt-6 ">
-                <nav>
+// Id of the active backend, provided by App
+#[derive(Debug, Clone, Copy)]
+struct ActiveSite(RwSignal<String>);
 
-                    <NavComponent href="/".to_string() text_to_show="Home".to_string() svg_to_use=home_svg />
+fn use_active_site(cx: Scope) -> RwSignal<String> {
+    use_context::<ActiveSite>(cx)
+        .expect("active site is provided by App")
+        .0
+}
 
-                    <NavComponent href="/dmap".to_string() text_to_show="Data Map".to_string() svg_to_use=data_svg />
-                    <hr class="my-6 border-gray-900 dark:border-gray-600" />
+// Makes `id` the backend for all following requests, false if there is no such backend
+fn activate_backend(cx: Scope, id: &str) -> bool {
+    let settings = use_ui_settings(cx);
+    let Some(backend) = settings.with_untracked(|s| s.backends.iter().find(|b| b.id == id).cloned())
+    else {
+        return false;
+    };
+    ACTIVE_BACKEND.with(|b| *b.borrow_mut() = Some(backend));
+    local_storage_set(ACTIVE_SITE_KEY, id);
+    let site = use_active_site(cx);
+    if site.get_untracked() != id {
+        site.set(id.to_string());
+    }
+    // Every site has its own login
+    if let Some(session) = use_context::<RwSignal<Option<Session>>>(cx) {
+        let site_session = load_session();
+        if session.get_untracked() != site_session {
+            session.set(site_session);
+        }
+    }
+    true
+}
 
+// Route of a page inside the active site, e.g. site_path("abc", "/dmap")
+fn site_path(site: &str, path: &str) -> String {
+    format!("/site/{site}{path}")
+}
 
-                </nav>
+// Name of the timezone the browser runs in, e.g. "Europe/Budapest"
+fn browser_time_zone() -> String {
+    let resolved = js_sys::Intl::DateTimeFormat::new(&js_sys::Array::new(), &js_sys::Object::new())
+        .resolved_options();
+    js_sys::Reflect::get(&resolved, &"timeZone".into())
+        .ok()
+        .and_then(|tz| tz.as_string())
+        .unwrap_or_else(|| "UTC".to_string())
+}
 
-        </div>
-    </aside>
-        <Routes>
+fn format_timestamp(ts: &DateTime, settings: &UiSettings) -> String {
+    let millis = ts.timestamp_millis() as f64;
+    let date = js_sys::Date::new(&millis.into());
+
+    match (settings.time_format, settings.time_zone) {
+        (TimeFormat::Iso, TimeZoneMode::Utc) => date.to_iso_string().into(),
+        (TimeFormat::Iso, TimeZoneMode::Local) => {
+            // getTimezoneOffset() is UTC - local in minutes, so shift the date by it
+            // and print the ISO string with the local offset instead of the "Z"
+            let offset_min = -date.get_timezone_offset() as i64;
+            let shifted = js_sys::Date::new(&(millis + (offset_min * 60_000) as f64).into());
+            let iso: String = shifted.to_iso_string().into();
+            let sign = if offset_min < 0 { '-' } else { '+' };
+            format!(
+                "{}{}{:02}:{:02}",
+                iso.trim_end_matches('Z'),
+                sign,
+                offset_min.abs() / 60,
+                offset_min.abs() % 60
+            )
+        }
+        (TimeFormat::Locale, tz) => {
+            let locale = window()
+                .navigator()
+                .language()
+                .unwrap_or_else(|| "en-US".to_string());
+            let options = js_sys::Object::new();
+            if tz == TimeZoneMode::Utc {
+                let _ = js_sys::Reflect::set(&options, &"timeZone".into(), &"UTC".into());
+                let _ = js_sys::Reflect::set(&options, &"timeZoneName".into(), &"short".into());
+            }
+            date.to_locale_string(&locale, &options).into()
+        }
+    }
+}
 
-            <Route path="/" view=HomeComponent />
-            <Route
-                path="/dmap"
-                view=DataNodeList
-            >
-                // if no id specified, fall back
-                <Route path=":id" view=DataNodeInfo>
-                    <Route path="" view=DataNodeDesc />
-                    <Route path="dnodes" view=DataNodeData/>
-                    <Route path="newdnode" view=NewDnodeComp/>
-                </Route>
-                // if no id specified, fall back
-                <Route path="" view=|cx| view! { cx,
-                    <div class="select-dnode dark:text-gray-300 m-2 p-5 rounded overflow-hidden shadow-lg border">
-                        "Select a data node to view the information."
-                    </div>
-                }/>
-                    </Route>
-                </Routes>
-                </div>
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum AuthScheme {
+    Bearer,
+    Basic,
+}
 
-            </main>
+// Ordered, every role can do what the ones before it can
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Role {
+    // Sees values only
+    Viewer,
+    // Writes values of rAPI nodes, e.g. setpoints
+    Operator,
+    // Creates, edits and deletes nodes and mappings
+    Engineer,
+    Admin,
+}
 
-            </Router>
+impl Role {
+    fn label(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Engineer => "engineer",
+            Role::Admin => "admin",
+        }
+    }
+}
 
+// A backend that does not report a role only gets read access
+fn default_role() -> Role {
+    Role::Viewer
+}
 
+// The logged in user. Username and role are kept in localStorage, the
+// credential only in sessionStorage so it is gone with the tab.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Session {
+    username: String,
+    scheme: AuthScheme,
+    // The bearer token, or base64 of "user:password" for basic auth
+    #[serde(skip)]
+    credential: String,
+    #[serde(default = "default_role")]
+    role: Role,
+}
 
+impl Session {
+    fn header_value(&self) -> String {
+        match self.scheme {
+            AuthScheme::Bearer => format!("Bearer {}", self.credential),
+            AuthScheme::Basic => format!("Basic {}", self.credential),
         }
+    }
 }
 
-#[component]
-fn HomeComponent(cx: Scope) -> impl IntoView {
-    view! {
-        cx,
-        <div class="bg-neutral-50 py-24 px-6 text-center dark:bg-gray-800 h-screen align-middle">
-        <h1 class="mt-2 mb-16 text-5xl dark:text-gray-300 font-bold tracking-tight md:text-6xl xl:text-7xl">
-          A simple UI written in LEPTOS <br />for the <span class="text-blue-300 dark:text-blue-900 mx-4"> DATA CONCENTRATOR </span>software
-        </h1>
-        <a class="mb-2 inline-block rounded text-gray-600 bg-gray-300 dark:bg-gray-600 dark:text-gray-300 px-12 pt-4 pb-3.5 text-sm font-medium uppercase leading-normal transition shadow duration-150 ease-in-out hover:bg-primary-600 focus:bg-primary-600 focus:outline-none focus:ring-0 active:bg-primary-700 md:mr-2 md:mb-0"
-         href="/dmap" role="button">Go to data mapping</a>
-      </div>
-    }
+#[derive(Debug, Deserialize)]
+struct LoginResponse {
+    token: String,
+    #[serde(default = "default_role")]
+    role: Role,
 }
 
-#[component]
-fn DataNodeList(cx: Scope) -> impl IntoView {
-    view! { cx,
-        <div class="flex flex-wrap sticky top-0 ">
-          <section class="relative w-[100%] sticky top-0 z-10">
-            <nav class="flex bg-gray-200 dark:bg-gray-900 dark:text-gray-300 boarder sticky top-0 z-10">
-              <div class="px-5 xl:px-12 py-3 flex w-full items-center">
-                <ul class="md:flex px-4 font-semibold font-heading space-x-12">
-                <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="rapi">"rAPI"</A></li>
-                <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="mbtcp">"Modbus TCP"</A></li>
-                <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="mqtt">"MQTT"</A></li>
-                </ul>
-                </div>
-            </nav>
-          </section>
-          <Outlet/>
-        </div>
-    }
+#[derive(Debug, Deserialize)]
+struct WhoAmIResponse {
+    #[serde(default = "default_role")]
+    role: Role,
 }
 
-#[component]
-fn DataNodeInfo(cx: Scope) -> impl IntoView {
-    // we can access the :id param reactively with `use_params_map`
-    let params = use_params_map(cx);
-    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
+const SESSION_KEY: &str = "dcui_session";
+// sessionStorage
+const CREDENTIAL_KEY: &str = "dcui_credential";
+// Fired on the window when the backend answers 401
+const SESSION_EXPIRED_EVENT: &str = "dcui-session-expired";
 
-    let _name = move || match id().as_str() {
-        "rapi" => "REST API",
-        "mbtcp" => "Modbus TCP",
-        "mqtt" => "MQTT",
-        _ => "Data protocol not found!",
-    };
+fn session_storage() -> Option<web_sys::Storage> {
+    window().session_storage().ok().flatten()
+}
 
-    view! { cx,
-            <div class="dnode-info w-[100%] sticky top-12 ">
-            <div class="flex flex-wrap sticky top-12 ">
-              <section class="relative w-[100%]  sticky top-12">
-                <nav class="flex bg-gray-100 dark:bg-gray-600 dark:text-gray-300 boarder sticky top-12">
-                  <div class="px-5 xl:px-12 py-3 flex w-full items-center">
-                    <ul class="md:flex px-4 font-semibold font-heading space-x-12">
-                    <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="">"Info"</A></li>
-                    <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="newdnode">"New"</A></li>
-                    <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="dnodes">"Data nodes"</A></li>
-                    </ul>
-                    </div>
-                </nav>
-              </section>
-            </div>
-            <Outlet/>
+// Sessions are per backend id, every site has its own login
+fn load_session_map<T: serde::de::DeserializeOwned>(raw: Option<String>) -> HashMap<String, T> {
+    raw.and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
 
-            </div>
+fn load_credentials() -> HashMap<String, String> {
+    load_session_map(session_storage().and_then(|storage| storage.get_item(CREDENTIAL_KEY).ok().flatten()))
+}
 
-    }
+// Without the credential of this tab there is no session, only the
+// remembered username
+fn load_session_for(site: &str) -> Option<Session> {
+    let mut session = load_session_map::<Session>(local_storage_get(SESSION_KEY)).remove(site)?;
+    session.credential = load_credentials().remove(site)?;
+    Some(session)
 }
 
-#[component]
-fn DataNodeDesc(cx: Scope) -> impl IntoView {
-    // we can access the :id param reactively with `use_params_map`
-    let params = use_params_map(cx);
-    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
+fn load_session() -> Option<Session> {
+    load_session_for(&active_backend().id)
+}
 
-    let dnode_descr = move || match id().as_str() {
-        "rapi" => "rAPI is the foundation for the communications of this software!",
-        "mbtcp" => "Modbus TCP \"mapper\", you can bind MB registers to the rAPI data nodes!",
-        "mqtt" => "MQTT \"mapper\", you can bind MQTT values to the rAPI data nodes!",
-        _ => "No description for this data node",
-    };
+fn remembered_username() -> String {
+    load_session_map::<Session>(local_storage_get(SESSION_KEY))
+        .remove(&active_backend().id)
+        .map(|session| session.username)
+        .unwrap_or_default()
+}
 
-    view! { cx,
-        <div class="default-info dark:text-gray-300 m-2 p-5 rounded overflow-hidden shadow-lg border" >
-        <p> {dnode_descr} </p>
-        </div>
+fn store_session_for(site: &str, session: Option<&Session>) {
+    let mut sessions = load_session_map::<Session>(local_storage_get(SESSION_KEY));
+    let mut credentials = load_credentials();
+    match session {
+        Some(session) => {
+            sessions.insert(site.to_string(), session.clone());
+            credentials.insert(site.to_string(), session.credential.clone());
+        }
+        None => {
+            sessions.remove(site);
+            credentials.remove(site);
+        }
+    }
+    if let Ok(raw) = serde_json::to_string(&sessions) {
+        local_storage_set(SESSION_KEY, &raw);
+    }
+    if let (Some(storage), Ok(raw)) = (session_storage(), serde_json::to_string(&credentials)) {
+        if let Err(err) = storage.set_item(CREDENTIAL_KEY, &raw) {
+            log!("Could not write {CREDENTIAL_KEY} to sessionStorage: {err:?}");
+        }
     }
 }
 
-#[component]
-fn DataNodeData(cx: Scope) -> impl IntoView {
-    // we can access the :id param reactively with `use_params_map`
-    let params = use_params_map(cx);
-    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
+fn store_session(session: Option<&Session>) {
+    store_session_for(&active_backend().id, session)
+}
 
-    let dnode_descr = match id().as_str() {
-        "rapi" => "bucket",
-        "mbtcp" => "mbstuff",
-        "mqtt" => "mqttstuff",
-        _ => "",
-    };
+fn use_session(cx: Scope) -> RwSignal<Option<Session>> {
+    use_context::<RwSignal<Option<Session>>>(cx).expect("Session to be provided by App")
+}
 
-    let id_for_later = dnode_descr;
+fn use_role(cx: Scope) -> Signal<Role> {
+    let session = use_session(cx);
+    Signal::derive(cx, move || {
+        session.with(|s| s.as_ref().map(|s| s.role).unwrap_or(Role::Viewer))
+    })
+}
 
-    let async_data = create_resource(
-        cx,
-        || (),
-        move |_| async move { get_all_node_data(dnode_descr).await },
-    );
+// The configured backend `url` points into, the longest base url wins
+fn backend_for_url(url: &str) -> Option<Backend> {
+    load_ui_settings()
+        .backends
+        .into_iter()
+        .chain(std::iter::once(active_backend()))
+        .filter(|b| url.starts_with(&b.url))
+        .max_by_key(|b| b.url.len())
+}
 
-    view! { cx,
-        <div class="contact-info">
-        <p>    {move || match async_data.read(cx) {
-            None => view! { cx, <p>"Loading..."</p> }.into_view(cx),
-            Some(data) => view! { cx, <ShowData data id_for_later/>  }.into_view(cx)
-        }}  </p>
-        </div>
+// Drops the session of the backend that answered 401 to `url`
+fn session_expired(url: &str) {
+    let Some(backend) = backend_for_url(url) else {
+        return;
+    };
+    store_session_for(&backend.id, None);
+    if let Ok(event) = web_sys::Event::new(SESSION_EXPIRED_EVENT) {
+        let _ = window().dispatch_event(&event);
     }
 }
 
-#[component]
-fn ShowData(cx: Scope, data: String, id_for_later: &'static str) -> impl IntoView {
-    if id_for_later == "mbstuff" {
-        let vect_data_mb: Vec<ModbusStruct> = serde_json::from_str(&data).unwrap();
-        let mapped_view = vect_data_mb
-            .into_iter()
-            .map(|mbstruct| {
-                view! { cx,
-                    <ShowMbSingleData mbstruct/>
-                }
-            })
-            .collect::<Vec<_>>();
-        view! { cx,
-            <div class="grid grid-cols-3 grid-flow-row gap-2">{mapped_view}</div>
-        }
-    } else if id_for_later == "mqttstuff" {
-        let vect_data_mqtt: Vec<MqttStruct> = serde_json::from_str(&data).unwrap();
-        let mapped_view = vect_data_mqtt
-            .into_iter()
-            .map(|mqttstruct| {
-                view! { cx,
-                    <ShowMqttSingleData mqttstruct/>
-                }
-            })
-            .collect::<Vec<_>>();
-        view! { cx,
-            <div class="grid grid-cols-3 grid-flow-row gap-2">{mapped_view}</div>
-        }
-    } else if id_for_later == "bucket" {
-        let vect_data_rapi: Vec<RapiStruct> = serde_json::from_str(&data).unwrap();
-        let mapped_view = vect_data_rapi
-            .into_iter()
-            .map(|rapistruct| {
-                view! { cx,
-                    <ShowRapiSingleData rapistruct/>
-                }
-            })
-            .collect::<Vec<_>>();
-        view! { cx,
-            <div class="grid grid-cols-2 grid-flow-row gap-2">{mapped_view}</div>
-        }
-    } else {
-        view! { cx,
-            <div>"No map"</div>
-        }
+// Adds the credential of the site `url` belongs to, never another site's
+fn with_auth(request: reqwest::RequestBuilder, url: &str) -> reqwest::RequestBuilder {
+    match backend_for_url(url).and_then(|backend| load_session_for(&backend.id)) {
+        Some(session) => request.header(AUTHORIZATION, session.header_value()),
+        None => request,
     }
 }
 
-#[component]
-fn ShowMbSingleData(cx: Scope, mbstruct: ModbusStruct) -> impl IntoView {
-    view! { cx,
-        <div class="mb_data_single m-2 p-5 rounded overflow-hidden shadow-lg border">
-            <ShowSingleDataSpan row_desc="MB locked to dNode: ".to_string() data_to_show=mbstruct.mb_lock_to_uid/>
-            <ShowSingleDataSpan row_desc="MB ip: ".to_string() data_to_show=mbstruct.mb_ip/>
-            <ShowSingleDataSpan row_desc="MB port: ".to_string() data_to_show=mbstruct.mb_port/>
-            <ShowSingleDataSpan row_desc="MB register: ".to_string() data_to_show=mbstruct.mb_register/>
-            <ShowSingleDataSpan row_desc="MB read/write: ".to_string() data_to_show=mbstruct.mb_rw/>
-        </div>
+// Fired on the window when a request reaches / fails to reach the backend
+const BACKEND_ONLINE_EVENT: &str = "dcui-backend-online";
+const BACKEND_OFFLINE_EVENT: &str = "dcui-backend-offline";
 
+fn report_connectivity(online: bool) {
+    let name = if online {
+        BACKEND_ONLINE_EVENT
+    } else {
+        BACKEND_OFFLINE_EVENT
+    };
+    if let Ok(event) = web_sys::Event::new(name) {
+        let _ = window().dispatch_event(&event);
     }
 }
 
+// GET with the session's credentials, non 2xx answers are errors. Only answers
+// of the active backend change the connection state.
+async fn backend_get(url: &str) -> Result<reqwest::Response, reqwest::Error> {
+    let active = backend_for_url(url).is_some_and(|backend| backend.id == active_backend().id);
+    let resp = match with_auth(reqwest::Client::new().get(url), url).send().await {
+        Ok(resp) => resp,
+        Err(err) => {
+            if active {
+                report_connectivity(false);
+            }
+            return Err(err);
+        }
+    };
+    if active {
+        report_connectivity(true);
+    }
+    if resp.status() == 401 {
+        session_expired(url);
+    }
+    resp.error_for_status()
+}
+
+async fn login(username: String, password: String) -> Result<Session, String> {
+    let mut map = HashMap::new();
+    map.insert("username", username.clone());
+    map.insert("password", password.clone());
+
+    let resp = reqwest::Client::new()
+        .post(api_url("/login"))
+        .json(&map)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    match resp.status().as_u16() {
+        200 => {
+            let body: LoginResponse = resp.json().await.map_err(|err| err.to_string())?;
+            Ok(Session {
+                username,
+                scheme: AuthScheme::Bearer,
+                credential: body.token,
+                role: body.role,
+            })
+        }
+        // No login endpoint on the backend, try basic auth against a read instead
+        404 | 405 => {
+            let credential = window()
+                .btoa(&format!("{username}:{password}"))
+                .map_err(|_| "Username and password must be plain latin characters".to_string())?;
+            let mut session = Session {
+                username,
+                scheme: AuthScheme::Basic,
+                credential,
+                role: default_role(),
+            };
+            let check = reqwest::Client::new()
+                .get(api_url("/getall/bucket"))
+                .header(AUTHORIZATION, session.header_value())
+                .send()
+                .await
+                .map_err(|err| err.to_string())?;
+            if !check.status().is_success() {
+                return Err("Wrong username or password".to_string());
+            }
+
+            // The role comes from /whoami when the backend has one
+            let whoami = reqwest::Client::new()
+                .get(api_url("/whoami"))
+                .header(AUTHORIZATION, session.header_value())
+                .send()
+                .await;
+            if let Ok(resp) = whoami {
+                if resp.status().is_success() {
+                    if let Ok(body) = resp.json::<WhoAmIResponse>().await {
+                        session.role = body.role;
+                    }
+                }
+            }
+            Ok(session)
+        }
+        401 | 403 => Err("Wrong username or password".to_string()),
+        status => Err(format!("Login failed with status {status}")),
+    }
+}
+
+async fn try_get_backend_data(backend: &Backend, node_name: &str) -> Result<String, reqwest::Error> {
+    let get_url = format!("{}/getall/{node_name}", backend.url);
+
+    let raw = backend_get(&get_url).await?.text().await?;
+    store_cached(&cache_key(&backend.id, node_name), &raw);
+    Ok(raw)
+}
+
+// Reads one node straight from the backend. /u writes the whole node, so every
+// update starts from this instead of a copy that may hold an old value.
+async fn fetch_node(backend: &Backend, uid: &str) -> Result<RapiStruct, String> {
+    let raw = try_get_backend_data(backend, "bucket")
+        .await
+        .map_err(|_| unsent_reason("500"))?;
+    schema::decode_list::<RapiStruct>(&raw)
+        .items
+        .into_iter()
+        .find(|node| node.node_uid == uid)
+        .ok_or_else(|| "The node is gone from the backend".to_string())
+}
+
+// Last known answer of a /getall/ endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedData {
+    raw: String,
+    fetched_at: DateTime,
+}
+
+const NODE_CACHE_KEY: &str = "dcui_node_cache";
+// Fired on the window whenever the cache or the running fetches change
+const NODE_CACHE_CHANGED_EVENT: &str = "dcui-node-cache-changed";
+// Cached data younger than this is shown without asking the backend again
+const CACHE_FRESH_SECS: i64 = 15;
+
+thread_local! {
+    static REVALIDATING: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+    // Why the last fetch of a cache key failed, cleared by the next success
+    static FETCH_ERRORS: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+}
+
+// The cache as seen by the components, kept in sync by App
+#[derive(Debug, Clone, Copy)]
+struct NodeCache {
+    entries: RwSignal<HashMap<String, CachedData>>,
+    revalidating: RwSignal<HashSet<String>>,
+    errors: RwSignal<HashMap<String, String>>,
+}
+
+fn use_node_cache(cx: Scope) -> NodeCache {
+    use_context::<NodeCache>(cx).expect("node cache is provided by App")
+}
+
+fn load_node_cache() -> HashMap<String, CachedData> {
+    local_storage_get(NODE_CACHE_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+// Cache entries are kept per backend
+fn cache_key(site: &str, endpoint: &str) -> String {
+    format!("{site}:{endpoint}")
+}
+
+fn node_cache_changed() {
+    if let Ok(event) = web_sys::Event::new(NODE_CACHE_CHANGED_EVENT) {
+        let _ = window().dispatch_event(&event);
+    }
+}
+
+fn store_cached(endpoint: &str, raw: &str) {
+    let mut cache = load_node_cache();
+    cache.insert(
+        endpoint.to_string(),
+        CachedData {
+            raw: raw.to_string(),
+            fetched_at: DateTime::now(),
+        },
+    );
+    if let Ok(raw) = serde_json::to_string(&cache) {
+        local_storage_set(NODE_CACHE_KEY, &raw);
+    }
+    node_cache_changed();
+}
+
+// Keeps the data but makes the next visit ask the backend
+fn invalidate_cached(endpoint: &str) {
+    let mut cache = load_node_cache();
+    if let Some(cached) = cache.get_mut(endpoint) {
+        cached.fetched_at = DateTime::from_millis(0);
+        if let Ok(raw) = serde_json::to_string(&cache) {
+            local_storage_set(NODE_CACHE_KEY, &raw);
+        }
+        node_cache_changed();
+    }
+}
+
+// Fetches the endpoint of the active backend in the background unless the
+// cached copy is fresh or a fetch is already running
+fn revalidate(endpoint: &'static str) {
+    revalidate_backend(active_backend(), endpoint);
+}
+
+fn revalidate_backend(backend: Backend, endpoint: &'static str) {
+    let key = cache_key(&backend.id, endpoint);
+    let fresh = load_node_cache().get(&key).is_some_and(|cached| {
+        DateTime::now().timestamp_millis() - cached.fetched_at.timestamp_millis()
+            < CACHE_FRESH_SECS * 1000
+    });
+    if endpoint.is_empty() || fresh {
+        return;
+    }
+    if !REVALIDATING.with(|r| r.borrow_mut().insert(key.clone())) {
+        return;
+    }
+    node_cache_changed();
+
+    spawn_local(async move {
+        let result = try_get_backend_data(&backend, endpoint).await;
+        FETCH_ERRORS.with(|errors| match result {
+            Ok(_) => errors.borrow_mut().remove(&key),
+            Err(err) => errors.borrow_mut().insert(key.clone(), err.to_string()),
+        });
+        REVALIDATING.with(|r| r.borrow_mut().remove(&key));
+        node_cache_changed();
+    });
+}
+
+fn cached_list<T: schema::Document>(
+    entries: &HashMap<String, CachedData>,
+    endpoint: &str,
+) -> Option<Vec<T>> {
+    entries
+        .get(endpoint)
+        .map(|cached| schema::decode_list(&cached.raw).items)
+}
+
+// "12 s ago", "3 min ago", ...
+fn age_text(fetched_at: &DateTime, now: &DateTime) -> String {
+    let secs = ((now.timestamp_millis() - fetched_at.timestamp_millis()) / 1000).max(0);
+    match secs {
+        0..=59 => format!("{secs} s ago"),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86399 => format!("{} h ago", secs / 3600),
+        _ => format!("{} days ago", secs / 86400),
+    }
+}
+
+fn is_stale(rapistruct: &RapiStruct, stale_after_secs: i64) -> bool {
+    let age_ms = DateTime::now().timestamp_millis() - rapistruct.node_last_update.timestamp_millis();
+    age_ms > stale_after_secs * 1000
+}
+
+// Every uid some Modbus or MQTT mapping is locked to
+fn mapped_uids(modbus: &[ModbusStruct], mqtt: &[MqttStruct]) -> HashSet<String> {
+    modbus
+        .iter()
+        .map(|mb| mb.mb_lock_to_uid.clone())
+        .chain(mqtt.iter().map(|mq| mq.mqtt_lock_to_uid.clone()))
+        .collect()
+}
+
+fn rapi_uids(rapi: &[RapiStruct]) -> HashSet<String> {
+    rapi.iter().map(|node| node.node_uid.clone()).collect()
+}
+
+// Uids reserved from the backend but not used yet, kept for the next create
+const UID_POOL_KEY: &str = "dcui_uid_pool";
+
+// Uids belong to the backend that handed them out
+fn uid_pool_key(backend: &Backend) -> String {
+    format!("{UID_POOL_KEY}_{}", backend.id)
+}
+// At most this many /c/ requests are in flight at once
+const UID_FETCH_CONCURRENCY: usize = 4;
+
+fn uid_pool(backend: &Backend) -> Vec<String> {
+    local_storage_get(&uid_pool_key(backend))
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn store_uid_pool(backend: &Backend, pool: &[String]) {
+    if let Ok(raw) = serde_json::to_string(pool) {
+        local_storage_set(&uid_pool_key(backend), &raw);
+    }
+}
+
+// Gives uids that were taken but not used back to the pool
+fn release_uids(uids: Vec<String>) {
+    release_uids_to(&active_backend(), uids)
+}
+
+fn release_uids_to(backend: &Backend, uids: Vec<String>) {
+    if uids.is_empty() {
+        return;
+    }
+    let mut pool = uid_pool(backend);
+    pool.extend(uids);
+    store_uid_pool(backend, &pool);
+}
+
+async fn reserve_uids(backend: &Backend, n: usize) -> Result<Vec<String>, String> {
+    let url = format!("{}/c/", backend.url);
+    let results: Vec<Result<NewUidGet, reqwest::Error>> = futures::stream::iter(0..n)
+        .map(|_| async {
+            backend_get(&url)
+                .await?
+                .json::<NewUidGet>()
+                .await
+        })
+        .buffer_unordered(UID_FETCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut uids = Vec::new();
+    let mut error = None;
+    for result in results {
+        match result {
+            Ok(new_uid) => uids.push(new_uid.uid),
+            Err(err) => error = Some(err.to_string()),
+        }
+    }
+
+    match error {
+        Some(err) => {
+            // Keep what we did get for the next try
+            release_uids_to(backend, uids);
+            Err(err)
+        }
+        None => Ok(uids),
+    }
+}
+
+// Takes `n` uids, from the pool first and from the backend for the rest
+async fn take_uids(n: usize) -> Result<Vec<String>, String> {
+    take_uids_from(&active_backend(), n).await
+}
+
+async fn take_uids_from(backend: &Backend, n: usize) -> Result<Vec<String>, String> {
+    let mut pool = uid_pool(backend);
+    let mut uids: Vec<String> = pool.drain(..n.min(pool.len())).collect();
+    store_uid_pool(backend, &pool);
+
+    if uids.len() < n {
+        match reserve_uids(backend, n - uids.len()).await {
+            Ok(fresh) => uids.extend(fresh),
+            Err(err) => {
+                release_uids_to(backend, uids);
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(uids)
+}
+
+async fn take_uid() -> Result<String, String> {
+    take_uids(1).await.map(|mut uids| uids.remove(0))
+}
+
+async fn post_data(url: &str, hmap: HashMap<&str, String>) -> String {
+    fn construct_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("reqwest"));
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        headers
+    }
+
+    let rw_client = reqwest::Client::new();
+    let res = with_auth(rw_client.post(url).json(&hmap).headers(construct_headers()), url)
+        .send()
+        .await;
+
+    let mut ret_val = String::new();
+
+    match res {
+        Ok(resp_in) => {
+            report_connectivity(true);
+            if resp_in.status() == 200 {
+                ret_val = String::from("200");
+            } else if resp_in.status() == 401 {
+                session_expired(url);
+                ret_val = String::from("401");
+            }
+        }
+        Err(err) => {
+            log!("{err:?}");
+            report_connectivity(false);
+            ret_val = String::from("500");
+        }
+    };
+    ret_val
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum AuditTarget {
+    RapiNode,
+    ModbusMapping,
+    MqttMapping,
+}
+
+impl AuditTarget {
+    // The document field that identifies what was changed
+    fn key_field(&self) -> &'static str {
+        match self {
+            AuditTarget::RapiNode => "node_uid",
+            AuditTarget::ModbusMapping => "mb_lock_to_uid",
+            AuditTarget::MqttMapping => "mqtt_lock_to_uid",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            AuditTarget::RapiNode => "rAPI node",
+            AuditTarget::ModbusMapping => "Modbus mapping",
+            AuditTarget::MqttMapping => "MQTT mapping",
+        }
+    }
+
+    // The /getall/ endpoint listing this kind of document
+    fn endpoint(&self) -> &'static str {
+        match self {
+            AuditTarget::RapiNode => "bucket",
+            AuditTarget::ModbusMapping => "mbstuff",
+            AuditTarget::MqttMapping => "mqttstuff",
+        }
+    }
+}
+
+// One configuration change, recorded by the UI or read from the backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuditEvent {
+    at: DateTime,
+    actor: String,
+    action: AuditAction,
+    target: AuditTarget,
+    key: String,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+    ok: bool,
+    #[serde(default)]
+    from_backend: bool,
+}
+
+const AUDIT_LOG_KEY: &str = "dcui_audit";
+// Fired on the window whenever an event is recorded
+const AUDIT_CHANGED_EVENT: &str = "dcui-audit-changed";
+// Oldest events are dropped past this
+const AUDIT_LOG_LIMIT: usize = 1000;
+
+fn load_audit_log() -> Vec<AuditEvent> {
+    local_storage_get(AUDIT_LOG_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn record_audit(event: AuditEvent) {
+    let mut log = load_audit_log();
+    log.push(event);
+    if log.len() > AUDIT_LOG_LIMIT {
+        log.drain(..log.len() - AUDIT_LOG_LIMIT);
+    }
+    if let Ok(raw) = serde_json::to_string(&log) {
+        local_storage_set(AUDIT_LOG_KEY, &raw);
+    }
+    if let Ok(event) = web_sys::Event::new(AUDIT_CHANGED_EVENT) {
+        let _ = window().dispatch_event(&event);
+    }
+}
+
+// A create/update/delete waiting to be sent to the backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OutboxItem {
+    id: u64,
+    url: String,
+    body: HashMap<String, String>,
+    action: AuditAction,
+    target: AuditTarget,
+    before: Option<serde_json::Value>,
+    queued_at: DateTime,
+    // Set when the backend refused the change on replay
+    error: Option<String>,
+}
+
+const OUTBOX_KEY: &str = "dcui_outbox";
+// Fired on the window whenever the outbox is written
+const OUTBOX_CHANGED_EVENT: &str = "dcui-outbox-changed";
+
+thread_local! {
+    static OUTBOX_REPLAYING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn load_outbox() -> Vec<OutboxItem> {
+    local_storage_get(OUTBOX_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn store_outbox(outbox: &[OutboxItem]) {
+    if let Ok(raw) = serde_json::to_string(outbox) {
+        local_storage_set(OUTBOX_KEY, &raw);
+    }
+    if let Ok(event) = web_sys::Event::new(OUTBOX_CHANGED_EVENT) {
+        let _ = window().dispatch_event(&event);
+    }
+}
+
+fn enqueue_outbox(mut item: OutboxItem) {
+    let mut outbox = load_outbox();
+    item.id = outbox.iter().map(|i| i.id + 1).max().unwrap_or(1);
+    outbox.push(item);
+    store_outbox(&outbox);
+}
+
+fn update_outbox(f: impl FnOnce(&mut Vec<OutboxItem>)) {
+    let mut outbox = load_outbox();
+    f(&mut outbox);
+    store_outbox(&outbox);
+}
+
+// Sends one change and records it in the audit log, unless the backend
+// could not be reached at all ("500")
+async fn send_mutation(item: &OutboxItem) -> String {
+    let hmap: HashMap<&str, String> = item
+        .body
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.clone()))
+        .collect();
+    let document = serde_json::to_value(&hmap).ok();
+    let key = hmap.get(item.target.key_field()).cloned().unwrap_or_default();
+    let (before, after) = match item.action {
+        AuditAction::Create => (None, document),
+        AuditAction::Update => (item.before.clone(), document),
+        AuditAction::Delete => (item.before.clone().or(document), None),
+    };
+
+    let resp = post_data(&item.url, hmap).await;
+
+    if resp == "200" {
+        // Queued items may belong to another backend than the active one
+        let site = backend_for_url(&item.url).unwrap_or_else(active_backend).id;
+        invalidate_cached(&cache_key(&site, item.target.endpoint()));
+    }
+    if resp != "500" {
+        record_audit(AuditEvent {
+            at: DateTime::now(),
+            actor: backend_for_url(&item.url)
+                .and_then(|backend| load_session_for(&backend.id))
+                .map(|s| s.username)
+                .unwrap_or_else(|| "anonymous".to_string()),
+            action: item.action,
+            target: item.target,
+            key,
+            before,
+            after,
+            ok: resp == "200",
+            from_backend: false,
+        });
+    }
+
+    resp
+}
+
+// Sends the queued changes in order, stops at the first one that fails
+async fn replay_outbox() {
+    if OUTBOX_REPLAYING.with(|replaying| replaying.replace(true)) {
+        return;
+    }
+
+    while let Some(item) = load_outbox().into_iter().next() {
+        let resp = send_mutation(&item).await;
+        match resp.as_str() {
+            "500" => break,
+            "200" => update_outbox(|outbox| outbox.retain(|i| i.id != item.id)),
+            _ => {
+                update_outbox(|outbox| {
+                    if let Some(queued) = outbox.iter_mut().find(|i| i.id == item.id) {
+                        queued.error = Some(format!("Refused by the backend ({resp})"));
+                    }
+                });
+                break;
+            }
+        }
+    }
+
+    OUTBOX_REPLAYING.with(|replaying| replaying.set(false));
+}
+
+// post_data for create/update/delete calls: records the change in the audit log
+// and queues it in the outbox when the backend cannot be reached. Returns
+// "202" for a queued change.
+async fn audited_post(
+    url: &str,
+    hmap: HashMap<&str, String>,
+    action: AuditAction,
+    target: AuditTarget,
+    before: Option<serde_json::Value>,
+) -> String {
+    let item = outbox_item(url, hmap, action, target, before);
+
+    // Keep the order: nothing overtakes changes that are already waiting
+    if !load_outbox().is_empty() {
+        enqueue_outbox(item);
+        spawn_local(replay_outbox());
+        return String::from("202");
+    }
+
+    let resp = send_mutation(&item).await;
+    if resp == "500" {
+        enqueue_outbox(item);
+        return String::from("202");
+    }
+    resp
+}
+
+// audited_post for changes that must never be queued: value writes, which
+// make no sense later, and creates that span several requests. Returns "500"
+// when the backend cannot be reached and "409" while older changes wait in
+// the outbox, as sending now would overtake them.
+async fn audited_post_now(
+    url: &str,
+    hmap: HashMap<&str, String>,
+    action: AuditAction,
+    target: AuditTarget,
+    before: Option<serde_json::Value>,
+) -> String {
+    if !load_outbox().is_empty() {
+        return String::from("409");
+    }
+    send_mutation(&outbox_item(url, hmap, action, target, before)).await
+}
+
+fn outbox_item(
+    url: &str,
+    hmap: HashMap<&str, String>,
+    action: AuditAction,
+    target: AuditTarget,
+    before: Option<serde_json::Value>,
+) -> OutboxItem {
+    OutboxItem {
+        id: 0,
+        url: url.to_string(),
+        body: hmap
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+        action,
+        target,
+        before,
+        queued_at: DateTime::now(),
+        error: None,
+    }
+}
+
+// Creates a mapping. A single create may wait in the outbox, a batch is sent
+// now or not at all so it never ends up half created and half queued.
+async fn create_mapping(url: &str, hmap: HashMap<&str, String>, target: AuditTarget, batch: bool) -> String {
+    if batch {
+        audited_post_now(url, hmap, AuditAction::Create, target, None).await
+    } else {
+        audited_post(url, hmap, AuditAction::Create, target, None).await
+    }
+}
+
+// Why an audited_post_now did not go through
+fn unsent_reason(resp: &str) -> String {
+    match resp {
+        "500" => "Backend offline, nothing was written".to_string(),
+        "409" => "Queued changes are waiting in the outbox, send or discard them first".to_string(),
+        resp => format!("Refused by the backend ({resp})"),
+    }
+}
+
+// Outcome of a create repeated up to `total` times, `failed` is the status of
+// the post it stopped at
+fn create_summary(created: usize, total: usize, failed: Option<&str>) -> String {
+    match failed {
+        None => format!("Created {created}"),
+        Some(resp) => format!("Created {created} of {total}, then stopped: {}", unsent_reason(resp)),
+    }
+}
+
+fn modbus_to_map(mb: &ModbusStruct) -> HashMap<&'static str, String> {
+    let mut map = HashMap::new();
+    map.insert("mb_lock_to_uid", mb.mb_lock_to_uid.clone());
+    map.insert("mb_ip", mb.mb_ip.clone());
+    map.insert("mb_port", mb.mb_port.clone());
+    map.insert("mb_rw", mb.mb_rw.clone());
+    map.insert("mb_register", mb.mb_register.clone());
+    map
+}
+
+fn mqtt_to_map(mq: &MqttStruct) -> HashMap<&'static str, String> {
+    let mut map = HashMap::new();
+    map.insert("mqtt_lock_to_uid", mq.mqtt_lock_to_uid.clone());
+    map.insert("mqtt_ip", mq.mqtt_ip.clone());
+    map.insert("mqtt_topic", mq.mqtt_topic.clone());
+    map.insert("mqtt_rw", mq.mqtt_rw.clone());
+    map.insert("mqtt_topic_modif", mq.mqtt_topic_modif.clone());
+    map
+}
+
+// The document /u takes, every field but the last update time
+fn rapi_to_map(node: &RapiStruct) -> HashMap<&'static str, String> {
+    let mut map = HashMap::new();
+    map.insert("node_val", node.node_val.clone());
+    map.insert("node_uid", node.node_uid.clone());
+    map.insert("node_rw_direction", node.node_rw_direction.clone());
+    map.insert("node_name", node.node_name.clone());
+    map.insert("node_path", node.node_path.clone());
+    map.insert("node_tags", node.node_tags.clone());
+    map.insert("node_description", node.node_description.clone());
+    map.insert("node_unit", node.node_unit.clone());
+    map.insert("node_device", node.node_device.clone());
+    map.insert("node_owner", node.node_owner.clone());
+    map.insert("node_notes", node.node_notes.clone());
+    map.insert("node_alarm_hh", node.node_alarm_hh.clone());
+    map.insert("node_alarm_h", node.node_alarm_h.clone());
+    map.insert("node_alarm_l", node.node_alarm_l.clone());
+    map.insert("node_alarm_ll", node.node_alarm_ll.clone());
+    map.insert("node_alarm_state", node.node_alarm_state.clone());
+    map
+}
+
+fn rapi_to_clone_map(node: &RapiStruct) -> HashMap<&'static str, String> {
+    let mut map = rapi_to_map(node);
+    map.remove("node_uid");
+    map
+}
+
+// "21.4 degC", just the value for nodes without a unit
+fn value_with_unit(node: &RapiStruct) -> String {
+    if node.node_unit.is_empty() {
+        node.node_val.clone()
+    } else {
+        format!("{} {}", node.node_val, node.node_unit)
+    }
+}
+
+// "/plant//hall 1/" -> "plant/hall 1"
+fn normalize_path(raw: &str) -> String {
+    path_segments(raw).join("/")
+}
+
+fn path_segments(path: &str) -> Vec<&str> {
+    path.split('/')
+        .map(|segment| segment.trim())
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+// True for the folder itself and everything below it
+fn in_path(node_path: &str, folder: &str) -> bool {
+    let node = path_segments(node_path);
+    let folder = path_segments(folder);
+    node.len() >= folder.len() && node[..folder.len()] == folder[..]
+}
+
+fn node_tags(node: &RapiStruct) -> Vec<String> {
+    split_tags(&node.node_tags)
+}
+
+fn split_tags(raw: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in raw.split(',').map(|tag| tag.trim()).filter(|tag| !tag.is_empty()) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+// " hall, temperature,,hall" -> "hall,temperature"
+fn normalize_tags(raw: &str) -> String {
+    split_tags(raw).join(",")
+}
+
+// Adds `by` to the number at the end of `text` keeping its zero padding,
+// "reg007" + 2 = "reg009"; text without a trailing number gets `by` appended.
+// None when the number does not fit a u64.
+fn increment_suffix(text: &str, by: u64) -> Option<String> {
+    let digits = text.chars().rev().take_while(|c| c.is_ascii_digit()).count();
+    let (head, tail) = text.split_at(text.len() - digits);
+    if tail.is_empty() {
+        return Some(if by == 0 { text.to_string() } else { format!("{text}{by}") });
+    }
+    let n = tail.parse::<u64>().ok()?.checked_add(by)?;
+    Some(format!("{head}{n:0width$}", width = tail.len()))
+}
+
+// The values a repeated create uses: `text`, then `text` + step, + 2 * step, ...
+fn repeated_suffixes(text: &str, count: u64, step: u64) -> Result<Vec<String>, String> {
+    (0..count)
+        .map(|i| i.checked_mul(step).and_then(|by| increment_suffix(text, by)))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| format!("Incrementing {text} {count} times by {step} overflows"))
+}
+
+const MAX_REPEAT: u64 = 100;
+
+// Parses the "repeat count" and "increment by" inputs of the create forms,
+// empty inputs mean 1
+fn parse_repeat(count: &str, step: &str) -> Result<(u64, u64), String> {
+    let parse = |raw: &str, what: &str| match raw.trim() {
+        "" => Ok(1),
+        raw => raw
+            .parse::<u64>()
+            .map_err(|_| format!("{what} must be a whole number")),
+    };
+    let count = parse(count, "Repeat count")?;
+    let step = parse(step, "Increment")?;
+    if count == 0 || count > MAX_REPEAT {
+        return Err(format!("Repeat count must be between 1 and {MAX_REPEAT}"));
+    }
+    if step == 0 {
+        return Err("Increment must be at least 1".to_string());
+    }
+    Ok((count, step))
+}
+
+fn repeat_values(count: NodeRef<Input>, step: NodeRef<Input>) -> Result<(u64, u64), String> {
+    parse_repeat(
+        &count.get().expect("<input> to exist").value(),
+        &step.get().expect("<input> to exist").value(),
+    )
+}
+
+// Create form prefilled from `map`, with the next suffix in `suffix_field`
+// so the clone does not collide with its source
+fn clone_href<'a>(path: &str, mut map: HashMap<&'a str, String>, suffix_field: &'a str) -> String {
+    if let Some(next) = map.get(suffix_field).and_then(|value| increment_suffix(value, 1)) {
+        map.insert(suffix_field, next);
+    }
+    format!("{path}{}", query_string(&map))
+}
+
+// "?key=value&..." with the values URI encoded, sorted by key
+fn query_string(map: &HashMap<&str, String>) -> String {
+    let mut pairs: Vec<_> = map.iter().collect();
+    pairs.sort();
+    let joined = pairs
+        .into_iter()
+        .map(|(key, value)| format!("{key}={}", js_sys::encode_uri_component(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("?{joined}")
+}
+
+#[component]
+fn NavComponent(
+    cx: Scope,
+    href: String,
+    text_to_show: String,
+    svg_to_use: HtmlElement<Svg>,
+    // Count shown next to the text, hidden while zero
+    #[prop(optional)] badge: Option<Signal<usize>>,
+) -> impl IntoView {
+    view! {
+        cx,
+        <a class="flex items-center my-2 px-4 py-2 dark:text-gray-300 transition-colors duration-300 transform rounded-md dark:bg-gray-800 bg-gray-300 " href={href}>
+        {svg_to_use}
+        <span class="mx-4 font-medium">{text_to_show}</span>
+        {move || badge.map(|badge| badge.get()).filter(|count| *count > 0).map(|count| view! { cx,
+            <span class="nav_badge ml-auto px-2 rounded-full bg-red-600 text-white text-sm font-bold">{count}</span>
+        })}
+        </a>
+    }
+}
+
+#[component]
+fn App(cx: Scope) -> impl IntoView {
+    let ui_settings = create_rw_signal(cx, load_ui_settings());
+    provide_context(cx, ui_settings);
+
+    let user_dashboards = create_rw_signal(cx, load_user_dashboards());
+    provide_context(cx, user_dashboards);
+
+    let session = create_rw_signal(cx, load_session());
+    provide_context(cx, session);
+    let logged_in = move |_cx| session.with(|s| s.is_some());
+
+    let outbox = create_rw_signal(cx, load_outbox());
+    provide_context(cx, outbox);
+    let alarms = Alarms(create_rw_signal(cx, load_alarms()));
+    provide_context(cx, alarms);
+    provide_context(cx, AlertHistory(create_rw_signal(cx, load_alert_history())));
+    let calc_nodes = CalcNodes(create_rw_signal(cx, load_calc_nodes()));
+    provide_context(cx, calc_nodes);
+    let backend_online = BackendOnline(create_rw_signal(cx, window().navigator().on_line()));
+    provide_context(cx, backend_online);
+
+    let active_site = ActiveSite(create_rw_signal(cx, active_backend().id));
+    provide_context(cx, active_site);
+
+    let health = BackendHealth(create_rw_signal(cx, None));
+    provide_context(cx, health);
+
+    let node_cache = NodeCache {
+        entries: create_rw_signal(cx, load_node_cache()),
+        revalidating: create_rw_signal(cx, HashSet::new()),
+        errors: create_rw_signal(cx, HashMap::new()),
+    };
+    provide_context(cx, node_cache);
+    window_event_listener_untyped(NODE_CACHE_CHANGED_EVENT, move |_| {
+        node_cache.entries.set(load_node_cache());
+        node_cache
+            .revalidating
+            .set(REVALIDATING.with(|r| r.borrow().clone()));
+        node_cache
+            .errors
+            .set(FETCH_ERRORS.with(|errors| errors.borrow().clone()));
+    });
+
+    // Persist every settings change
+    create_effect(cx, move |_| {
+        ui_settings.with(|settings| {
+            if let Ok(raw) = serde_json::to_string(settings) {
+                local_storage_set(UI_SETTINGS_KEY, &raw);
+            }
+        })
+    });
+
+    create_effect(cx, move |_| {
+        user_dashboards.with(|boards| {
+            if let Ok(raw) = serde_json::to_string(boards) {
+                local_storage_set(USER_DASHBOARDS_KEY, &raw);
+            }
+        })
+    });
+
+    create_effect(cx, move |_| {
+        calc_nodes.0.with(|calcs| {
+            if let Ok(raw) = serde_json::to_string(calcs) {
+                local_storage_set(CALC_NODES_KEY, &raw);
+            }
+        })
+    });
+
+    // Svg icon for the home menu point
+    let home_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M2.25 12l8.954-8.955c.44-.439 1.152-.439 1.591 0L21.75 12M4.5 9.75v10.125c0 .621.504 1.125 1.125 1.125H9.75v-4.875c0-.621.504-1.125 1.125-1.125h2.25c.621 0 1.125.504 1.125 1.125V21h4.125c.621 0 1.125-.504 1.125-1.125V9.75M8.25 21h8.25" />
+    </svg>};
+
+    // Svg icon for the home menu point
+    let data_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path d="M22.485,10.975,12,17.267,1.515,10.975A1,1,0,1,0,.486,12.69l11,6.6a1,1,0,0,0,1.03,0l11-6.6a1,1,0,1,0-1.029-1.715Z"/>
+    <path d="M22.485,15.543,12,21.834,1.515,15.543A1,1,0,1,0,.486,17.258l11,6.6a1,1,0,0,0,1.03,0l11-6.6a1,1,0,1,0-1.029-1.715Z"/>
+    <path d="M12,14.773a2.976,2.976,0,0,1-1.531-.425L.485,8.357a1,1,0,0,1,0-1.714L10.469.652a2.973,2.973,0,0,1,3.062,0l9.984,5.991a1,1,0,0,1,0,1.714l-9.984,5.991A2.976,2.976,0,0,1,12,14.773ZM2.944,7.5,11.5,12.633a.974.974,0,0,0,1,0L21.056,7.5,12.5,2.367a.974.974,0,0,0-1,0h0Z"/>
+    </svg>};
+
+    // Svg icon for the all sites menu point
+    let sites_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M5.25 14.25h13.5m-13.5 0a3 3 0 01-3-3m3 3a3 3 0 100 6h13.5a3 3 0 100-6m-16.5-3a3 3 0 013-3h13.5a3 3 0 013 3m-19.5 0a4.5 4.5 0 01.9-2.7L5.737 5.1a3.375 3.375 0 012.7-1.35h7.126c1.062 0 2.062.5 2.7 1.35l2.587 3.45a4.5 4.5 0 01.9 2.7m0 0a3 3 0 01-3 3m0 3h.008v.008h-.008v-.008zm0-6h.008v.008h-.008v-.008zm-3 6h.008v.008h-.008v-.008zm0-6h.008v.008h-.008v-.008z" />
+    </svg>};
+
+    // Svg icon for the copy to site menu point
+    let copy_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M15.75 17.25v3.375c0 .621-.504 1.125-1.125 1.125h-9.75a1.125 1.125 0 01-1.125-1.125V7.875c0-.621.504-1.125 1.125-1.125H6.75a9.06 9.06 0 011.5.124m7.5 10.376h3.375c.621 0 1.125-.504 1.125-1.125V11.25c0-4.46-3.243-8.161-7.5-8.876a9.06 9.06 0 00-1.5-.124H9.375c-.621 0-1.125.504-1.125 1.125v3.5m7.5 10.375H9.375a1.125 1.125 0 01-1.125-1.125v-9.25m12 6.625v-1.875a3.375 3.375 0 00-3.375-3.375h-1.5a1.125 1.125 0 01-1.125-1.125v-1.5a3.375 3.375 0 00-3.375-3.375H9.75" />
+    </svg>};
+
+    // Svg icon for the new signal wizard menu point
+    let wizard_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M12 9v6m3-3H9m12 0a9 9 0 11-18 0 9 9 0 0118 0z" />
+    </svg>};
+
+    // Svg icon for the dashboards menu point
+    let boards_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M3.75 6A2.25 2.25 0 016 3.75h2.25A2.25 2.25 0 0110.5 6v2.25a2.25 2.25 0 01-2.25 2.25H6a2.25 2.25 0 01-2.25-2.25V6zM3.75 15.75A2.25 2.25 0 016 13.5h2.25a2.25 2.25 0 012.25 2.25V18a2.25 2.25 0 01-2.25 2.25H6A2.25 2.25 0 013.75 18v-2.25zM13.5 6a2.25 2.25 0 012.25-2.25H18A2.25 2.25 0 0120.25 6v2.25A2.25 2.25 0 0118 10.5h-2.25a2.25 2.25 0 01-2.25-2.25V6zM13.5 15.75a2.25 2.25 0 012.25-2.25H18a2.25 2.25 0 012.25 2.25V18A2.25 2.25 0 0118 20.25h-2.25A2.25 2.25 0 0113.5 18v-2.25z" />
+    </svg>};
+
+    // Svg icon for the backend status menu point
+    let status_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M3.75 13.5l10.5-11.25L12 10.5h8.25L9.75 21.75 12 13.5H3.75z" />
+    </svg>};
+
+    // Svg icon for the alarms menu point
+    let alarms_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M14.857 17.082a23.848 23.848 0 005.454-1.31A8.967 8.967 0 0118 9.75v-.7V9A6 6 0 006 9v.75a8.967 8.967 0 01-2.312 6.022c1.733.64 3.56 1.085 5.455 1.31m5.714 0a24.255 24.255 0 01-5.714 0m5.714 0a3 3 0 11-5.714 0" />
+    </svg>};
+
+    // Svg icon for the integrity menu point
+    let integrity_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M9 12.75L11.25 15 15 9.75m-3-7.036A11.959 11.959 0 013.598 6 11.99 11.99 0 003 9.749c0 5.592 3.824 10.29 9 11.623 5.176-1.332 9-6.03 9-11.622 0-1.31-.21-2.571-.598-3.751h-.152c-3.196 0-6.1-1.248-8.25-3.285z" />
+    </svg>};
+
+    // Svg icon for the audit log menu point
+    let audit_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M12 6v6h4.5m4.5 0a9 9 0 11-18 0 9 9 0 0118 0z" />
+    </svg>};
+
+    // Svg icon for the outbox menu point
+    let outbox_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M2.25 13.5h3.86a2.25 2.25 0 012.012 1.244l.256.512a2.25 2.25 0 002.013 1.244h3.218a2.25 2.25 0 002.013-1.244l.256-.512a2.25 2.25 0 012.013-1.244h3.859M12 3v8.25m0 0l-3-3m3 3l3-3M2.25 13.5v4.5A2.25 2.25 0 004.5 20.25h15A2.25 2.25 0 0021.75 18v-4.5" />
+    </svg>};
+
+    // Svg icon for the calculated nodes menu point
+    let calc_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M15.75 15.75V18m-7.5-6.75h.008v.008H8.25v-.008zm0 2.25h.008v.008H8.25V13.5zm0 2.25h.008v.008H8.25v-.008zm0 2.25h.008v.008H8.25V18zm2.498-6.75h.007v.008h-.007v-.008zm0 2.25h.007v.008h-.007V13.5zm0 2.25h.007v.008h-.007v-.008zm0 2.25h.007v.008h-.007V18zm2.504-6.75h.008v.008h-.008v-.008zm0 2.25h.008v.008h-.008V13.5zm0 2.25h.008v.008h-.008v-.008zm0 2.25h.008v.008h-.008V18zm2.498-6.75h.008v.008h-.008v-.008zm0 2.25h.008v.008h-.008V13.5zM8.25 6h7.5v2.25h-7.5V6zM12 2.25c-1.892 0-3.758.11-5.593.322C5.307 2.7 4.5 3.65 4.5 4.757V19.5a2.25 2.25 0 002.25 2.25h10.5a2.25 2.25 0 002.25-2.25V4.757c0-1.108-.806-2.057-1.907-2.185A48.507 48.507 0 0012 2.25z" />
+    </svg>};
+
+    // Svg icon for the settings menu point
+    let settings_svg = view! {cx,
+    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+    <path stroke-linecap="round" stroke-linejoin="round" d="M10.5 6h9.75M10.5 6a1.5 1.5 0 11-3 0m3 0a1.5 1.5 0 10-3 0M3.75 6H7.5m3 12h9.75m-9.75 0a1.5 1.5 0 01-3 0m3 0a1.5 1.5 0 00-3 0m-3.75 0H7.5m9-6h3.75m-3.75 0a1.5 1.5 0 01-3 0m3 0a1.5 1.5 0 00-3 0m-9.75 0h9.75" />
+    </svg>};
+
+    view! { cx,
+        <Router>
+
+        <main>
+        <div class="dark:bg-gray-800 min-h-screen">
+
+        <aside class="flex flex-col w-64 float-left h-screen px-4 py-8 overflow-y-auto bg-gray-200 border-r rtl:border-r-0 rtl:border-l dark:bg-gray-900 dark:border-gray-700 border sticky top-0">
+
+        <div class="relative mt-6">
+            <span class="mx-2 font-medium dark:text-gray-300">"DATA CONCENTRATOR UI"</span>
+
+        </div>
+
+        <SiteSwitcher/>
+
+        <div class="flex flex-col justify-between flex-1 mt-6 ">
+                <nav>
+
+                    <NavComponent href="/".to_string() text_to_show="Home".to_string() svg_to_use=home_svg />
+
+                    <NavComponent href="/sites".to_string() text_to_show="All sites".to_string() svg_to_use=sites_svg />
+
+                    {move || view! { cx,
+                        <NavComponent href=site_path(&active_site.0.get(), "/dmap") text_to_show="Data Map".to_string() svg_to_use=data_svg.clone() />
+                    }}
+
+                    {move || view! { cx,
+                        <NavComponent href=site_path(&active_site.0.get(), "/calc") text_to_show="Calculated nodes".to_string() svg_to_use=calc_svg.clone() />
+                    }}
+
+                    {move || view! { cx,
+                        <NavComponent href=site_path(&active_site.0.get(), "/copy") text_to_show="Copy to site".to_string() svg_to_use=copy_svg.clone() />
+                    }}
+
+                    {move || view! { cx,
+                        <NavComponent href=site_path(&active_site.0.get(), "/wizard") text_to_show="New signal".to_string() svg_to_use=wizard_svg.clone() />
+                    }}
+
+                    {move || view! { cx,
+                        <NavComponent href=site_path(&active_site.0.get(), "/boards") text_to_show="Dashboards".to_string() svg_to_use=boards_svg.clone() />
+                    }}
+                    // One entry per user dashboard
+                    <For
+                        each=move || user_dashboards.get()
+                        key=|board| (board.id, board.name.clone())
+                        view=move |cx, board| {
+                            let board_svg = view! {cx,
+                            <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5">
+                            <path stroke-linecap="round" stroke-linejoin="round" d="M3 13.125C3 12.504 3.504 12 4.125 12h2.25c.621 0 1.125.504 1.125 1.125v6.75C7.5 20.496 6.996 21 6.375 21h-2.25A1.125 1.125 0 013 19.875v-6.75zM9.75 8.625c0-.621.504-1.125 1.125-1.125h2.25c.621 0 1.125.504 1.125 1.125v11.25c0 .621-.504 1.125-1.125 1.125h-2.25a1.125 1.125 0 01-1.125-1.125V8.625zM16.5 4.125c0-.621.504-1.125 1.125-1.125h2.25C20.496 3 21 3.504 21 4.125v15.75c0 .621-.504 1.125-1.125 1.125h-2.25a1.125 1.125 0 01-1.125-1.125V4.125z" />
+                            </svg>};
+                            view! { cx,
+                                <NavComponent href=site_path(&board.site, &format!("/boards/{}", board.id)) text_to_show=board.name svg_to_use=board_svg />
+                            }
+                        }
+                    />
+                    <hr class="my-6 border-gray-900 dark:border-gray-600" />
+
+                    <NavComponent href="/alarms".to_string() text_to_show="Alarms".to_string() svg_to_use=alarms_svg
+                        badge=Signal::derive(cx, move || alarms.0.with(|alarms| alarms.iter().filter(|a| a.cleared_at.is_none()).count())) />
+                    <NavComponent href="/status".to_string() text_to_show="Backend status".to_string() svg_to_use=status_svg />
+                    {move || view! { cx,
+                        <NavComponent href=site_path(&active_site.0.get(), "/integrity") text_to_show="Integrity".to_string() svg_to_use=integrity_svg.clone() />
+                    }}
+                    <NavComponent href="/audit".to_string() text_to_show="Audit log".to_string() svg_to_use=audit_svg />
+                    <NavComponent href="/outbox".to_string() text_to_show="Pending changes".to_string() svg_to_use=outbox_svg />
+                    <NavComponent href="/settings".to_string() text_to_show="Settings".to_string() svg_to_use=settings_svg />
+
+                </nav>
+
+                <HealthIndicator/>
+                <OutboxIndicator/>
+                <SessionPanel/>
+        </div>
+    </aside>
+        <SessionWatcher/>
+        <OutboxWatcher/>
+        <HealthWatcher/>
+        <AlarmWatcher/>
+        <Routes>
+
+            <Route path="/login" view=LoginComponent />
+            // Everything else needs a session
+            <ProtectedRoute path="/" redirect_path="/login" condition=logged_in view=DashboardComponent />
+            <ProtectedRoute path="/settings" redirect_path="/login" condition=logged_in view=SettingsComponent />
+            <ProtectedRoute path="/audit" redirect_path="/login" condition=logged_in view=AuditLogComponent />
+            <ProtectedRoute path="/sites" redirect_path="/login" condition=logged_in view=SitesOverview />
+            <ProtectedRoute path="/status" redirect_path="/login" condition=logged_in view=BackendStatus />
+            <ProtectedRoute path="/alarms" redirect_path="/login" condition=logged_in view=AlarmsComponent />
+            <ProtectedRoute
+                path="/site/:site"
+                redirect_path="/login"
+                condition=logged_in
+                view=SiteScope
+            >
+                <Route path="" view=DashboardComponent />
+                <Route path="copy" view=CopyToSite />
+                <Route path="node/:uid" view=NodeDetail />
+                <Route path="calc" view=CalculatedNodes />
+                <Route path="wizard" view=NodeWizard />
+                <Route path="integrity" view=IntegrityReport />
+                <Route path="boards" view=UserDashboardList />
+                <Route path="boards/:board" view=UserDashboardView />
+                <Route path="dmap" view=DataNodeList>
+                    <Route path=":id" view=DataNodeInfo>
+                        <Route path="" view=DataNodeDesc />
+                        <Route path="dnodes" view=DataNodeData/>
+                        <Route path="newdnode" view=NewDnodeComp/>
+                    </Route>
+                    <Route path="" view=|cx| view! { cx,
+                        <div class="select-dnode dark:text-gray-300 m-2 p-5 rounded overflow-hidden shadow-lg border">
+                            "Select a data node to view the information."
+                        </div>
+                    }/>
+                </Route>
+            </ProtectedRoute>
+            <ProtectedRoute path="/outbox" redirect_path="/login" condition=logged_in view=OutboxComponent />
+                </Routes>
+                </div>
+
+            </main>
+
+            </Router>
+
+        }
+}
+
+// Everything the dashboard needs, fetched in one go
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SystemOverview {
+    rapi: Vec<RapiStruct>,
+    modbus: Vec<ModbusStruct>,
+    mqtt: Vec<MqttStruct>,
+    reachable: bool,
+    refreshed_at: DateTime,
+}
+
+async fn get_system_overview() -> SystemOverview {
+    get_site_overview(active_backend()).await
+}
+
+async fn get_site_overview(backend: Backend) -> SystemOverview {
+    let rapi = try_get_backend_data(&backend, "bucket").await;
+    let modbus = try_get_backend_data(&backend, "mbstuff").await;
+    let mqtt = try_get_backend_data(&backend, "mqttstuff").await;
+
+    let reachable = rapi.is_ok() && modbus.is_ok() && mqtt.is_ok();
+
+    SystemOverview {
+        rapi: rapi
+            .map(|raw| schema::decode_list(&raw).items)
+            .unwrap_or_default(),
+        modbus: modbus
+            .map(|raw| schema::decode_list(&raw).items)
+            .unwrap_or_default(),
+        mqtt: mqtt
+            .map(|raw| schema::decode_list(&raw).items)
+            .unwrap_or_default(),
+        reachable,
+        refreshed_at: DateTime::now(),
+    }
+}
+
+#[component]
+fn DashboardComponent(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+    let site = use_active_site(cx);
+    let (refresh, set_refresh) = create_signal(cx, 0);
+    let overview = create_resource(
+        cx,
+        move || (refresh.get(), site.get()),
+        |_| get_system_overview(),
+    );
+
+    let tiles = move || {
+        overview.read(cx).map(|overview| {
+            let settings = settings.get();
+            let href = |path: &str| site_path(&site.get_untracked(), path);
+            let stale = overview
+                .rapi
+                .iter()
+                .filter(|node| is_stale(node, settings.stale_after_secs))
+                .count();
+            let mapped = mapped_uids(&overview.modbus, &overview.mqtt);
+            let unmapped = overview
+                .rapi
+                .iter()
+                .filter(|node| !mapped.contains(&node.node_uid))
+                .count();
+            let known = rapi_uids(&overview.rapi);
+            let mb_missing = overview
+                .modbus
+                .iter()
+                .filter(|mb| !known.contains(&mb.mb_lock_to_uid))
+                .count();
+            let mqtt_missing = overview
+                .mqtt
+                .iter()
+                .filter(|mq| !known.contains(&mq.mqtt_lock_to_uid))
+                .count();
+            let backend = if overview.reachable {
+                "Reachable"
+            } else {
+                "Unreachable"
+            };
+
+            view! { cx,
+                <DashboardTile title="rAPI nodes".to_string() value=overview.rapi.len().to_string() href=href("/dmap/rapi/dnodes")/>
+                <DashboardTile title="Modbus mappings".to_string() value=overview.modbus.len().to_string() href=href("/dmap/mbtcp/dnodes")/>
+                <DashboardTile title="MQTT mappings".to_string() value=overview.mqtt.len().to_string() href=href("/dmap/mqtt/dnodes")/>
+                <DashboardTile title="Stale nodes".to_string() value=stale.to_string() href=href("/dmap/rapi/dnodes?filter=stale")/>
+                <DashboardTile title="Unmapped nodes".to_string() value=unmapped.to_string() href=href("/dmap/rapi/dnodes?filter=unmapped")/>
+                <DashboardTile title="Modbus mappings to missing uids".to_string() value=mb_missing.to_string() href=href("/dmap/mbtcp/dnodes?filter=missing")/>
+                <DashboardTile title="MQTT mappings to missing uids".to_string() value=mqtt_missing.to_string() href=href("/dmap/mqtt/dnodes?filter=missing")/>
+                <DashboardTile title="Backend".to_string() value=backend.to_string() href="/sites".to_string()/>
+                <DashboardTile title="Last refresh".to_string() value=format_timestamp(&overview.refreshed_at, &settings) href=href("")/>
+            }
+        })
+    };
+
+    view! { cx,
+        <div class="dashboard p-5 dark:text-gray-300">
+            <div class="flex items-center mb-4">
+                <h1 class="text-2xl font-bold">
+                    "System overview: "
+                    {move || settings.with(|s| {
+                        s.backends
+                            .iter()
+                            .find(|b| b.id == site.get())
+                            .map(|b| b.name.clone())
+                            .unwrap_or_default()
+                    })}
+                </h1>
+                <button
+                class="ml-auto bg-blue-600 text-gray-200 hover:bg-blue-900 dark:text-gray-300 font-bold py-2 px-4 rounded"
+                on:click=move |_| set_refresh.update(|n| *n += 1)
+                >
+                "Refresh"
+                </button>
+            </div>
+            <Suspense fallback=move || view! { cx, <p>"Loading..."</p> }>
+                <div class="grid grid-cols-3 grid-flow-row gap-2">{tiles}</div>
+            </Suspense>
+        </div>
+    }
+}
+
+#[component]
+fn DashboardTile(cx: Scope, title: String, value: String, href: String) -> impl IntoView {
+    view! { cx,
+        <a class="dashboard_tile block m-2 p-5 rounded overflow-hidden shadow-lg border hover:bg-gray-300 dark:hover:bg-gray-700" href={href}>
+            <span class="block text-sm font-medium uppercase">{title}</span>
+            <span class="block text-3xl font-bold mt-2">{value}</span>
+        </a>
+    }
+}
+
+#[component]
+fn DataNodeList(cx: Scope) -> impl IntoView {
+    view! { cx,
+        <div class="flex flex-wrap sticky top-0 ">
+          <section class="relative w-[100%] sticky top-0 z-10">
+            <nav class="flex bg-gray-200 dark:bg-gray-900 dark:text-gray-300 boarder sticky top-0 z-10">
+              <div class="px-5 xl:px-12 py-3 flex w-full items-center">
+                <ul class="md:flex px-4 font-semibold font-heading space-x-12">
+                <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="rapi">"rAPI"</A></li>
+                <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="mbtcp">"Modbus TCP"</A></li>
+                <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="mqtt">"MQTT"</A></li>
+                </ul>
+                </div>
+            </nav>
+          </section>
+          <Outlet/>
+        </div>
+    }
+}
+
+#[component]
+fn DataNodeInfo(cx: Scope) -> impl IntoView {
+    // we can access the :id param reactively with `use_params_map`
+    let params = use_params_map(cx);
+    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
+
+    let _name = move || match id().as_str() {
+        "rapi" => "REST API",
+        "mbtcp" => "Modbus TCP",
+        "mqtt" => "MQTT",
+        _ => "Data protocol not found!",
+    };
+
+    view! { cx,
+            <div class="dnode-info w-[100%] sticky top-12 ">
+            <div class="flex flex-wrap sticky top-12 ">
+              <section class="relative w-[100%]  sticky top-12">
+                <nav class="flex bg-gray-100 dark:bg-gray-600 dark:text-gray-300 boarder sticky top-12">
+                  <div class="px-5 xl:px-12 py-3 flex w-full items-center">
+                    <ul class="md:flex px-4 font-semibold font-heading space-x-12">
+                    <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="">"Info"</A></li>
+                    <RequiresRole role=Role::Engineer>
+                    <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="newdnode">"New"</A></li>
+                    </RequiresRole>
+                    <li><A  class="hover:text-gray-500 dark:hover:text-gray-200" href="dnodes">"Data nodes"</A></li>
+                    </ul>
+                    </div>
+                </nav>
+              </section>
+            </div>
+            <Outlet/>
+
+            </div>
+
+    }
+}
+
+#[component]
+fn DataNodeDesc(cx: Scope) -> impl IntoView {
+    // we can access the :id param reactively with `use_params_map`
+    let params = use_params_map(cx);
+    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
+
+    let dnode_descr = move || match id().as_str() {
+        "rapi" => "rAPI is the foundation for the communications of this software!",
+        "mbtcp" => "Modbus TCP \"mapper\", you can bind MB registers to the rAPI data nodes!",
+        "mqtt" => "MQTT \"mapper\", you can bind MQTT values to the rAPI data nodes!",
+        _ => "No description for this data node",
+    };
+
+    view! { cx,
+        <div class="default-info dark:text-gray-300 m-2 p-5 rounded overflow-hidden shadow-lg border" >
+        <p> {dnode_descr} </p>
+        </div>
+    }
+}
+
+#[component]
+fn DataNodeData(cx: Scope) -> impl IntoView {
+    // we can access the :id param reactively with `use_params_map`
+    let params = use_params_map(cx);
+    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
+
+    let dnode_descr = match id().as_str() {
+        "rapi" => "bucket",
+        "mbtcp" => "mbstuff",
+        "mqtt" => "mqttstuff",
+        _ => "",
+    };
+
+    let id_for_later = dnode_descr;
+
+    // ?filter=... narrows the list, used by the dashboard tiles
+    let query = use_query_map(cx);
+    let filter = move || query.with(|query| NodeFilter::from_query(query.get("filter")));
+    // ?path=... and ?tag=... narrow the rAPI list, set by the node tree and the tag list
+    let folder = move || query.with(|query| query.get("path").cloned().unwrap_or_default());
+    let tag = move || query.with(|query| query.get("tag").cloned().unwrap_or_default());
+    let filtered = move || filter() != NodeFilter::All || !folder().is_empty() || !tag().is_empty();
+    let filter_text = move || {
+        let mut parts = Vec::new();
+        if filter() != NodeFilter::All {
+            parts.push(filter().description().to_string());
+        }
+        if !folder().is_empty() {
+            parts.push(format!("in {}", folder()));
+        }
+        if !tag().is_empty() {
+            parts.push(format!("tagged {}", tag()));
+        }
+        parts.join(", ")
+    };
+
+    // Render from the shared cache right away and refresh it in the background
+    let cache = use_node_cache(cx);
+    let site = use_active_site(cx);
+    let related_endpoints = move || match filter() {
+        NodeFilter::Unmapped => vec!["mbstuff", "mqttstuff"],
+        NodeFilter::MissingUid => vec!["bucket"],
+        _ => vec![],
+    };
+    let refresh = move || {
+        revalidate(dnode_descr);
+        for endpoint in related_endpoints() {
+            revalidate(endpoint);
+        }
+    };
+    create_effect(cx, move |_| {
+        site.track();
+        refresh();
+    });
+    // Shown instead of the list when the fetch failed and nothing is cached
+    let fetch_error = move || {
+        let site = site.get();
+        let keys: Vec<String> = std::iter::once(dnode_descr)
+            .chain(related_endpoints())
+            .map(|endpoint| cache_key(&site, endpoint))
+            .collect();
+        if cache.revalidating.with(|r| keys.iter().any(|key| r.contains(key))) {
+            return None;
+        }
+        cache
+            .errors
+            .with(|errors| keys.iter().find_map(|key| errors.get(key).cloned()))
+    };
+
+    let data = create_memo(cx, move |_| {
+        let key = cache_key(&site.get(), dnode_descr);
+        cache
+            .entries
+            .with(|entries| entries.get(&key).map(|cached| cached.raw.clone()))
+    });
+    let related = create_memo(cx, move |_| {
+        let site = site.get();
+        cache.entries.with(|entries| match filter() {
+            NodeFilter::Unmapped => Some(mapped_uids(
+                &cached_list::<ModbusStruct>(entries, &cache_key(&site, "mbstuff"))?,
+                &cached_list::<MqttStruct>(entries, &cache_key(&site, "mqttstuff"))?,
+            )),
+            NodeFilter::MissingUid => Some(rapi_uids(&cached_list::<RapiStruct>(
+                entries,
+                &cache_key(&site, "bucket"),
+            )?)),
+            _ => Some(HashSet::new()),
+        })
+    });
+
+    let nodes = Signal::derive(cx, move || {
+        let key = cache_key(&site.get(), "bucket");
+        cache
+            .entries
+            .with(|entries| cached_list::<RapiStruct>(entries, &key))
+            .unwrap_or_default()
+    });
+
+    let (now, set_now) = create_signal(cx, DateTime::now());
+    if let Ok(handle) = set_interval_with_handle(
+        move || set_now.set(DateTime::now()),
+        std::time::Duration::from_secs(1),
+    ) {
+        on_cleanup(cx, move || handle.clear());
+    }
+    let cache_state = move || {
+        let key = cache_key(&site.get(), dnode_descr);
+        let refreshing = cache.revalidating.with(|r| r.contains(&key));
+        let fetched_at = cache
+            .entries
+            .with(|entries| entries.get(&key).map(|cached| cached.fetched_at))?;
+        let age = age_text(&fetched_at, &now.get());
+        let failed = cache.errors.with(|errors| errors.get(&key).cloned());
+        Some(match (refreshing, failed) {
+            (true, _) => format!("Cached data from {age}, refreshing..."),
+            (false, Some(err)) => format!("Cached data from {age}, refreshing failed: {err}"),
+            (false, None) => format!("Updated {age}"),
+        })
+    };
+
+    view! { cx,
+        <div class="contact-info flex items-start">
+        {(dnode_descr == "bucket").then(|| view! { cx, <NodeTree nodes/> })}
+        <div class="flex-1">
+        <Show when=filtered fallback=|_| ()>
+            <div class="dark:text-gray-300 m-2 p-3 rounded border flex items-center">
+                <span>"Filtered: "{filter_text}</span>
+                <A class="ml-4 underline hover:text-gray-500" href="">"Clear filter"</A>
+            </div>
+        </Show>
+        {(dnode_descr == "bucket").then(|| view! { cx, <TagFilter nodes/> })}
+        <p class="mx-2 text-sm text-gray-500">{cache_state}</p>
+        <p>    {move || match (data.get(), related.get()) {
+            (Some(data), Some(related)) => view! { cx,
+                <ShowData data id_for_later filter=filter() related folder=folder() tag=tag()/>
+            }.into_view(cx),
+            _ => match fetch_error() {
+                Some(err) => view! { cx,
+                    <div class="fetch_error m-2 p-3 rounded border border-red-500 dark:text-gray-300">
+                        <span>"Could not load the data: "{err}</span>
+                        <button
+                        class="ml-4 bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-1 px-2 rounded"
+                        on:click=move |_| refresh()
+                        >
+                        "Retry"
+                        </button>
+                    </div>
+                }.into_view(cx),
+                None => view! { cx, <p>"Loading..."</p> }.into_view(cx),
+            },
+        }}  </p>
+        </div>
+        </div>
+    }
+}
+
+// The current page with the query parameter `key` set, or removed for None
+fn href_with_query(cx: Scope, key: &str, value: Option<&str>) -> String {
+    let location = use_location(cx);
+    let mut query = location.query.get();
+    match value {
+        Some(value) => {
+            query.insert(key.to_string(), value.to_string());
+        }
+        None => {
+            query.remove(key);
+        }
+    }
+    format!("{}{}", location.pathname.get(), query.to_query_string())
+}
+
+// A folder of the node tree and the number of nodes in and below it
+#[derive(Debug, Default)]
+struct PathFolder {
+    count: usize,
+    children: BTreeMap<String, PathFolder>,
+}
+
+fn path_tree(nodes: &[RapiStruct]) -> PathFolder {
+    let mut root = PathFolder::default();
+    for node in nodes {
+        root.count += 1;
+        let mut folder = &mut root;
+        for segment in path_segments(&node.node_path) {
+            folder = folder.children.entry(segment.to_string()).or_default();
+            folder.count += 1;
+        }
+    }
+    root
+}
+
+// Folders below the selected one stay collapsed
+fn path_folder_views(cx: Scope, folder: &PathFolder, prefix: &str, selected: &str) -> Vec<View> {
+    folder
+        .children
+        .iter()
+        .map(|(name, child)| {
+            let path = if prefix.is_empty() {
+                name.clone()
+            } else {
+                format!("{prefix}/{name}")
+            };
+            let class = if path == selected {
+                "font-bold underline"
+            } else {
+                "hover:text-gray-500"
+            };
+            let label = format!("{name} ({})", child.count);
+            let children = (!child.children.is_empty() && in_path(selected, &path)).then(|| {
+                view! { cx, <ul class="ml-3">{path_folder_views(cx, child, &path, selected)}</ul> }
+            });
+            view! { cx,
+                <li>
+                    <A class=class href=href_with_query(cx, "path", Some(&path))>{label}</A>
+                    {children}
+                </li>
+            }
+            .into_view(cx)
+        })
+        .collect()
+}
+
+// Folders of the rAPI nodes by their path, picking one filters the list
+#[component]
+fn NodeTree(cx: Scope, nodes: Signal<Vec<RapiStruct>>) -> impl IntoView {
+    let query = use_query_map(cx);
+    let selected = move || query.with(|query| query.get("path").cloned().unwrap_or_default());
+
+    view! { cx,
+        <nav class="node_tree w-64 shrink-0 m-2 p-3 rounded border dark:text-gray-300 text-sm">
+            {move || {
+                let tree = nodes.with(|nodes| path_tree(nodes));
+                let selected = selected();
+                let unfiled = tree.count - tree.children.values().map(|c| c.count).sum::<usize>();
+                let all_class = if selected.is_empty() { "font-bold underline" } else { "hover:text-gray-500" };
+                view! { cx,
+                    <A class=all_class href=href_with_query(cx, "path", None)>
+                        {format!("All nodes ({})", tree.count)}
+                    </A>
+                    <ul class="ml-3">{path_folder_views(cx, &tree, "", &selected)}</ul>
+                    {(unfiled > 0).then(|| view! { cx,
+                        <p class="mt-2 text-gray-500">{format!("{unfiled} without a path")}</p>
+                    })}
+                }
+            }}
+        </nav>
+    }
+}
+
+// Every tag used by the rAPI nodes, picking one filters the list
+#[component]
+fn TagFilter(cx: Scope, nodes: Signal<Vec<RapiStruct>>) -> impl IntoView {
+    let query = use_query_map(cx);
+    let selected = move || query.with(|query| query.get("tag").cloned().unwrap_or_default());
+
+    move || {
+        let mut tags: BTreeMap<String, usize> = BTreeMap::new();
+        nodes.with(|nodes| {
+            for tag in nodes.iter().flat_map(node_tags) {
+                *tags.entry(tag).or_default() += 1;
+            }
+        });
+        let selected = selected();
+        (!tags.is_empty()).then(|| {
+            let chips = tags
+                .into_iter()
+                .map(|(tag, count)| {
+                    // Picking the selected tag again clears the filter
+                    let (class, href) = if tag == selected {
+                        ("tag_chip inline-block mr-2 mb-1 px-2 rounded-full bg-blue-600 text-gray-200", href_with_query(cx, "tag", None))
+                    } else {
+                        ("tag_chip inline-block mr-2 mb-1 px-2 rounded-full bg-gray-300 dark:bg-gray-600 hover:bg-gray-400", href_with_query(cx, "tag", Some(&tag)))
+                    };
+                    view! { cx, <A class=class href>{format!("{tag} ({count})")}</A> }
+                })
+                .collect::<Vec<_>>();
+            view! { cx,
+                <div class="tag_filter mx-2 mb-2 text-sm dark:text-gray-300">
+                    <span class="font-bold mr-2">"Tags:"</span>
+                    {chips}
+                </div>
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NodeFilter {
+    All,
+    Stale,
+    Unmapped,
+    MissingUid,
+}
+
+impl NodeFilter {
+    fn from_query(value: Option<&String>) -> Self {
+        match value.map(|v| v.as_str()) {
+            Some("stale") => NodeFilter::Stale,
+            Some("unmapped") => NodeFilter::Unmapped,
+            Some("missing") => NodeFilter::MissingUid,
+            _ => NodeFilter::All,
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            NodeFilter::All => "all",
+            NodeFilter::Stale => "stale nodes",
+            NodeFilter::Unmapped => "nodes without Modbus or MQTT mapping",
+            NodeFilter::MissingUid => "mappings locked to a missing uid",
+        }
+    }
+}
+
+#[component]
+fn ShowData(
+    cx: Scope,
+    data: String,
+    id_for_later: &'static str,
+    #[prop(optional)] filter: Option<NodeFilter>,
+    #[prop(optional)] related: HashSet<String>,
+    // Only rAPI nodes in this folder, "" for all
+    #[prop(optional)] folder: String,
+    // Only rAPI nodes with this tag, "" for all
+    #[prop(optional)] tag: String,
+) -> impl IntoView {
+    let filter = filter.unwrap_or(NodeFilter::All);
+    let stale_after_secs = use_ui_settings(cx).get_untracked().stale_after_secs;
+
+    if id_for_later == "mbstuff" {
+        let decoded = schema::decode_list::<ModbusStruct>(&data);
+        let warnings = decoded.warnings;
+        let malformed_view = malformed_cards::<ModbusStruct>(cx, decoded.malformed);
+        let mapped_view = decoded
+            .items
+            .into_iter()
+            .zip(decoded.sources)
+            .filter(|(mb, _)| filter != NodeFilter::MissingUid || !related.contains(&mb.mb_lock_to_uid))
+            .map(|(mbstruct, source)| {
+                view! { cx,
+                    <ShowMbSingleData mbstruct source/>
+                }
+            })
+            .collect::<Vec<_>>();
+        view! { cx,
+            <div>
+                <SchemaWarnings warnings/>
+                <div class="grid grid-cols-3 grid-flow-row gap-2">{mapped_view}{malformed_view}</div>
+            </div>
+        }
+    } else if id_for_later == "mqttstuff" {
+        let decoded = schema::decode_list::<MqttStruct>(&data);
+        let warnings = decoded.warnings;
+        let malformed_view = malformed_cards::<MqttStruct>(cx, decoded.malformed);
+        let mapped_view = decoded
+            .items
+            .into_iter()
+            .zip(decoded.sources)
+            .filter(|(mq, _)| filter != NodeFilter::MissingUid || !related.contains(&mq.mqtt_lock_to_uid))
+            .map(|(mqttstruct, source)| {
+                view! { cx,
+                    <ShowMqttSingleData mqttstruct source/>
+                }
+            })
+            .collect::<Vec<_>>();
+        view! { cx,
+            <div>
+                <SchemaWarnings warnings/>
+                <div class="grid grid-cols-3 grid-flow-row gap-2">{mapped_view}{malformed_view}</div>
+            </div>
+        }
+    } else if id_for_later == "bucket" {
+        let decoded = schema::decode_list::<RapiStruct>(&data);
+        let warnings = decoded.warnings;
+        let malformed_view = malformed_cards::<RapiStruct>(cx, decoded.malformed);
+        let mapped_view = decoded
+            .items
+            .into_iter()
+            .zip(decoded.sources)
+            .filter(|(node, _)| match filter {
+                NodeFilter::Stale => is_stale(node, stale_after_secs),
+                NodeFilter::Unmapped => !related.contains(&node.node_uid),
+                _ => true,
+            })
+            .filter(|(node, _)| in_path(&node.node_path, &folder))
+            .filter(|(node, _)| tag.is_empty() || node_tags(node).contains(&tag))
+            .map(|(rapistruct, source)| {
+                view! { cx,
+                    <ShowRapiSingleData rapistruct source/>
+                }
+            })
+            .collect::<Vec<_>>();
+        view! { cx,
+            <div>
+                <SchemaWarnings warnings/>
+                <div class="grid grid-cols-2 grid-flow-row gap-2">{mapped_view}{malformed_view}</div>
+            </div>
+        }
+    } else {
+        view! { cx,
+            <div>"No map"</div>
+        }
+    }
+}
+
+fn malformed_cards<T: schema::Document>(cx: Scope, malformed: Vec<schema::Malformed>) -> Vec<View> {
+    malformed
+        .into_iter()
+        .map(|record| view! { cx, <MalformedRecord kind=T::KIND record/> }.into_view(cx))
+        .collect()
+}
+
+// Takes the place of a document the backend sent in a shape we cannot decode
+#[component]
+fn MalformedRecord(cx: Scope, kind: &'static str, record: schema::Malformed) -> impl IntoView {
+    view! { cx,
+        <div class="malformed_record m-2 p-5 rounded overflow-hidden shadow-lg border border-red-500">
+            <p class="font-bold text-red-600">{format!("Malformed {kind} (#{})", record.index)}</p>
+            <p class="text-sm dark:text-gray-300">{record.error}</p>
+            <pre class="mt-2 p-2 text-xs overflow-x-auto rounded bg-gray-100 text-gray-900">{record.raw}</pre>
+        </div>
+    }
+}
+
+// Shown above a list when the backend answer did not match the expected schema
+#[component]
+fn SchemaWarnings(cx: Scope, warnings: Vec<String>) -> impl IntoView {
+    (!warnings.is_empty()).then(|| {
+        let lines = warnings
+            .into_iter()
+            .map(|warning| view! { cx, <li>{warning}</li> })
+            .collect::<Vec<_>>();
+        view! { cx,
+            <div class="schema_warnings m-2 p-3 rounded border border-yellow-500 bg-yellow-100 text-gray-900">
+                <span class="font-bold">"The backend answer does not fully match the expected format:"</span>
+                <ul class="list-disc ml-6 text-sm">{lines}</ul>
+            </div>
+        }
+    })
+}
+
+#[component]
+fn ShowMbSingleData(cx: Scope, mbstruct: ModbusStruct, source: serde_json::Value) -> impl IntoView {
+    let site = use_active_site(cx).get_untracked();
+    let clone_href = clone_href(&site_path(&site, "/dmap/mbtcp/newdnode"), modbus_to_map(&mbstruct), "mb_register");
+
+    view! { cx,
+        <div class="mb_data_single m-2 p-5 rounded overflow-hidden shadow-lg border">
+            <ShowSingleDataSpan row_desc="MB locked to dNode: ".to_string() data_to_show=mbstruct.mb_lock_to_uid/>
+            <ShowSingleDataSpan row_desc="MB ip: ".to_string() data_to_show=mbstruct.mb_ip/>
+            <ShowSingleDataSpan row_desc="MB port: ".to_string() data_to_show=mbstruct.mb_port/>
+            <ShowSingleDataSpan row_desc="MB register: ".to_string() data_to_show=mbstruct.mb_register/>
+            <ShowSingleDataSpan row_desc="MB read/write: ".to_string() data_to_show=mbstruct.mb_rw/>
+            <RequiresRole role=Role::Engineer>
+                <CardActionLink href=clone_href.clone() text="Clone"/>
+            </RequiresRole>
+            <RecordJson target=AuditTarget::ModbusMapping source/>
+        </div>
+
+    }
+}
+
+#[component]
+fn ShowMqttSingleData(cx: Scope, mqttstruct: MqttStruct, source: serde_json::Value) -> impl IntoView {
+    let site = use_active_site(cx).get_untracked();
+    let clone_href = clone_href(&site_path(&site, "/dmap/mqtt/newdnode"), mqtt_to_map(&mqttstruct), "mqtt_topic");
+
+    view! { cx,
+        <div class="mqtt_data_single m-2 p-5 rounded overflow-hidden shadow-lg border">
+            <ShowSingleDataSpan row_desc="MQTT locked to dNode: ".to_string() data_to_show=mqttstruct.mqtt_lock_to_uid/>
+            <ShowSingleDataSpan row_desc="MQTT ip: ".to_string() data_to_show=mqttstruct.mqtt_ip/>
+            <ShowSingleDataSpan row_desc="MQTT topic: ".to_string() data_to_show=mqttstruct.mqtt_topic/>
+            <ShowSingleDataSpan row_desc="MQTT topic modifier: ".to_string() data_to_show=mqttstruct.mqtt_topic_modif/>
+            <ShowSingleDataSpan row_desc="MQTT read/write: ".to_string() data_to_show=mqttstruct.mqtt_rw/>
+            <RequiresRole role=Role::Engineer>
+                <CardActionLink href=clone_href.clone() text="Clone"/>
+            </RequiresRole>
+            <RecordJson target=AuditTarget::MqttMapping source/>
+        </div>
+    }
+}
+
+#[component]
+fn ShowRapiSingleData(cx: Scope, rapistruct: RapiStruct, source: serde_json::Value) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+    // Follows changes of the timezone and format settings
+    let node_last_update = rapistruct.node_last_update;
+    let last_update = Signal::derive(cx, move || format_timestamp(&node_last_update, &settings.get()));
+    let site = use_active_site(cx).get_untracked();
+    let clone_href = clone_href(&site_path(&site, "/dmap/rapi/newdnode"), rapi_to_clone_map(&rapistruct), "node_name");
+    let node_tags = node_tags(&rapistruct);
+    let detail_href = site_path(&site, &format!("/node/{}", rapistruct.node_uid));
+    let value = value_with_unit(&rapistruct);
+
+    view! { cx,
+        <div class="rapi_data_single m-2 p-5 rounded overflow-hidden shadow-lg border">
+            {(!rapistruct.node_description.is_empty()).then(|| view! { cx,
+                <p class="node_description mb-4 dark:text-gray-300 italic">{rapistruct.node_description.clone()}</p>
+            })}
+            <ShowSingleDataSpan row_desc="Data node uid: ".to_string() data_to_show=rapistruct.node_uid/>
+            <ShowSingleDataSpan row_desc="Data node value: ".to_string() data_to_show=value/>
+            <ShowSingleDataSpan row_desc="Data node last updated: ".to_string() data_to_show=last_update/>
+            <ShowSingleDataSpan row_desc="Data node name: ".to_string() data_to_show=rapistruct.node_name/>
+            <ShowSingleDataSpan row_desc="Data node read/write ? : ".to_string() data_to_show=rapistruct.node_rw_direction/>
+            <ShowSingleDataSpan row_desc="Data node path: ".to_string() data_to_show=rapistruct.node_path/>
+            <ShowSingleDataSpan row_desc="Data node tags: ".to_string() data_to_show=node_tags.join(", ")/>
+            <ShowSingleDataSpan row_desc="Source device: ".to_string() data_to_show=rapistruct.node_device/>
+            <ShowSingleDataSpan row_desc="Owner: ".to_string() data_to_show=rapistruct.node_owner/>
+            <CardActionLink href=detail_href text="Details"/>
+            <RequiresRole role=Role::Engineer>
+                <CardActionLink href=clone_href.clone() text="Clone"/>
+            </RequiresRole>
+            <RecordJson target=AuditTarget::RapiNode source/>
+        </div>
+    }
+}
+
+#[component]
+fn CardActionLink(cx: Scope, href: String, text: &'static str) -> impl IntoView {
+    view! { cx,
+        <A class="inline-block mr-2 bg-gray-300 dark:bg-gray-600 dark:text-gray-300 hover:bg-gray-400 font-medium py-1 px-3 rounded" href=href>
+            {text}
+        </A>
+    }
+}
+
+// Fields the backend fills in itself, left out when posting an edited document
+const BACKEND_SET_FIELDS: &[&str] = &["node_last_update"];
+
+// Splits JSON text into (class, text) pieces for highlighting. Does not validate,
+// anything it does not recognise is passed through unstyled.
+fn highlight_json(raw: &str) -> Vec<(&'static str, String)> {
+    let chars: Vec<char> = raw.chars().collect();
+    let mut pieces: Vec<(&'static str, String)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let class = match chars[i] {
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                // A string followed by a colon is a key
+                match chars[i..].iter().find(|c| !c.is_whitespace()) {
+                    Some(':') => "text-purple-700",
+                    _ => "text-green-700",
+                }
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || "+-.".contains(chars[i])) {
+                    i += 1;
+                }
+                "text-blue-700"
+            }
+            c if c.is_ascii_alphabetic() => {
+                while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                "text-orange-700"
+            }
+            _ => {
+                i += 1;
+                ""
+            }
+        };
+        let text: String = chars[start..i].iter().collect();
+        match pieces.last_mut() {
+            Some((last, joined)) if last.is_empty() && class.is_empty() => joined.push_str(&text),
+            _ => pieces.push((class, text)),
+        }
+    }
+    pieces
+}
+
+fn check_edited(target: AuditTarget, raw: &str) -> Result<schema::Checked, String> {
+    match target {
+        AuditTarget::RapiNode => schema::check_document::<RapiStruct>(raw),
+        AuditTarget::ModbusMapping => schema::check_document::<ModbusStruct>(raw),
+        AuditTarget::MqttMapping => schema::check_document::<MqttStruct>(raw),
+    }
+}
+
+// The string fields of a document, as the endpoints take them
+fn string_fields(document: &serde_json::Value) -> HashMap<String, String> {
+    document
+        .as_object()
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Turns a hand-edited document into the body to post plus warnings about it.
+// Fails when the document does not match the struct of `target` or when it
+// would change the uid of the node, which writes to a different node.
+fn edited_body(
+    target: AuditTarget,
+    raw: &str,
+    original: &HashMap<String, String>,
+) -> Result<(HashMap<String, String>, Vec<String>), String> {
+    let checked = check_edited(target, raw)?;
+    if let Some(name) = checked
+        .missing
+        .iter()
+        .find(|name| !BACKEND_SET_FIELDS.contains(&name.as_str()))
+    {
+        return Err(format!("Missing field `{name}`"));
+    }
+
+    let fields: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(raw).map_err(|err| err.to_string())?;
+    let mut body = HashMap::new();
+    for (name, value) in fields {
+        if BACKEND_SET_FIELDS.contains(&name.as_str()) {
+            continue;
+        }
+        match value {
+            serde_json::Value::String(value) => {
+                body.insert(name, value);
+            }
+            _ => return Err(format!("`{name}` must be a string, the backend only stores strings")),
+        }
+    }
+    if target == AuditTarget::RapiNode && body.get("node_uid") != original.get("node_uid") {
+        return Err("`node_uid` can not be changed".to_string());
+    }
+
+    let warnings = checked
+        .unknown
+        .into_iter()
+        .map(|name| format!("Unknown field `{name}` will be posted as it is"))
+        .collect();
+    Ok((body, warnings))
+}
+
+// Posts a hand-edited node, /u overwrites it. The node is read again first and
+// keeps its current value unless the edit changed it. Mappings have no update
+// endpoint, so they are not editable.
+async fn post_edited(original: &HashMap<String, String>, mut body: HashMap<String, String>) -> Result<(), String> {
+    let uid = body.get("node_uid").cloned().unwrap_or_default();
+    let fresh = fetch_node(&active_backend(), &uid).await?;
+    if body.get("node_val") == original.get("node_val") {
+        body.insert("node_val".to_string(), fresh.node_val.clone());
+    }
+    let hmap: HashMap<&str, String> = body
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.clone()))
+        .collect();
+    let resp = audited_post_now(
+        &api_url("/u"),
+        hmap,
+        AuditAction::Update,
+        AuditTarget::RapiNode,
+        serde_json::to_value(&fresh).ok(),
+    )
+    .await;
+    match resp.as_str() {
+        "200" => Ok(()),
+        _ => Err(unsent_reason(&resp)),
+    }
+}
+
+#[component]
+fn JsonHighlight(cx: Scope, raw: String) -> impl IntoView {
+    let pieces = highlight_json(&raw)
+        .into_iter()
+        .map(|(class, text)| view! { cx, <span class=class>{text}</span> })
+        .collect::<Vec<_>>();
+
+    view! { cx,
+        <pre class="json_view mt-2 p-2 text-xs overflow-x-auto rounded bg-gray-100 text-gray-900">{pieces}</pre>
+    }
+}
+
+// "View JSON" toggle of a card: the document as the backend stores it,
+// including fields the UI ignores, and an editor for engineers
+#[component]
+fn RecordJson(cx: Scope, target: AuditTarget, source: serde_json::Value) -> impl IntoView {
+    let (showing, set_showing) = create_signal(cx, false);
+    let (editing, set_editing) = create_signal(cx, false);
+    let can_write = use_capability(cx, "write");
+    let raw = serde_json::to_string_pretty(&source).unwrap_or_default();
+
+    view! { cx,
+        <div class="mt-2">
+            <button
+            class="record_json_toggle inline-block mr-2 bg-gray-300 dark:bg-gray-600 dark:text-gray-300 hover:bg-gray-400 font-medium py-1 px-3 rounded"
+            on:click=move |_| {
+                set_editing.set(false);
+                set_showing.update(|showing| *showing = !*showing);
+            }
+            >
+            {move || if showing.get() { "Hide JSON" } else { "View JSON" }}
+            </button>
+            <RequiresRole role=Role::Engineer>
+                <Show
+                when=move || target == AuditTarget::RapiNode && can_write.get() && !editing.get()
+                fallback=|_| ()
+                >
+                    <button
+                    class="record_json_edit inline-block mr-2 bg-gray-300 dark:bg-gray-600 dark:text-gray-300 hover:bg-gray-400 font-medium py-1 px-3 rounded"
+                    on:click=move |_| {
+                        set_showing.set(false);
+                        set_editing.set(true);
+                    }
+                    >
+                    "Edit JSON"
+                    </button>
+                </Show>
+            </RequiresRole>
+            {
+                let raw = raw.clone();
+                move || showing.get().then(|| view! { cx, <JsonHighlight raw=raw.clone()/> })
+            }
+            {
+                let source = source.clone();
+                move || editing.get().then(|| view! { cx,
+                    <JsonEditor target source=source.clone() on_close=move || set_editing.set(false)/>
+                })
+            }
+        </div>
+    }
+}
+
+// Hand editing of one document, checked against the struct before it can be posted
+#[component]
+fn JsonEditor<F>(cx: Scope, target: AuditTarget, source: serde_json::Value, on_close: F) -> impl IntoView
+where
+    F: Fn() + Copy + 'static,
+{
+    let text = create_rw_signal(cx, serde_json::to_string_pretty(&source).unwrap_or_default());
+    let (status, set_status) = create_signal(cx, String::new());
+    let original = string_fields(&source);
+    let checked = {
+        let original = original.clone();
+        create_memo(cx, move |_| text.with(|raw| edited_body(target, raw, &original)))
+    };
+    let rows = move || text.with(|raw| raw.lines().count() + 1);
+
+    let on_post = move |_| {
+        let Ok((body, _)) = checked.get_untracked() else {
+            return;
+        };
+        if body == original {
+            set_status.set("Nothing changed".to_string());
+            return;
+        }
+        let original = original.clone();
+        set_status.set("Posting...".to_string());
+        spawn_local(async move {
+            set_status.set(match post_edited(&original, body).await {
+                Ok(()) => "Saved".to_string(),
+                Err(err) => format!("Not saved: {err}"),
+            });
+        });
+    };
+
+    let problems = move || match checked.get() {
+        Ok((_, warnings)) => warnings
+            .into_iter()
+            .map(|warning| view! { cx, <li class="text-yellow-600">{warning}</li> })
+            .collect::<Vec<_>>(),
+        Err(err) => vec![view! { cx, <li class="json_error text-red-600">{err}</li> }],
+    };
+
+    view! { cx,
+        <div class="json_editor_box mt-2">
+            // The textarea is transparent over the highlighted copy of its text
+            <div class="relative font-mono text-xs">
+                <pre aria-hidden="true"
+                class="absolute inset-0 m-0 p-2 border-2 border-transparent whitespace-pre-wrap break-words rounded bg-gray-100 text-gray-900 pointer-events-none">
+                    {move || text.with(|raw| highlight_json(raw))
+                        .into_iter()
+                        .map(|(class, text)| view! { cx, <span class=class>{text}</span> })
+                        .collect::<Vec<_>>()}
+                </pre>
+                <textarea
+                class="json_editor relative block w-full m-0 p-2 border-2 border-gray-200 rounded whitespace-pre-wrap break-words bg-transparent text-transparent caret-black resize-none overflow-hidden font-mono text-xs"
+                spellcheck="false"
+                rows=rows
+                prop:value=move || text.get()
+                on:input=move |ev| text.set(event_target_value(&ev))
+                ></textarea>
+            </div>
+            <ul class="mt-1 text-sm">{problems}</ul>
+            <p class="text-xs text-gray-500">
+                "node_last_update is set by the backend and not posted. An unchanged node_val is replaced by the current value before posting."
+            </p>
+            <button
+            class="json_post bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-1 px-3 rounded mt-2 mr-2 disabled:opacity-50"
+            disabled=move || checked.with(|checked| checked.is_err())
+            on:click=on_post
+            >
+            "Post"
+            </button>
+            <button
+            class="bg-gray-300 dark:bg-gray-600 dark:text-gray-300 hover:bg-gray-400 font-medium py-1 px-3 rounded mt-2"
+            on:click=move |_| on_close()
+            >
+            "Close"
+            </button>
+            <span class="ml-2 dark:text-gray-300">{move || status.get()}</span>
+        </div>
+    }
+}
+
+#[component]
+fn ShowSingleDataSpan(cx: Scope, row_desc: String, #[prop(into)] data_to_show: MaybeSignal<String>) -> impl IntoView {
+    view! {cx,
+        <div class="md:flex md:items-center mb-6 ">
+
+            <div class="md:w-1/2">
+                <label class="block dark:text-gray-300 font-bold md:text-right mb-1 md:mb-0 pr-4" for="uid">
+                    {row_desc}
+                </label>
+            </div>
+            <div class="md:w-1/2">
+                <span
+                class="appearance-none dark:text-gray-300 border-2 border-gray-200 rounded w-full py-2 px-2 leading-tight focus:outline-none focus:bg-white focus:border-gray-700"
+                >
+                    {move || data_to_show.get()}
+                </span>
+            </div>
+       </div>
+    }
+}
+
+// One rAPI node with all of its metadata and the mappings locked to it
+#[component]
+fn NodeDetail(cx: Scope) -> impl IntoView {
+    let params = use_params_map(cx);
+    let uid = move || params.with(|params| params.get("uid").cloned().unwrap_or_default());
+    let cache = use_node_cache(cx);
+    let site = use_active_site(cx);
+    let settings = use_ui_settings(cx);
+
+    create_effect(cx, move |_| {
+        site.track();
+        for endpoint in ["bucket", "mbstuff", "mqttstuff"] {
+            revalidate(endpoint);
+        }
+    });
+
+    // None while the node list is loading
+    let node = move || {
+        let key = cache_key(&site.get(), "bucket");
+        let nodes = cache.entries.with(|entries| cached_list::<RapiStruct>(entries, &key))?;
+        Some(nodes.into_iter().find(|node| node.node_uid == uid()))
+    };
+    let mappings = move || {
+        let site = site.get();
+        let uid = uid();
+        cache.entries.with(|entries| {
+            let modbus = cached_list::<ModbusStruct>(entries, &cache_key(&site, "mbstuff")).unwrap_or_default();
+            let mqtt = cached_list::<MqttStruct>(entries, &cache_key(&site, "mqttstuff")).unwrap_or_default();
+            let mut lines: Vec<String> = modbus
+                .into_iter()
+                .filter(|mb| mb.mb_lock_to_uid == uid)
+                .map(|mb| format!("Modbus {}:{} register {} ({})", mb.mb_ip, mb.mb_port, mb.mb_register, mb.mb_rw))
+                .collect();
+            lines.extend(
+                mqtt.into_iter()
+                    .filter(|mq| mq.mqtt_lock_to_uid == uid)
+                    .map(|mq| format!("MQTT {} {}{} ({})", mq.mqtt_ip, mq.mqtt_topic, mq.mqtt_topic_modif, mq.mqtt_rw)),
+            );
+            lines
+        })
+    };
+    let calcs = use_calc_nodes(cx);
+    let used_by = move || {
+        let site = site.get();
+        let uid = uid();
+        calcs.with(|calcs| {
+            calcs
+                .iter()
+                .filter(|calc| calc.site == site)
+                .filter(|calc| expr::parse(&calc.expression).is_ok_and(|parsed| parsed.refs().contains(&uid)))
+                .map(|calc| calc.name.clone())
+                .collect::<Vec<_>>()
+        })
+    };
+    // The form is only rebuilt when another node is opened, not on every refresh
+    let shown_uid = create_memo(cx, move |_| node().flatten().map(|node| node.node_uid));
+
+    let details = move || match node() {
+        None => view! { cx, <p class="dark:text-gray-300 m-3">"Loading..."</p> }.into_view(cx),
+        Some(None) => view! { cx,
+            <p class="dark:text-gray-300 m-3">{format!("There is no rAPI node with uid {} on this site", uid())}</p>
+        }
+        .into_view(cx),
+        Some(Some(node)) => {
+            let last_update = format_timestamp(&node.node_last_update, &settings.get());
+            let source = serde_json::to_value(&node).unwrap_or_default();
+            view! { cx,
+                <h2 class="font-bold text-xl mb-2">{node.node_name.clone()}</h2>
+                <p class="node_description mb-4 italic">{node.node_description.clone()}</p>
+                <ShowSingleDataSpan row_desc="Data node uid: ".to_string() data_to_show=node.node_uid.clone()/>
+                <ShowSingleDataSpan row_desc="Data node value: ".to_string() data_to_show=value_with_unit(&node)/>
+                <ShowSingleDataSpan row_desc="Data node last updated: ".to_string() data_to_show=last_update/>
+                <ShowSingleDataSpan row_desc="Data node read/write ? : ".to_string() data_to_show=node.node_rw_direction.clone()/>
+                <ShowSingleDataSpan row_desc="Data node path: ".to_string() data_to_show=node.node_path.clone()/>
+                <ShowSingleDataSpan row_desc="Data node tags: ".to_string() data_to_show=node_tags(&node).join(", ")/>
+                <ShowSingleDataSpan row_desc="Source device: ".to_string() data_to_show=node.node_device.clone()/>
+                <ShowSingleDataSpan row_desc="Owner: ".to_string() data_to_show=node.node_owner.clone()/>
+                <ShowSingleDataSpan row_desc="Alarm limits: ".to_string() data_to_show=alarm_limits_text(&node)/>
+                <h3 class="font-bold mb-2">"Notes"</h3>
+                <p class="node_notes mb-4 whitespace-pre-wrap">{node.node_notes.clone()}</p>
+                <RecordJson target=AuditTarget::RapiNode source/>
+            }
+            .into_view(cx)
+        }
+    };
+
+    view! { cx,
+        <div class="node_detail m-5 p-5 max-w-3xl rounded overflow-hidden shadow-lg border dark:text-gray-300">
+            {details}
+            <h3 class="font-bold mt-4 mb-2">"Mappings"</h3>
+            <ul class="list-disc ml-6">
+                {move || {
+                    let lines = mappings();
+                    if lines.is_empty() {
+                        vec![view! { cx, <li>"No Modbus or MQTT mapping is locked to this node"</li> }]
+                    } else {
+                        lines.into_iter().map(|line| view! { cx, <li>{line}</li> }).collect::<Vec<_>>()
+                    }
+                }}
+            </ul>
+            <p class="node_used_by mt-4">
+                {move || match used_by() {
+                    names if names.is_empty() => "Not used by calculated nodes".to_string(),
+                    names => format!("Used by calculated nodes: {}", names.join(", ")),
+                }}
+            </p>
+        </div>
+        <RequiresRole role=Role::Engineer>
+            {move || shown_uid.get().map(|uid| view! { cx, <NodeMetadataForm uid/> })}
+        </RequiresRole>
+    }
+}
+
+// Edits the descriptive fields of a node, the value and uid are left alone
+#[component]
+fn NodeMetadataForm(cx: Scope, uid: String) -> impl IntoView {
+    let cache = use_node_cache(cx);
+    let site = use_active_site(cx);
+    let can_write = use_capability(cx, "write");
+    // Cached copy the form starts from, the save reads the node again first
+    let key = cache_key(&site.get_untracked(), "bucket");
+    let cached = cache
+        .entries
+        .with_untracked(|entries| cached_list::<RapiStruct>(entries, &key))
+        .and_then(|nodes| nodes.into_iter().find(|node| node.node_uid == uid));
+    let Some(node) = cached else {
+        return ().into_view(cx);
+    };
+    let read_write = node.node_rw_direction.contains('w');
+
+    let description = create_rw_signal(cx, node.node_description);
+    let unit = create_rw_signal(cx, node.node_unit);
+    let device = create_rw_signal(cx, node.node_device);
+    let owner = create_rw_signal(cx, node.node_owner);
+    let path = create_rw_signal(cx, node.node_path);
+    let tags = create_rw_signal(cx, node.node_tags);
+    let notes = create_rw_signal(cx, node.node_notes);
+    let alarm_hh = create_rw_signal(cx, node.node_alarm_hh);
+    let alarm_h = create_rw_signal(cx, node.node_alarm_h);
+    let alarm_l = create_rw_signal(cx, node.node_alarm_l);
+    let alarm_ll = create_rw_signal(cx, node.node_alarm_ll);
+    let alarm_state = create_rw_signal(cx, node.node_alarm_state);
+    let (status, set_status) = create_signal(cx, String::new());
+
+    let on_save = move |_| {
+        let limits = [alarm_hh, alarm_h, alarm_l, alarm_ll].map(|limit| limit.get_untracked().trim().to_string());
+        if limits.iter().any(|limit| !limit.is_empty() && limit.parse::<f64>().is_err()) {
+            set_status.set("Alarm limits must be numbers".to_string());
+            return;
+        }
+        set_status.set("Reading the current value...".to_string());
+        let uid = uid.clone();
+        spawn_local(async move {
+            let before = match fetch_node(&active_backend(), &uid).await {
+                Ok(node) => node,
+                Err(err) => {
+                    set_status.set(err);
+                    return;
+                }
+            };
+            let mut after = before.clone();
+            after.node_description = description.get_untracked().trim().to_string();
+            after.node_unit = unit.get_untracked().trim().to_string();
+            after.node_device = device.get_untracked().trim().to_string();
+            after.node_owner = owner.get_untracked().trim().to_string();
+            after.node_path = normalize_path(&path.get_untracked());
+            after.node_tags = normalize_tags(&tags.get_untracked());
+            after.node_notes = notes.get_untracked().trim().to_string();
+            [after.node_alarm_hh, after.node_alarm_h, after.node_alarm_l, after.node_alarm_ll] = limits;
+            after.node_alarm_state = alarm_state.get_untracked();
+            set_status.set("Saving...".to_string());
+            let resp = audited_post_now(
+                &api_url("/u"),
+                rapi_to_map(&after),
+                AuditAction::Update,
+                AuditTarget::RapiNode,
+                serde_json::to_value(&before).ok(),
+            )
+            .await;
+            set_status.set(match resp.as_str() {
+                "200" => format!("Saved, value {} written back", after.node_val),
+                _ => format!("Not saved: {}", unsent_reason(&resp)),
+            });
+        });
+    };
+
+    view! { cx,
+        <Show when=move || can_write.get() fallback=|_| ()>
+            <div class="node_metadata_form m-5 p-5 max-w-3xl rounded overflow-hidden shadow-lg border dark:text-gray-300">
+                <h3 class="font-bold text-lg mb-4">"Edit metadata"</h3>
+                <p class="metadata_value_warning mb-4 text-orange-600">
+                    {if read_write {
+                        "Saving writes the whole node back: the current value is read again and rewritten, which is a setpoint write on this read/write node."
+                    } else {
+                        "Saving writes the whole node back: the current value is read again and rewritten."
+                    }}
+                </p>
+                <WizardInput label="Description: " value=description/>
+                <WizardInput label="Unit: " value=unit/>
+                <WizardInput label="Source device: " value=device/>
+                <WizardInput label="Owner: " value=owner/>
+                <WizardInput label="Path (site/area/device/signal): " value=path/>
+                <WizardInput label="Tags (comma separated): " value=tags/>
+                <label class="block font-bold mb-1">"Notes: "</label>
+                <textarea
+                class="node_notes_input dark:bg-gray-700 border-2 border-gray-200 rounded w-full h-32 p-2"
+                prop:value=move || notes.get()
+                on:input=move |ev| notes.set(event_target_value(&ev))
+                ></textarea>
+                <h3 class="font-bold text-lg mt-4 mb-4">"Alarm limits"</h3>
+                <WizardInput label="High-high: " value=alarm_hh/>
+                <WizardInput label="High: " value=alarm_h/>
+                <WizardInput label="Low: " value=alarm_l/>
+                <WizardInput label="Low-low: " value=alarm_ll/>
+                <div class="md:flex md:items-center mb-6">
+                    <div class="md:w-1/3">
+                        <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4">"State alarm: "</label>
+                    </div>
+                    <div class="md:w-2/3">
+                        <select
+                        class="alarm_state_select dark:bg-gray-700 border-2 border-gray-200 rounded w-full py-2 px-4"
+                        prop:value=move || alarm_state.get()
+                        on:change=move |ev| alarm_state.set(event_target_value(&ev))
+                        >
+                            <option value="">"None"</option>
+                            <option value="1">"Alarm while on"</option>
+                            <option value="0">"Alarm while off"</option>
+                        </select>
+                    </div>
+                </div>
+                <button
+                class="bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-2 px-4 rounded mt-2"
+                on:click=on_save.clone()
+                >
+                "Save"
+                </button>
+                <span class="ml-2">{move || status.get()}</span>
+            </div>
+        </Show>
+    }
+    .into_view(cx)
+}
+
+#[component]
+fn NewRapiNode(cx: Scope) -> impl IntoView {
+    // A clone comes with a prefilled query, get its uid right away
+    let cloning = use_query_map(cx).with_untracked(|query| query.get("node_name").is_some());
+    let new_uid = create_action(cx, |_: &()| take_uid());
+    if cloning {
+        new_uid.dispatch(());
+    }
+    let uid_slot = new_uid.value();
+    let has_uid = move || uid_slot.with(|uid| matches!(uid, Some(Ok(_))));
+
+    let async_result = move || match uid_slot.get() {
+        Some(Ok(uid)) => view! { cx, <NewRapiForm uid uid_slot/> }.into_view(cx),
+        Some(Err(err)) => view! { cx,
+            <h3 class="dark:text-gray-300 text-base m-3">{format!("Could not get a new uid: {err}")}</h3>
+        }
+        .into_view(cx),
+        None => view! { cx, <h3 class="dark:text-gray-300 text-base m-3">"Loading..."</h3> }.into_view(cx),
+    };
+
+    view! { cx,
+        <div class="new_node m-5 p-5 max-w-sm rounded overflow-hidden shadow-lg border">
+
+            <Show
+            when=move || { new_uid.pending().get() || uid_slot.with(|uid| uid.is_some()) }
+            fallback=|_cx| view! { _cx, <h3 class="dark:text-gray-300 text-base m-3"> "Click on \"Generate new UID\" to make a new datanode!"</h3> }
+          >
+            {async_result}
+          </Show>
+
+          <button
+          class="bg-blue-600 text-gray-200 hover:bg-blue-900 dark:text-gray-300 font-bold py-2 px-4 rounded"
+          on:click= move |_| {
+              new_uid.dispatch(());
+          }
+          class:btn_disabled=move || { new_uid.pending().get() || has_uid() }
+      >
+          "Generate new UID"
+        </button>
+
+        </div>
+    }
+}
+
+#[component]
+fn NewRapiForm(
+    cx: Scope,
+    uid: String,
+    uid_slot: RwSignal<Option<Result<String, String>>>,
+) -> impl IntoView {
+    let query = use_query_map(cx).get_untracked();
+    let prefill = move |key: &str| query.get(key).cloned().unwrap_or_default();
+
+    let input_element_name: NodeRef<Input> = create_node_ref(cx);
+    let input_element_default_value: NodeRef<Input> = create_node_ref(cx);
+    let input_element_path: NodeRef<Input> = create_node_ref(cx);
+    let input_element_tags: NodeRef<Input> = create_node_ref(cx);
+    let input_element_description: NodeRef<Input> = create_node_ref(cx);
+    let input_element_unit: NodeRef<Input> = create_node_ref(cx);
+    let input_element_device: NodeRef<Input> = create_node_ref(cx);
+    let input_element_owner: NodeRef<Input> = create_node_ref(cx);
+    let input_element_notes: NodeRef<Input> = create_node_ref(cx);
+
+    let select_element_rw: NodeRef<Select> = create_node_ref(cx);
+
+    let input_element_repeat: NodeRef<Input> = create_node_ref(cx);
+    let input_element_step: NodeRef<Input> = create_node_ref(cx);
+    let (form_status, set_form_status) = create_signal(cx, String::new());
+    let (uid_used, set_uid_used) = create_signal(cx, false);
+
+    let uid_tmp = uid.clone();
+
+    // A uid the backend has not stored a node under goes back to the pool
+    let consumed = Rc::new(Cell::new(false));
+    {
+        let consumed = consumed.clone();
+        let uid = uid.clone();
+        on_cleanup(cx, move || {
+            if !consumed.get() {
+                release_uids(vec![uid]);
+            }
+        });
+    }
+
+    let on_submit = move |ev: SubmitEvent| {
+        ev.prevent_default();
+
+        let value_name = input_element_name.get().expect("<input> to exist").value();
+        let value_default_value = input_element_default_value
+            .get()
+            .expect("<input> to exist")
+            .value();
+        let value_path = normalize_path(&input_element_path.get().expect("<input> to exist").value());
+        let value_tags = normalize_tags(&input_element_tags.get().expect("<input> to exist").value());
+        let metadata: Vec<(&str, String)> = [
+            ("node_description", input_element_description),
+            ("node_unit", input_element_unit),
+            ("node_device", input_element_device),
+            ("node_owner", input_element_owner),
+            ("node_notes", input_element_notes),
+        ]
+        .into_iter()
+        .map(|(field, input)| (field, input.get().expect("<input> to exist").value().trim().to_string()))
+        .collect();
+
+        let value_rw = select_element_rw.get().expect("<select> to exist").value();
+        let names = match repeat_values(input_element_repeat, input_element_step)
+            .and_then(|(repeat, step)| repeated_suffixes(&value_name, repeat, step))
+        {
+            Ok(names) => names,
+            Err(err) => {
+                set_form_status.set(err);
+                return;
+            }
+        };
+        set_form_status.set(String::new());
+        let first_uid = uid_tmp.clone();
+        let consumed = consumed.clone();
+        // Once the backend stored a node under the generated uid every node gets a fresh one
+        let first_taken = consumed.get();
+
+        spawn_local(async move {
+            // The first node uses the generated uid, every copy gets a fresh one
+            let fresh = names.len() - usize::from(!first_taken);
+            let mut uids = match take_uids(fresh).await {
+                Ok(copies) => copies.into_iter(),
+                Err(err) => {
+                    set_form_status.set(format!("Could not get uids for the copies: {err}"));
+                    return;
+                }
+            };
+            // Not queued: a uid waiting in the outbox could be handed out again
+            let total = names.len();
+            for (i, name) in names.iter().enumerate() {
+                let generated = i == 0 && !first_taken;
+                let node_uid = if generated {
+                    first_uid.clone()
+                } else {
+                    uids.next().unwrap_or_default()
+                };
+                let mut map = HashMap::new();
+                map.insert("node_val", value_default_value.clone());
+                map.insert("node_uid", node_uid.clone());
+                map.insert("node_rw_direction", value_rw.clone());
+                map.insert("node_name", name.clone());
+                map.insert("node_path", value_path.clone());
+                map.insert("node_tags", value_tags.clone());
+                map.extend(metadata.iter().cloned());
+
+                let resp = audited_post_now(
+                    &api_url("/u"),
+                    map,
+                    AuditAction::Create,
+                    AuditTarget::RapiNode,
+                    None,
+                )
+                .await;
+                if resp != "200" {
+                    // The generated uid stays with the form for another try
+                    let unused = uids.chain((!generated).then_some(node_uid)).collect();
+                    release_uids(unused);
+                    let mut status = create_summary(i, total, Some(&resp));
+                    if i > 0 {
+                        // Continue from the first name that was not created
+                        let existing = names[..i].join(", ");
+                        status.push_str(&format!(
+                            ". {existing} exist, the form now continues from {name}: submit again for the remaining {}",
+                            total - i
+                        ));
+                        if let Some(input) = input_element_name.get_untracked() {
+                            input.set_value(name);
+                        }
+                        if let Some(input) = input_element_repeat.get_untracked() {
+                            input.set_value(&(total - i).to_string());
+                        }
+                    }
+                    set_form_status.set(status);
+                    return;
+                }
+                if generated {
+                    consumed.set(true);
+                    set_uid_used.set(true);
+                }
+            }
+            set_form_status.set(create_summary(total, total, None));
+            uid_slot.set(None);
+        });
+    };
+
+    view! { cx,
+            <div class="new_node_form">
+
+                <form class="w-full max-w-sm p-4"
+                 on:submit=on_submit>
+                 <div class="md:flex md:items-center mb-6">
+
+                 <div class="md:w-1/3">
+                 <label class="block dark:text-gray-300 font-bold md:text-right mb-1 md:mb-0 pr-4" for="uid">
+                 "Generated uid: "
+                </label>
+                 </div>
+                <div class="md:w-2/3">
+                <span
+                class="generated_uid appearance-none  border-2 border-blue-300 rounded w-full py-2 px-2 dark:text-gray-300 leading-tight focus:outline-none focus:bg-white focus:border-gray-700"
+                >
+                {uid}
+                </span>
+                <Show when=move || uid_used.get() fallback=|_| ()>
+                    <p class="text-xs dark:text-gray-300">"Already created, new nodes get fresh uids"</p>
+                </Show>
+                </div>
+                </div>
+
+                <FormInputCust node_ref_cust=input_element_name
+                label_text="Data node name: ".to_string()
+                id_name="name_input".to_string()
+                value=prefill("node_name") />
+
+                <FormInputCust node_ref_cust=input_element_default_value
+                label_text="Data node default value: ".to_string()
+                id_name="value_input".to_string()
+                value=prefill("node_val") />
+
+                <FormSelectCust node_ref_cust=select_element_rw
+                label_text="Data node read/write: ".to_string()
+                id_name="read_write_select".to_string()
+                value=prefill("node_rw_direction") />
+
+                <FormInputCust node_ref_cust=input_element_path
+                label_text="Path (site/area/device/signal): ".to_string()
+                id_name="path_input".to_string()
+                value=prefill("node_path") />
+
+                <FormInputCust node_ref_cust=input_element_tags
+                label_text="Tags (comma separated): ".to_string()
+                id_name="tags_input".to_string()
+                value=prefill("node_tags") />
+
+                <FormInputCust node_ref_cust=input_element_description
+                label_text="Description: ".to_string()
+                id_name="description_input".to_string()
+                value=prefill("node_description") />
+
+                <FormInputCust node_ref_cust=input_element_unit
+                label_text="Unit: ".to_string()
+                id_name="unit_input".to_string()
+                value=prefill("node_unit") />
+
+                <FormInputCust node_ref_cust=input_element_device
+                label_text="Source device: ".to_string()
+                id_name="device_input".to_string()
+                value=prefill("node_device") />
+
+                <FormInputCust node_ref_cust=input_element_owner
+                label_text="Owner: ".to_string()
+                id_name="owner_input".to_string()
+                value=prefill("node_owner") />
+
+                <FormInputCust node_ref_cust=input_element_notes
+                label_text="Notes: ".to_string()
+                id_name="notes_input".to_string()
+                value=prefill("node_notes") />
+
+                <FormInputCust node_ref_cust=input_element_repeat
+                label_text="Repeat count: ".to_string()
+                id_name="repeat_input".to_string()
+                value="1".to_string() />
+
+                <FormInputCust node_ref_cust=input_element_step
+                label_text="Name suffix increment: ".to_string()
+                id_name="step_input".to_string()
+                value="1".to_string() />
+
+                <p class="form_status dark:text-gray-300">{move || form_status.get()}</p>
+                <FormSubmitButton />
+            </form>
+            </div>
+    }
+}
+
+#[component]
+fn FormSubmitButton(cx: Scope) -> impl IntoView {
+    view! {cx,
+        <input
+        class="shadow text-gray-300 bg-green-500 hover:bg-green-400 focus:shadow-outline focus:outline-none text-white font-bold py-2 px-4 rounded m-5"
+        type="submit" value="Submit"/>
+    }
+}
+
+// Renders its children only for users with at least `role`
+#[component]
+fn RequiresRole(
+    cx: Scope,
+    role: Role,
+    // Shown instead of the children, nothing when left out
+    #[prop(optional)] message: Option<&'static str>,
+    children: ChildrenFn,
+) -> impl IntoView {
+    let current = use_role(cx);
+
+    view! { cx,
+        <Show
+        when=move || { current.get() >= role }
+        fallback=move |cx| message.map(|message| view! { cx,
+            <p class="new_node dark:text-gray-300 m-3">
+                {format!("{message} (your role: {})", current.get().label())}
+            </p>
+        })
+        >
+            {children(cx)}
+        </Show>
+    }
+}
+
+#[component]
+fn FormInputCust(
+    cx: Scope,
+    node_ref_cust: NodeRef<Input>,
+    id_name: String,
+    label_text: String,
+    #[prop(optional)] value: String,
+) -> impl IntoView {
+    view! {cx,
+        <div class="md:flex md:items-center mb-6">
+
+          <div class="md:w-1/3">
+          <label class="block dark:text-gray-300 font-bold md:text-right mb-1 md:mb-0 pr-4" for={&id_name}>
+          {label_text}
+          </label>
+        </div>
+        <div class="md:w-2/3">
+        <input type="text"
+        id={&id_name}
+        class="dark:bg-gray-700 appearance-none border-2 border-gray-200 rounded w-full py-2 px-4 dark:text-gray-300 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+        node_ref=node_ref_cust
+        value=value
+    />
+    </div>
+    </div>}
+}
+
+#[component]
+fn FormSelectCust(
+    cx: Scope,
+    node_ref_cust: NodeRef<Select>,
+    id_name: String,
+    label_text: String,
+    #[prop(optional)] value: String,
+) -> impl IntoView {
+    view! {cx,
+        <div class="md:flex md:items-center mb-6">
+
+          <div class="md:w-1/3">
+          <label class="block dark:text-gray-300 font-bold md:text-right mb-1 md:mb-0 pr-4" for={&id_name}>
+          {label_text}
+          </label>
+        </div>
+        <div class="md:w-2/3">
+        <select
+        id={&id_name}
+        class="dark:bg-gray-700 appearance-none dark:text-gray-300 border-2 border-gray-200 rounded w-full py-2 px-4  leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+        node_ref=node_ref_cust
+        >
+        <option value="rw" selected=value == "rw">rw</option>
+        <option value="r" selected=value == "r">r</option>
+        <option value="w" selected=value == "w">w</option>
+        </select>
+    </div>
+    </div>}
+}
+
+#[component]
+fn NewMbtcpNode(cx: Scope) -> impl IntoView {
+    // Field values can be prefilled from the query, e.g. ?mb_ip=10.0.0.1
+    let query = use_query_map(cx).get_untracked();
+    let prefill = move |key: &str| query.get(key).cloned().unwrap_or_default();
+
+    let input_element_ip: NodeRef<Input> = create_node_ref(cx);
+    let input_element_port: NodeRef<Input> = create_node_ref(cx);
+
+    let select_element_rw: NodeRef<Select> = create_node_ref(cx);
+
+    let input_element_lock_to_uid: NodeRef<Input> = create_node_ref(cx);
+    let input_element_register: NodeRef<Input> = create_node_ref(cx);
+
+    let input_element_repeat: NodeRef<Input> = create_node_ref(cx);
+    let input_element_step: NodeRef<Input> = create_node_ref(cx);
+    let (form_status, set_form_status) = create_signal(cx, String::new());
+
+    let on_submit = move |ev: SubmitEvent| {
+        ev.prevent_default();
+
+        let value_ip = input_element_ip.get().expect("<input> to exist").value();
+        let value_port = input_element_port.get().expect("<input> to exist").value();
+        let value_lock_to_uid = input_element_lock_to_uid
+            .get()
+            .expect("<input> to exist")
+            .value();
+        let value_register = input_element_register
+            .get()
+            .expect("<input> to exist")
+            .value();
+
+        let value_rw = select_element_rw.get().expect("<select> to exist").value();
+        let registers = match repeat_values(input_element_repeat, input_element_step)
+            .and_then(|(repeat, step)| repeated_suffixes(&value_register, repeat, step))
+        {
+            Ok(registers) => registers,
+            Err(err) => {
+                set_form_status.set(err);
+                return;
+            }
+        };
+        set_form_status.set(String::new());
+
+        spawn_local(async move {
+            let total = registers.len();
+            for (created, register) in registers.into_iter().enumerate() {
+                let mut map = HashMap::new();
+
+                map.insert("mb_lock_to_uid", value_lock_to_uid.clone());
+                map.insert("mb_ip", value_ip.clone());
+                map.insert("mb_port", value_port.clone());
+                map.insert("mb_rw", value_rw.clone());
+                map.insert("mb_register", register);
+
+                let resp = create_mapping(&api_url("/cmbtcp"), map, AuditTarget::ModbusMapping, total > 1).await;
+                if resp == "202" {
+                    set_form_status.set("Queued until the backend is back".to_string());
+                    return;
+                }
+                if resp != "200" {
+                    set_form_status.set(create_summary(created, total, Some(&resp)));
+                    return;
+                }
+            }
+            set_form_status.set(create_summary(total, total, None));
+        });
+    };
+
+    view! { cx,
+        <div class="m-5 p-5 max-w-sm rounded overflow-hidden shadow-lg border">
+        <div class="new_node_form ">
+            <form class="w-full max-w-sm p-4"
+            on:submit=on_submit>
+
+            <FormInputCust node_ref_cust=input_element_lock_to_uid
+            label_text="Mb lock to uid: ".to_string()
+            id_name="mb_lock_to_id".to_string()
+            value=prefill("mb_lock_to_uid") />
+
+            <FormInputCust node_ref_cust=input_element_ip
+            label_text="Mb IP: ".to_string()
+            id_name="mb_ip".to_string()
+            value=prefill("mb_ip") />
+
+            <FormInputCust node_ref_cust=input_element_port
+            label_text="Mb port: ".to_string()
+            id_name="mb_port".to_string()
+            value=prefill("mb_port") />
+
+            <FormInputCust node_ref_cust=input_element_register
+            label_text="Mb register: ".to_string()
+            id_name="mb_register".to_string()
+            value=prefill("mb_register") />
+
+            <FormSelectCust node_ref_cust=select_element_rw
+            label_text="Mb read/write: ".to_string()
+            id_name="mb_read_write_select".to_string()
+            value=prefill("mb_rw") />
+
+            <FormInputCust node_ref_cust=input_element_repeat
+            label_text="Repeat count: ".to_string()
+            id_name="mb_repeat".to_string()
+            value="1".to_string() />
+
+            <FormInputCust node_ref_cust=input_element_step
+            label_text="Register increment: ".to_string()
+            id_name="mb_step".to_string()
+            value="1".to_string() />
+
+            <p class="form_status dark:text-gray-300">{move || form_status.get()}</p>
+            <FormSubmitButton />
+
+        </form>
+        </div>
+        </div>
+
+    }
+}
+
+#[component]
+fn NewMqttNode(cx: Scope) -> impl IntoView {
+    // Field values can be prefilled from the query, e.g. ?mqtt_topic=plant/temp
+    let query = use_query_map(cx).get_untracked();
+    let prefill = move |key: &str| query.get(key).cloned().unwrap_or_default();
+
+    let input_element_ip: NodeRef<Input> = create_node_ref(cx);
+    let input_element_topic: NodeRef<Input> = create_node_ref(cx);
+
+    let select_element_rw: NodeRef<Select> = create_node_ref(cx);
+
+    let input_element_lock_to_uid: NodeRef<Input> = create_node_ref(cx);
+    let input_element_topic_modif: NodeRef<Input> = create_node_ref(cx);
+
+    let input_element_repeat: NodeRef<Input> = create_node_ref(cx);
+    let input_element_step: NodeRef<Input> = create_node_ref(cx);
+    let (form_status, set_form_status) = create_signal(cx, String::new());
+
+    let on_submit = move |ev: SubmitEvent| {
+        ev.prevent_default();
+
+        let value_ip = input_element_ip.get().expect("<input> to exist").value();
+        let value_topic = input_element_topic.get().expect("<input> to exist").value();
+        let value_lock_to_uid = input_element_lock_to_uid
+            .get()
+            .expect("<input> to exist")
+            .value();
+        let value_topic_modif = input_element_topic_modif
+            .get()
+            .expect("<input> to exist")
+            .value();
+
+        let value_rw = select_element_rw.get().expect("<select> to exist").value();
+        let topics = match repeat_values(input_element_repeat, input_element_step)
+            .and_then(|(repeat, step)| repeated_suffixes(&value_topic, repeat, step))
+        {
+            Ok(topics) => topics,
+            Err(err) => {
+                set_form_status.set(err);
+                return;
+            }
+        };
+        set_form_status.set(String::new());
+
+        spawn_local(async move {
+            let total = topics.len();
+            for (created, topic) in topics.into_iter().enumerate() {
+                let mut map = HashMap::new();
+                map.insert("mqtt_lock_to_uid", value_lock_to_uid.clone());
+                map.insert("mqtt_ip", value_ip.clone());
+                map.insert("mqtt_topic", topic);
+                map.insert("mqtt_rw", value_rw.clone());
+                map.insert("mqtt_topic_modif", value_topic_modif.clone());
+
+                let resp = create_mapping(&api_url("/cmqtt"), map, AuditTarget::MqttMapping, total > 1).await;
+                if resp == "202" {
+                    set_form_status.set("Queued until the backend is back".to_string());
+                    return;
+                }
+                if resp != "200" {
+                    set_form_status.set(create_summary(created, total, Some(&resp)));
+                    return;
+                }
+            }
+            set_form_status.set(create_summary(total, total, None));
+        });
+    };
+
+    view! { cx,
+        <div class="m-5 p-5 max-w-sm rounded overflow-hidden shadow-lg border">
+
+        <div class="new_node_form">
+            <form on:submit=on_submit>
+
+            <FormInputCust node_ref_cust=input_element_lock_to_uid
+            label_text="Mqtt lock to uid: ".to_string()
+            id_name="mqtt_lock_to_id".to_string()
+            value=prefill("mqtt_lock_to_uid") />
+
+            <FormInputCust node_ref_cust=input_element_ip
+            label_text="Mqtt IP: ".to_string()
+            id_name="mqtt_ip".to_string()
+            value=prefill("mqtt_ip") />
+
+            <FormInputCust node_ref_cust=input_element_topic
+            label_text="Mqtt topic: ".to_string()
+            id_name="mqtt_port".to_string()
+            value=prefill("mqtt_topic") />
+
+            <FormInputCust node_ref_cust=input_element_topic_modif
+            label_text="Mqtt topic modif: ".to_string()
+            id_name="mqtt_register".to_string()
+            value=prefill("mqtt_topic_modif") />
+
+            <FormSelectCust node_ref_cust=select_element_rw
+            label_text="Mqtt read/write: ".to_string()
+            id_name="mqtt_read_write_select".to_string()
+            value=prefill("mqtt_rw") />
+
+            <FormInputCust node_ref_cust=input_element_repeat
+            label_text="Repeat count: ".to_string()
+            id_name="mqtt_repeat".to_string()
+            value="1".to_string() />
+
+            <FormInputCust node_ref_cust=input_element_step
+            label_text="Topic suffix increment: ".to_string()
+            id_name="mqtt_step".to_string()
+            value="1".to_string() />
+
+            <p class="form_status dark:text-gray-300">{move || form_status.get()}</p>
+            <FormSubmitButton />
+
+        </form>
+        </div>
+        </div>
+
+    }
+}
+
+#[component]
+fn NoNewNode(cx: Scope) -> impl IntoView {
+    view! { cx,
+        <div class="new_node dark:text-gray-300">
+            "There are no new nodes for this protocol"
+        </div>
+    }
+}
+#[component]
+fn NewDnodeComp(cx: Scope) -> impl IntoView {
+    let params = use_params_map(cx);
+    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
+
+    let dnode_descr = move || match id().as_str() {
+        "rapi" => view! { cx,
+            <NewRapiNode/>
+        },
+        "mbtcp" => view! { cx,
+            <NewMbtcpNode/>
+        },
+        "mqtt" => view! { cx,
+            <NewMqttNode/>
+        },
+        _ => view! { cx,
+            <NoNewNode/>
+        },
+    };
+
+    view! { cx,
+        <RequiresRole role=Role::Engineer message="You are not allowed to create data nodes">
+            {dnode_descr}
+        </RequiresRole>
+    }
+}
+
+#[component]
+fn SettingsComponent(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+
+    let on_zone_change = move |ev| {
+        let time_zone = match event_target_value(&ev).as_str() {
+            "utc" => TimeZoneMode::Utc,
+            _ => TimeZoneMode::Local,
+        };
+        settings.update(|s| s.time_zone = time_zone);
+    };
+
+    let on_format_change = move |ev| {
+        let time_format = match event_target_value(&ev).as_str() {
+            "iso" => TimeFormat::Iso,
+            _ => TimeFormat::Locale,
+        };
+        settings.update(|s| s.time_format = time_format);
+    };
+
+    let on_stale_change = move |ev| {
+        if let Ok(secs) = event_target_value(&ev).parse::<i64>() {
+            settings.update(|s| s.stale_after_secs = secs.max(1));
+        }
+    };
+
+    // Preview of the current time with the selected settings
+    let preview = move || format_timestamp(&DateTime::now(), &settings.get());
+
+    let input_site_name: NodeRef<Input> = create_node_ref(cx);
+    let input_site_url: NodeRef<Input> = create_node_ref(cx);
+    let (site_error, set_site_error) = create_signal(cx, String::new());
+
+    let on_add_site = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let name = input_site_name.get().expect("<input> to exist").value();
+        let url = input_site_url.get().expect("<input> to exist").value();
+        let url = url.trim().trim_end_matches('/').to_string();
+        // The id ends up in the /site/:site routes, keep it url friendly
+        let id: String = name
+            .trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+
+        if id.is_empty() || url.is_empty() {
+            set_site_error.set("Name and url are required".to_string());
+        } else if settings.with_untracked(|s| s.backends.iter().any(|b| b.id == id)) {
+            set_site_error.set(format!("There is already a site called {id}"));
+        } else {
+            settings.update(|s| {
+                s.backends.push(Backend {
+                    id,
+                    name: name.trim().to_string(),
+                    url,
+                })
+            });
+            set_site_error.set(String::new());
+            input_site_name.get().expect("<input> to exist").set_value("");
+            input_site_url.get().expect("<input> to exist").set_value("");
+        }
+    };
+
+    let site_rows = move || {
+        settings.with(|s| {
+            let removable = s.backends.len() > 1;
+            s.backends
+                .iter()
+                .map(|backend| {
+                    let id = backend.id.clone();
+                    view! { cx,
+                        <tr class="border-b">
+                            <td class="p-2">{backend.name.clone()}</td>
+                            <td class="p-2 font-mono">{backend.id.clone()}</td>
+                            <td class="p-2 font-mono">{backend.url.clone()}</td>
+                            <td class="p-2">
+                                <button
+                                class="underline hover:text-gray-500"
+                                class:hidden=!removable
+                                on:click=move |_| {
+                                    settings.update(|s| s.backends.retain(|b| b.id != id));
+                                    // Never leave the UI pointing at a removed site
+                                    if active_backend().id == id {
+                                        if let Some(first) = settings.with_untracked(|s| s.backends.first().cloned()) {
+                                            activate_backend(cx, &first.id);
+                                        }
+                                    }
+                                }
+                                >
+                                "Remove"
+                                </button>
+                            </td>
+                        </tr>
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    view! { cx,
+        <div class="settings m-5 p-5 max-w-lg rounded overflow-hidden shadow-lg border dark:text-gray-300">
+            <h3 class="font-bold text-lg mb-6">"Date and time"</h3>
+
+            <div class="md:flex md:items-center mb-6">
+                <div class="md:w-1/3">
+                    <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4" for="time_zone_select">
+                        "Timezone: "
+                    </label>
+                </div>
+                <div class="md:w-2/3">
+                    <select
+                    id="time_zone_select"
+                    class="dark:bg-gray-700 appearance-none dark:text-gray-300 border-2 border-gray-200 rounded w-full py-2 px-4 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                    on:change=on_zone_change
+                    >
+                    <option value="local" selected=move || settings.get().time_zone == TimeZoneMode::Local>
+                        {format!("Local ({})", browser_time_zone())}
+                    </option>
+                    <option value="utc" selected=move || settings.get().time_zone == TimeZoneMode::Utc>"UTC"</option>
+                    </select>
+                </div>
+            </div>
+
+            <div class="md:flex md:items-center mb-6">
+                <div class="md:w-1/3">
+                    <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4" for="time_format_select">
+                        "Format: "
+                    </label>
+                </div>
+                <div class="md:w-2/3">
+                    <select
+                    id="time_format_select"
+                    class="dark:bg-gray-700 appearance-none dark:text-gray-300 border-2 border-gray-200 rounded w-full py-2 px-4 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                    on:change=on_format_change
+                    >
+                    <option value="locale" selected=move || settings.get().time_format == TimeFormat::Locale>"Browser locale"</option>
+                    <option value="iso" selected=move || settings.get().time_format == TimeFormat::Iso>"ISO 8601"</option>
+                    </select>
+                </div>
+            </div>
+
+            <div class="md:flex md:items-center mb-6">
+                <div class="md:w-1/3">
+                    <span class="block font-bold md:text-right mb-1 md:mb-0 pr-4">"Preview: "</span>
+                </div>
+                <div class="md:w-2/3">
+                    <span class="border-2 border-gray-200 rounded w-full py-2 px-2 leading-tight">{preview}</span>
+                </div>
+            </div>
+
+            <h3 class="font-bold text-lg mb-6">"Data nodes"</h3>
+
+            <div class="md:flex md:items-center mb-6">
+                <div class="md:w-1/3">
+                    <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4" for="stale_after_input">
+                        "Stale after (s): "
+                    </label>
+                </div>
+                <div class="md:w-2/3">
+                    <input type="number" min="1"
+                    id="stale_after_input"
+                    class="dark:bg-gray-700 appearance-none border-2 border-gray-200 rounded w-full py-2 px-4 dark:text-gray-300 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                    prop:value=move || settings.get().stale_after_secs.to_string()
+                    on:change=on_stale_change
+                    />
+                </div>
+            </div>
+
+            <AlertSettingsSection/>
+
+            <h3 class="font-bold text-lg mb-6">"Sites"</h3>
+
+            <table class="w-full text-left text-sm mb-6">
+                <thead>
+                    <tr class="border-b font-bold">
+                        <th class="p-2">"Name"</th>
+                        <th class="p-2">"Id"</th>
+                        <th class="p-2">"Url"</th>
+                        <th class="p-2"></th>
+                    </tr>
+                </thead>
+                <tbody>{site_rows}</tbody>
+            </table>
+
+            <form on:submit=on_add_site>
+                <div class="md:flex md:items-center mb-6">
+                    <div class="md:w-1/3">
+                        <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4" for="site_name_input">
+                            "Name: "
+                        </label>
+                    </div>
+                    <div class="md:w-2/3">
+                        <input type="text"
+                        id="site_name_input"
+                        class="dark:bg-gray-700 appearance-none border-2 border-gray-200 rounded w-full py-2 px-4 dark:text-gray-300 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                        node_ref=input_site_name
+                        />
+                    </div>
+                </div>
+                <div class="md:flex md:items-center mb-6">
+                    <div class="md:w-1/3">
+                        <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4" for="site_url_input">
+                            "Url: "
+                        </label>
+                    </div>
+                    <div class="md:w-2/3">
+                        <input type="text"
+                        id="site_url_input"
+                        placeholder="http://10.0.0.5:8000"
+                        class="dark:bg-gray-700 appearance-none border-2 border-gray-200 rounded w-full py-2 px-4 dark:text-gray-300 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                        node_ref=input_site_url
+                        />
+                    </div>
+                </div>
+                <FormSubmitButton />
+            </form>
+            <p class="text-red-500">{move || site_error.get()}</p>
+
+            <h3 class="font-bold text-lg my-6">"Demo backend"</h3>
+            <p class="mb-4">"A backend simulated in the browser with sample data, for demos and UI work without a concentrator."</p>
+            <button
+            class="bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-2 px-4 mr-2 rounded"
+            class:hidden=move || settings.with(|s| s.backends.iter().any(|b| b.url == mock::MOCK_BACKEND_URL))
+            on:click=move |_| {
+                mock::install();
+                settings.update(|s| s.backends.push(mock::demo_backend()));
+            }
+            >
+            "Add demo site"
+            </button>
+            <button
+            class="bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-2 px-4 rounded"
+            on:click=move |_| mock::reset()
+            >
+            "Reset demo data"
+            </button>
+        </div>
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum IntegrityIssue {
+    MbMissingUid(ModbusStruct),
+    MqttMissingUid(MqttStruct),
+    DuplicateRegister {
+        ip: String,
+        port: String,
+        register: String,
+        mappings: Vec<ModbusStruct>,
+    },
+    DuplicateTopicWriters {
+        ip: String,
+        topic: String,
+        mappings: Vec<MqttStruct>,
+    },
+}
+
+fn integrity_issues(
+    rapi: &[RapiStruct],
+    modbus: &[ModbusStruct],
+    mqtt: &[MqttStruct],
+) -> Vec<IntegrityIssue> {
+    let known = rapi_uids(rapi);
+    let mut issues = Vec::new();
+
+    for mb in modbus.iter().filter(|mb| !known.contains(&mb.mb_lock_to_uid)) {
+        issues.push(IntegrityIssue::MbMissingUid(mb.clone()));
+    }
+    for mq in mqtt.iter().filter(|mq| !known.contains(&mq.mqtt_lock_to_uid)) {
+        issues.push(IntegrityIssue::MqttMissingUid(mq.clone()));
+    }
+
+    // Vec instead of a HashMap so the report keeps the backend's order
+    let mut registers: Vec<((String, String, String), Vec<ModbusStruct>)> = Vec::new();
+    for mb in modbus {
+        let key = (mb.mb_ip.clone(), mb.mb_port.clone(), mb.mb_register.clone());
+        match registers.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(mb.clone()),
+            None => registers.push((key, vec![mb.clone()])),
+        }
+    }
+    for ((ip, port, register), mappings) in registers {
+        if mappings.len() > 1 {
+            issues.push(IntegrityIssue::DuplicateRegister {
+                ip,
+                port,
+                register,
+                mappings,
+            });
+        }
+    }
+
+    let mut topics: Vec<((String, String), Vec<MqttStruct>)> = Vec::new();
+    for mq in mqtt.iter().filter(|mq| mq.mqtt_rw.contains('w')) {
+        let key = (mq.mqtt_ip.clone(), mq.mqtt_topic.clone());
+        match topics.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(mq.clone()),
+            None => topics.push((key, vec![mq.clone()])),
+        }
+    }
+    for ((ip, topic), mappings) in topics {
+        if mappings.len() > 1 {
+            issues.push(IntegrityIssue::DuplicateTopicWriters {
+                ip,
+                topic,
+                mappings,
+            });
+        }
+    }
+
+    issues
+}
+
+#[component]
+fn IntegrityReport(cx: Scope) -> impl IntoView {
+    let site = use_active_site(cx);
+    let (refresh, set_refresh) = create_signal(cx, 0);
+    let overview = create_resource(
+        cx,
+        move || (refresh.get(), site.get()),
+        |_| get_system_overview(),
+    );
+
+    let report = move || {
+        overview.read(cx).map(|overview| {
+            if !overview.reachable {
+                return view! { cx, <p>"Backend is not reachable, no report."</p> }.into_view(cx);
+            }
+            let issues = integrity_issues(&overview.rapi, &overview.modbus, &overview.mqtt);
+            if issues.is_empty() {
+                return view! { cx, <p>"No problems found."</p> }.into_view(cx);
+            }
+            issues
+                .into_iter()
+                .map(|issue| view! { cx, <IntegrityIssueCard issue/> })
+                .collect::<Vec<_>>()
+                .into_view(cx)
+        })
+    };
+
+    view! { cx,
+        <div class="integrity p-5 dark:text-gray-300">
+            <div class="flex items-center mb-4">
+                <h1 class="text-2xl font-bold">"Integrity report"</h1>
+                <button
+                class="ml-auto bg-blue-600 text-gray-200 hover:bg-blue-900 dark:text-gray-300 font-bold py-2 px-4 rounded"
+                on:click=move |_| set_refresh.update(|n| *n += 1)
+                >
+                "Refresh"
+                </button>
+            </div>
+            <Suspense fallback=move || view! { cx, <p>"Loading..."</p> }>
+                <div class="grid grid-cols-2 grid-flow-row gap-2">{report}</div>
+            </Suspense>
+        </div>
+    }
+}
+
+#[component]
+fn IntegrityIssueCard(cx: Scope, issue: IntegrityIssue) -> impl IntoView {
+    let site = use_active_site(cx).get_untracked();
+    let missing = matches!(
+        issue,
+        IntegrityIssue::MbMissingUid(_) | IntegrityIssue::MqttMissingUid(_)
+    );
+    let (title, modbus, mqtt) = match issue {
+        IntegrityIssue::MbMissingUid(mb) => (
+            format!("Modbus mapping locked to missing uid {}", mb.mb_lock_to_uid),
+            vec![mb],
+            vec![],
+        ),
+        IntegrityIssue::MqttMissingUid(mq) => (
+            format!("MQTT mapping locked to missing uid {}", mq.mqtt_lock_to_uid),
+            vec![],
+            vec![mq],
+        ),
+        IntegrityIssue::DuplicateRegister {
+            ip,
+            port,
+            register,
+            mappings,
+        } => (
+            format!("Register {register} on {ip}:{port} is used by {} mappings", mappings.len()),
+            mappings,
+            vec![],
+        ),
+        IntegrityIssue::DuplicateTopicWriters { ip, topic, mappings } => (
+            format!("Topic {topic} on {ip} is written by {} mappings", mappings.len()),
+            vec![],
+            mappings,
+        ),
+    };
+
+    // The backend has no endpoints to delete or relock a mapping, each row
+    // links to its node and to a create form holding the same mapping, so it
+    // can be recreated with the right uid or register/topic
+    let modbus_rows = modbus
+        .into_iter()
+        .map(|mb| IntegrityRow {
+            summary: format!(
+                "{} -> {}:{} reg {} ({})",
+                mb.mb_lock_to_uid, mb.mb_ip, mb.mb_port, mb.mb_register, mb.mb_rw
+            ),
+            node_href: site_path(&site, &format!("/node/{}", mb.mb_lock_to_uid)),
+            missing_href: missing.then(|| site_path(&site, "/dmap/mbtcp/dnodes?filter=missing")),
+            recreate_href: format!(
+                "{}{}",
+                site_path(&site, "/dmap/mbtcp/newdnode"),
+                query_string(&modbus_to_map(&mb))
+            ),
+        });
+
+    let mqtt_rows = mqtt.into_iter().map(|mq| IntegrityRow {
+        summary: format!(
+            "{} -> {} {} ({})",
+            mq.mqtt_lock_to_uid, mq.mqtt_ip, mq.mqtt_topic, mq.mqtt_rw
+        ),
+        node_href: site_path(&site, &format!("/node/{}", mq.mqtt_lock_to_uid)),
+        missing_href: missing.then(|| site_path(&site, "/dmap/mqtt/dnodes?filter=missing")),
+        recreate_href: format!(
+            "{}{}",
+            site_path(&site, "/dmap/mqtt/newdnode"),
+            query_string(&mqtt_to_map(&mq))
+        ),
+    });
+
+    let rows = modbus_rows
+        .chain(mqtt_rows)
+        .map(|row| {
+            let IntegrityRow {
+                summary,
+                node_href,
+                missing_href,
+                recreate_href,
+            } = row;
+            view! { cx,
+                <div class="integrity_row mb-3">
+                    <div class="font-mono text-sm mb-1">{summary}</div>
+                    <CardActionLink href=node_href text="Node"/>
+                    {missing_href.map(|href| view! { cx, <CardActionLink href text="Missing list"/> })}
+                    <RequiresRole role=Role::Engineer>
+                        <CardActionLink href=recreate_href.clone() text="Recreate"/>
+                    </RequiresRole>
+                </div>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! { cx,
+        <div class="integrity_issue m-2 p-5 rounded overflow-hidden shadow-lg border">
+            <h3 class="font-bold mb-4">{title}</h3>
+            {rows}
+        </div>
+    }
+}
+
+// One mapping on an integrity card with the pages to fix it on
+struct IntegrityRow {
+    summary: String,
+    node_href: String,
+    missing_href: Option<String>,
+    recreate_href: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WizardStep {
+    Node,
+    Mappings,
+    Review,
+    Done,
+}
+
+#[derive(Debug, Clone)]
+enum WizardMapping {
+    Modbus(ModbusStruct),
+    Mqtt(MqttStruct),
+}
+
+impl WizardMapping {
+    fn summary(&self) -> String {
+        match self {
+            WizardMapping::Modbus(mb) => format!(
+                "Modbus {}:{} register {} ({})",
+                mb.mb_ip, mb.mb_port, mb.mb_register, mb.mb_rw
+            ),
+            WizardMapping::Mqtt(mq) => format!(
+                "MQTT {} topic {}{} ({})",
+                mq.mqtt_ip, mq.mqtt_topic, mq.mqtt_topic_modif, mq.mqtt_rw
+            ),
+        }
+    }
+
+    async fn create(&self) -> String {
+        self.create_on(&active_backend()).await
+    }
+
+    async fn create_on(&self, backend: &Backend) -> String {
+        match self {
+            WizardMapping::Modbus(mb) => {
+                audited_post_now(
+                    &format!("{}/cmbtcp", backend.url),
+                    modbus_to_map(mb),
+                    AuditAction::Create,
+                    AuditTarget::ModbusMapping,
+                    None,
+                )
+                .await
+            }
+            WizardMapping::Mqtt(mq) => {
+                audited_post_now(
+                    &format!("{}/cmqtt", backend.url),
+                    mqtt_to_map(mq),
+                    AuditAction::Create,
+                    AuditTarget::MqttMapping,
+                    None,
+                )
+                .await
+            }
+        }
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum WizardOutcome {
+    Created,
+    // Nothing was written, the wizard can be run again
+    NodeFailed,
+    // The node exists but some of its mappings do not
+    Orphaned(String),
+}
+
+// Creates the node and then its mappings. The backend can not delete, so a
+// failed mapping leaves the node behind and the user is sent to it.
+async fn create_node_with_mappings(
+    node: HashMap<&'static str, String>,
+    mappings: Vec<WizardMapping>,
+    progress: WriteSignal<Vec<String>>,
+) -> WizardOutcome {
+    let say = move |line: String| progress.update(|lines| lines.push(line));
+    let node_uid = node.get("node_uid").cloned().unwrap_or_default();
+
+    let resp = audited_post_now(
+        &api_url("/u"),
+        node,
+        AuditAction::Create,
+        AuditTarget::RapiNode,
+        None,
+    )
+    .await;
+    if resp != "200" {
+        say(format!("Could not create data node {node_uid}: {}", unsent_reason(&resp)));
+        return WizardOutcome::NodeFailed;
+    }
+    say(format!("Created data node {node_uid}"));
+
+    for mapping in mappings {
+        let resp = mapping.create().await;
+        if resp == "200" {
+            say(format!("Created {}", mapping.summary()));
+            continue;
+        }
+
+        say(format!("Could not create {}: {}", mapping.summary(), unsent_reason(&resp)));
+        say(format!(
+            "Data node {node_uid} is orphaned, its remaining mappings were not created"
+        ));
+        return WizardOutcome::Orphaned(node_uid);
+    }
+
+    WizardOutcome::Created
+}
+
 #[component]
-fn ShowMqttSingleData(cx: Scope, mqttstruct: MqttStruct) -> impl IntoView {
+fn NodeWizard(cx: Scope) -> impl IntoView {
+    let uid = create_resource(cx, || (), |_| take_uid());
+    let node_uid = move || match uid.read(cx) {
+        None => "Loading...".to_string(),
+        Some(Ok(uid)) => uid,
+        Some(Err(err)) => format!("Could not get a uid: {err}"),
+    };
+
+    // Hand the uid back to the pool if the wizard is left before the node was
+    // created. While the create runs the uid is out of `reserved`.
+    let reserved = Rc::new(RefCell::new(None::<String>));
+    let left = Rc::new(Cell::new(false));
+    {
+        let reserved = reserved.clone();
+        create_effect(cx, move |_| {
+            if let Some(Ok(uid)) = uid.read(cx) {
+                *reserved.borrow_mut() = Some(uid);
+            }
+        });
+    }
+    {
+        let reserved = reserved.clone();
+        let left = left.clone();
+        on_cleanup(cx, move || {
+            left.set(true);
+            if let Some(uid) = reserved.borrow_mut().take() {
+                release_uids(vec![uid]);
+            }
+        });
+    }
+
+    let (step, set_step) = create_signal(cx, WizardStep::Node);
+    let name = create_rw_signal(cx, String::new());
+    let default_value = create_rw_signal(cx, String::new());
+    let rw = create_rw_signal(cx, "rw".to_string());
+    let path = create_rw_signal(cx, String::new());
+    let tags = create_rw_signal(cx, String::new());
+    let description = create_rw_signal(cx, String::new());
+    let unit = create_rw_signal(cx, String::new());
+    let device = create_rw_signal(cx, String::new());
+    let owner = create_rw_signal(cx, String::new());
+    let notes = create_rw_signal(cx, String::new());
+    let mappings = create_rw_signal(cx, Vec::<WizardMapping>::new());
+
+    let (progress, set_progress) = create_signal(cx, Vec::<String>::new());
+    let (outcome, set_outcome) = create_signal(cx, None::<WizardOutcome>);
+    let online = use_context::<BackendOnline>(cx).map(|online| online.0);
+
+    let on_create = move |_| {
+        // Half of the node and its mappings must not wait in the outbox
+        if online.map(|online| !online.get_untracked()).unwrap_or(false) || !load_outbox().is_empty() {
+            set_progress.set(vec![
+                "The backend is offline or changes are waiting in the outbox, nothing was created".to_string(),
+            ]);
+            set_outcome.set(Some(WizardOutcome::NodeFailed));
+            set_step.set(WizardStep::Done);
+            return;
+        }
+        let Some(taken) = reserved.borrow_mut().take() else {
+            return;
+        };
+        let mut node = HashMap::new();
+        node.insert("node_val", default_value.get());
+        node.insert("node_uid", taken.clone());
+        node.insert("node_rw_direction", rw.get());
+        node.insert("node_name", name.get());
+        node.insert("node_path", normalize_path(&path.get()));
+        node.insert("node_tags", normalize_tags(&tags.get()));
+        node.insert("node_description", description.get().trim().to_string());
+        node.insert("node_unit", unit.get().trim().to_string());
+        node.insert("node_device", device.get().trim().to_string());
+        node.insert("node_owner", owner.get().trim().to_string());
+        node.insert("node_notes", notes.get().trim().to_string());
+
+        set_progress.set(Vec::new());
+        set_outcome.set(None);
+        set_step.set(WizardStep::Done);
+        let to_create = mappings.get();
+        let reserved = reserved.clone();
+        let left = left.clone();
+        spawn_local(async move {
+            let outcome = create_node_with_mappings(node, to_create, set_progress).await;
+            // Nothing was stored under the uid: keep it for another try or hand it back
+            if outcome == WizardOutcome::NodeFailed {
+                if left.get() {
+                    release_uids(vec![taken]);
+                } else {
+                    *reserved.borrow_mut() = Some(taken);
+                }
+            }
+            set_outcome.set(Some(outcome));
+        });
+    };
+
+    let step_view = move || match step.get() {
+        WizardStep::Node => view! { cx,
+            <h3 class="font-bold text-lg mb-4">"1. Data node"</h3>
+            <ShowSingleDataSpan row_desc="Generated uid: ".to_string() data_to_show=node_uid()/>
+            <WizardInput label="Data node name: " value=name/>
+            <WizardInput label="Data node default value: " value=default_value/>
+            <WizardInput label="Path (site/area/device/signal): " value=path/>
+            <WizardInput label="Tags (comma separated): " value=tags/>
+            <WizardInput label="Description: " value=description/>
+            <WizardInput label="Unit: " value=unit/>
+            <WizardInput label="Source device: " value=device/>
+            <WizardInput label="Owner: " value=owner/>
+            <WizardInput label="Notes: " value=notes/>
+            <div class="md:flex md:items-center mb-6">
+                <div class="md:w-1/3">
+                    <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4">"Data node read/write: "</label>
+                </div>
+                <div class="md:w-2/3">
+                    <select
+                    class="dark:bg-gray-700 appearance-none dark:text-gray-300 border-2 border-gray-200 rounded w-full py-2 px-4 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                    on:change=move |ev| rw.set(event_target_value(&ev))
+                    >
+                    <option value="rw" selected=rw.get_untracked() == "rw">rw</option>
+                    <option value="r" selected=rw.get_untracked() == "r">r</option>
+                    <option value="w" selected=rw.get_untracked() == "w">w</option>
+                    </select>
+                </div>
+            </div>
+            <WizardButtons
+                back=None
+                next=Some(WizardStep::Mappings)
+                next_enabled=Signal::derive(cx, move || !name.get().trim().is_empty() && matches!(uid.read(cx), Some(Ok(_))))
+                set_step/>
+        }
+        .into_view(cx),
+        WizardStep::Mappings => view! { cx,
+            <h3 class="font-bold text-lg mb-4">"2. Protocol mappings"</h3>
+            <WizardMappingList mappings editable=true/>
+            <div class="flex flex-wrap">
+                <WizardModbusForm node_uid=node_uid() mappings/>
+                <WizardMqttForm node_uid=node_uid() mappings/>
+            </div>
+            <WizardButtons
+                back=Some(WizardStep::Node)
+                next=Some(WizardStep::Review)
+                next_enabled=Signal::derive(cx, || true)
+                set_step/>
+        }
+        .into_view(cx),
+        WizardStep::Review => view! { cx,
+            <h3 class="font-bold text-lg mb-4">"3. Review"</h3>
+            <ShowSingleDataSpan row_desc="Data node uid: ".to_string() data_to_show=node_uid()/>
+            <ShowSingleDataSpan row_desc="Data node name: ".to_string() data_to_show=name.get()/>
+            <ShowSingleDataSpan row_desc="Data node default value: ".to_string() data_to_show=default_value.get()/>
+            <ShowSingleDataSpan row_desc="Data node read/write: ".to_string() data_to_show=rw.get()/>
+            <ShowSingleDataSpan row_desc="Data node path: ".to_string() data_to_show=normalize_path(&path.get())/>
+            <ShowSingleDataSpan row_desc="Data node tags: ".to_string() data_to_show=split_tags(&tags.get()).join(", ")/>
+            <ShowSingleDataSpan row_desc="Description: ".to_string() data_to_show=description.get()/>
+            <ShowSingleDataSpan row_desc="Unit: ".to_string() data_to_show=unit.get()/>
+            <ShowSingleDataSpan row_desc="Source device: ".to_string() data_to_show=device.get()/>
+            <ShowSingleDataSpan row_desc="Owner: ".to_string() data_to_show=owner.get()/>
+            <ShowSingleDataSpan row_desc="Notes: ".to_string() data_to_show=notes.get()/>
+            <WizardMappingList mappings editable=false/>
+            <div class="flex items-center">
+                <button
+                class="bg-gray-300 dark:bg-gray-600 font-bold py-2 px-4 rounded"
+                on:click=move |_| set_step.set(WizardStep::Mappings)
+                >
+                "Back"
+                </button>
+                <button
+                class="ml-auto shadow bg-green-500 hover:bg-green-400 text-white font-bold py-2 px-4 rounded"
+                on:click=on_create.clone()
+                >
+                "Create"
+                </button>
+            </div>
+        }
+        .into_view(cx),
+        WizardStep::Done => view! { cx,
+            <h3 class="font-bold text-lg mb-4">"4. Result"</h3>
+            <ul class="mb-4 font-mono text-sm">
+                {move || progress.get().into_iter().map(|line| view! { cx, <li>{line}</li> }).collect::<Vec<_>>()}
+            </ul>
+            {move || match outcome.get() {
+                None => ().into_view(cx),
+                Some(WizardOutcome::Created) => view! { cx,
+                    <A class="underline hover:text-gray-500" href=site_path(&active_backend().id, "/dmap/rapi/dnodes")>"Go to the data nodes"</A>
+                }
+                .into_view(cx),
+                Some(WizardOutcome::NodeFailed) => view! { cx,
+                    <button
+                    class="bg-gray-300 dark:bg-gray-600 font-bold py-2 px-4 rounded"
+                    on:click=move |_| set_step.set(WizardStep::Review)
+                    >
+                    "Back to review"
+                    </button>
+                }
+                .into_view(cx),
+                Some(WizardOutcome::Orphaned(uid)) => view! { cx,
+                    <A class="underline hover:text-gray-500" href=site_path(&active_backend().id, &format!("/node/{uid}"))>
+                        {format!("Open orphaned data node {uid}")}
+                    </A>
+                }
+                .into_view(cx),
+            }}
+        }
+        .into_view(cx),
+    };
+
     view! { cx,
-        <div class="mqtt_data_single m-2 p-5 rounded overflow-hidden shadow-lg border">
-            <ShowSingleDataSpan row_desc="MQTT locked to dNode: ".to_string() data_to_show=mqttstruct.mqtt_lock_to_uid/>
-            <ShowSingleDataSpan row_desc="MQTT ip: ".to_string() data_to_show=mqttstruct.mqtt_ip/>
-            <ShowSingleDataSpan row_desc="MQTT topic: ".to_string() data_to_show=mqttstruct.mqtt_topic/>
-            <ShowSingleDataSpan row_desc="MQTT topic modifier: ".to_string() data_to_show=mqttstruct.mqtt_topic_modif/>
-            <ShowSingleDataSpan row_desc="MQTT read/write: ".to_string() data_to_show=mqttstruct.mqtt_rw/>
+        <div class="node_wizard m-5 p-5 max-w-2xl rounded overflow-hidden shadow-lg border dark:text-gray-300">
+            <RequiresRole role=Role::Engineer message="You are not allowed to create data nodes">
+                {step_view.clone()}
+            </RequiresRole>
         </div>
     }
 }
 
 #[component]
-fn ShowRapiSingleData(cx: Scope, rapistruct: RapiStruct) -> impl IntoView {
+fn WizardInput(cx: Scope, label: &'static str, value: RwSignal<String>) -> impl IntoView {
     view! { cx,
-        <div class="rapi_data_single m-2 p-5 rounded overflow-hidden shadow-lg border">
-            <ShowSingleDataSpan row_desc="Data node uid: ".to_string() data_to_show=rapistruct.node_uid/>
-            <ShowSingleDataSpan row_desc="Data node value: ".to_string() data_to_show=rapistruct.node_val/>
-            <ShowSingleDataSpan row_desc="Data node last updated: ".to_string() data_to_show=rapistruct.node_last_update.to_string()/>
-            <ShowSingleDataSpan row_desc="Data node name: ".to_string() data_to_show=rapistruct.node_name/>
-            <ShowSingleDataSpan row_desc="Data node read/write ? : ".to_string() data_to_show=rapistruct.node_rw_direction/>
+        <div class="md:flex md:items-center mb-6">
+            <div class="md:w-1/3">
+                <label class="block font-bold md:text-right mb-1 md:mb-0 pr-4">{label}</label>
+            </div>
+            <div class="md:w-2/3">
+                <input type="text"
+                class="dark:bg-gray-700 appearance-none border-2 border-gray-200 rounded w-full py-2 px-4 dark:text-gray-300 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                prop:value=move || value.get()
+                on:input=move |ev| value.set(event_target_value(&ev))
+                />
+            </div>
         </div>
     }
 }
 
 #[component]
-fn ShowSingleDataSpan(cx: Scope, row_desc: String, data_to_show: String) -> impl IntoView {
-    view! {cx,
-        <div class="md:flex md:items-center mb-6 ">
-
-            <div class="md:w-1/2">
-                <label class="block dark:text-gray-300 font-bold md:text-right mb-1 md:mb-0 pr-4" for="uid">
-                    {row_desc}
-                </label>
-            </div>
-            <div class="md:w-1/2">
-                <span
-                class="appearance-none dark:text-gray-300 border-2 border-gray-200 rounded w-full py-2 px-2 leading-tight focus:outline-none focus:bg-white focus:border-gray-700"
+fn WizardButtons(
+    cx: Scope,
+    back: Option<WizardStep>,
+    next: Option<WizardStep>,
+    next_enabled: Signal<bool>,
+    set_step: WriteSignal<WizardStep>,
+) -> impl IntoView {
+    view! { cx,
+        <div class="flex items-center">
+            {back.map(|back| view! { cx,
+                <button
+                class="bg-gray-300 dark:bg-gray-600 font-bold py-2 px-4 rounded"
+                on:click=move |_| set_step.set(back)
                 >
-                    {data_to_show}
-                </span>
-            </div>
-       </div>
+                "Back"
+                </button>
+            })}
+            {next.map(|next| view! { cx,
+                <button
+                class="ml-auto bg-blue-600 text-gray-200 hover:bg-blue-900 font-bold py-2 px-4 rounded"
+                prop:disabled=move || !next_enabled.get()
+                on:click=move |_| set_step.set(next)
+                >
+                "Next"
+                </button>
+            })}
+        </div>
     }
 }
 
 #[component]
-fn NewRapiNode(cx: Scope) -> impl IntoView {
-    let (count, set_count) = create_signal(cx, 0);
-    let async_data = create_resource(cx, move || count.get(), crt_new_uid);
+fn WizardMappingList(cx: Scope, mappings: RwSignal<Vec<WizardMapping>>, editable: bool) -> impl IntoView {
+    view! { cx,
+        <ul class="mb-4">
+            <Show when=move || mappings.with(|m| m.is_empty()) fallback=|_| ()>
+                <li>"No mappings, the data node is created on its own."</li>
+            </Show>
+            {move || mappings.get().into_iter().enumerate().map(|(idx, mapping)| view! { cx,
+                <li class="flex items-center mb-2">
+                    <span class="font-mono text-sm">{mapping.summary()}</span>
+                    <Show when=move || editable fallback=|_| ()>
+                        <button
+                        class="ml-auto bg-red-600 text-gray-200 hover:bg-red-900 font-bold py-1 px-2 rounded"
+                        on:click=move |_| mappings.update(|m| { m.remove(idx); })
+                        >
+                        "Remove"
+                        </button>
+                    </Show>
+                </li>
+            }).collect::<Vec<_>>()}
+        </ul>
+    }
+}
+
+#[component]
+fn WizardModbusForm(cx: Scope, node_uid: String, mappings: RwSignal<Vec<WizardMapping>>) -> impl IntoView {
+    let input_element_ip: NodeRef<Input> = create_node_ref(cx);
+    let input_element_port: NodeRef<Input> = create_node_ref(cx);
+    let input_element_register: NodeRef<Input> = create_node_ref(cx);
+    let select_element_rw: NodeRef<Select> = create_node_ref(cx);
 
-    let async_result = move || async_data.read(cx).unwrap_or_else(|| "Loading...".into());
+    let on_submit = move |ev: SubmitEvent| {
+        ev.prevent_default();
+
+        mappings.update(|m| {
+            m.push(WizardMapping::Modbus(ModbusStruct {
+                mb_lock_to_uid: node_uid.clone(),
+                mb_ip: input_element_ip.get().expect("<input> to exist").value(),
+                mb_port: input_element_port.get().expect("<input> to exist").value(),
+                mb_register: input_element_register.get().expect("<input> to exist").value(),
+                mb_rw: select_element_rw.get().expect("<select> to exist").value(),
+            }))
+        });
+    };
 
     view! { cx,
-        <div class="new_node m-5 p-5 max-w-sm rounded overflow-hidden shadow-lg border">
+        <form class="w-full max-w-sm p-4 m-2 border rounded" on:submit=on_submit>
+            <h4 class="font-bold mb-4">"Add Modbus TCP mapping"</h4>
+            <FormInputCust node_ref_cust=input_element_ip
+            label_text="Mb IP: ".to_string()
+            id_name="wizard_mb_ip".to_string() />
+            <FormInputCust node_ref_cust=input_element_port
+            label_text="Mb port: ".to_string()
+            id_name="wizard_mb_port".to_string() />
+            <FormInputCust node_ref_cust=input_element_register
+            label_text="Mb register: ".to_string()
+            id_name="wizard_mb_register".to_string() />
+            <FormSelectCust node_ref_cust=select_element_rw
+            label_text="Mb read/write: ".to_string()
+            id_name="wizard_mb_rw".to_string() />
+            <FormSubmitButton />
+        </form>
+    }
+}
 
-            <Show
-            when=move || { count.get() > 0 }
-            fallback=|_cx| view! { _cx, <h3 class="dark:text-gray-300 text-base m-3"> "Click on \"Generate new UID\" to make a new datanode!"</h3> }
-          >
-            <NewRapiForm uid=async_result() scount=set_count/>
-          </Show>
+#[component]
+fn WizardMqttForm(cx: Scope, node_uid: String, mappings: RwSignal<Vec<WizardMapping>>) -> impl IntoView {
+    let input_element_ip: NodeRef<Input> = create_node_ref(cx);
+    let input_element_topic: NodeRef<Input> = create_node_ref(cx);
+    let input_element_topic_modif: NodeRef<Input> = create_node_ref(cx);
+    let select_element_rw: NodeRef<Select> = create_node_ref(cx);
 
-          <button
-          class="bg-blue-600 text-gray-200 hover:bg-blue-900 dark:text-gray-300 font-bold py-2 px-4 rounded"
-          on:click= move |_| {
-              set_count.update(|n| *n += 1);
-          }
-          class:btn_disabled=move || { count.get() > 0 }
-      >
-          "Generate new UID"
-        </button>
+    let on_submit = move |ev: SubmitEvent| {
+        ev.prevent_default();
 
-        </div>
+        mappings.update(|m| {
+            m.push(WizardMapping::Mqtt(MqttStruct {
+                mqtt_lock_to_uid: node_uid.clone(),
+                mqtt_ip: input_element_ip.get().expect("<input> to exist").value(),
+                mqtt_topic: input_element_topic.get().expect("<input> to exist").value(),
+                mqtt_topic_modif: input_element_topic_modif
+                    .get()
+                    .expect("<input> to exist")
+                    .value(),
+                mqtt_rw: select_element_rw.get().expect("<select> to exist").value(),
+            }))
+        });
+    };
+
+    view! { cx,
+        <form class="w-full max-w-sm p-4 m-2 border rounded" on:submit=on_submit>
+            <h4 class="font-bold mb-4">"Add MQTT mapping"</h4>
+            <FormInputCust node_ref_cust=input_element_ip
+            label_text="Mqtt IP: ".to_string()
+            id_name="wizard_mqtt_ip".to_string() />
+            <FormInputCust node_ref_cust=input_element_topic
+            label_text="Mqtt topic: ".to_string()
+            id_name="wizard_mqtt_topic".to_string() />
+            <FormInputCust node_ref_cust=input_element_topic_modif
+            label_text="Mqtt topic modif: ".to_string()
+            id_name="wizard_mqtt_topic_modif".to_string() />
+            <FormSelectCust node_ref_cust=select_element_rw
+            label_text="Mqtt read/write: ".to_string()
+            id_name="wizard_mqtt_rw".to_string() />
+            <FormSubmitButton />
+        </form>
     }
 }
 
 #[component]
-fn NewRapiForm(cx: Scope, uid: String, scount: WriteSignal<i32>) -> impl IntoView {
-    let input_element_name: NodeRef<Input> = create_node_ref(cx);
-    let input_element_default_value: NodeRef<Input> = create_node_ref(cx);
-
-    let select_element_rw: NodeRef<Select> = create_node_ref(cx);
+fn LoginComponent(cx: Scope) -> impl IntoView {
+    let session = use_session(cx);
+    let navigate = Rc::new(use_navigate(cx));
 
-    let uid_tmp = uid.clone();
+    let input_element_user: NodeRef<Input> = create_node_ref(cx);
+    let input_element_password: NodeRef<Input> = create_node_ref(cx);
+    let (error, set_error) = create_signal(cx, String::new());
 
     let on_submit = move |ev: SubmitEvent| {
         ev.prevent_default();
 
-        let value_name = input_element_name.get().expect("<input> to exist").value();
-        let value_default_value = input_element_default_value
+        let username = input_element_user.get().expect("<input> to exist").value();
+        let password = input_element_password
             .get()
             .expect("<input> to exist")
             .value();
-
-        let value_rw = select_element_rw.get().expect("<select> to exist").value();
-        let mut map = HashMap::new();
-        map.insert("node_val", value_default_value);
-        map.insert("node_uid", uid_tmp.clone());
-        map.insert("node_rw_direction", value_rw);
-        map.insert("node_name", value_name);
+        let navigate = navigate.clone();
 
         spawn_local(async move {
-            let resp = post_data("http://127.0.0.1:8000/u", map).await;
-            if resp == "200".to_string() {
-                scount.set(0);
-            } else {
-                log!("Error on server side, needs better handler later!");
+            match login(username, password).await {
+                Ok(new_session) => {
+                    store_session(Some(&new_session));
+                    session.set(Some(new_session));
+                    set_error.set(String::new());
+                    let _ = navigate("/", Default::default());
+                }
+                Err(err) => set_error.set(err),
             }
         });
     };
 
     view! { cx,
-            <div class="new_node_form">
-
-                <form class="w-full max-w-sm p-4"
-                 on:submit=on_submit>
-                 <div class="md:flex md:items-center mb-6">
-
-                 <div class="md:w-1/3">
-                 <label class="block dark:text-gray-300 font-bold md:text-right mb-1 md:mb-0 pr-4" for="uid">
-                 "Generated uid: "
-                </label>
-                 </div>
-                <div class="md:w-2/3">
-                <span
-                class="appearance-none  border-2 border-blue-300 rounded w-full py-2 px-2 dark:text-gray-300 leading-tight focus:outline-none focus:bg-white focus:border-gray-700"
-                >
-                {uid}
-                </span>
-                </div>
+        <div class="login m-5 p-5 max-w-sm rounded overflow-hidden shadow-lg border dark:text-gray-300">
+            <h3 class="font-bold text-lg mb-4">"Log in"</h3>
+            <Show when=move || session.with(|s| s.is_none()) && error.with(|e| e.is_empty()) fallback=|_| ()>
+                <p class="mb-4">"Log in to the data concentrator to continue."</p>
+            </Show>
+            <form class="w-full max-w-sm p-4" on:submit=on_submit>
+                <FormInputCust node_ref_cust=input_element_user
+                label_text="Username: ".to_string()
+                id_name="login_user".to_string()
+                value=remembered_username() />
+
+                <div class="md:flex md:items-center mb-6">
+                    <div class="md:w-1/3">
+                        <label class="block dark:text-gray-300 font-bold md:text-right mb-1 md:mb-0 pr-4" for="login_password">
+                            "Password: "
+                        </label>
+                    </div>
+                    <div class="md:w-2/3">
+                        <input type="password"
+                        id="login_password"
+                        class="dark:bg-gray-700 appearance-none border-2 border-gray-200 rounded w-full py-2 px-4 dark:text-gray-300 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
+                        node_ref=input_element_password
+                        />
+                    </div>
                 </div>
 
-                <FormInputCust node_ref_cust=input_element_name
-                label_text="Data node name: ".to_string()
-                id_name="name_input".to_string() />
-
-                <FormInputCust node_ref_cust=input_element_default_value
-                label_text="Data node default value: ".to_string()
-                id_name="value_input".to_string() />
-
-                <FormSelectCust node_ref_cust=select_element_rw
-                label_text="Data node read/write: ".to_string()
-                id_name="read_write_select".to_string() />
-
                 <FormSubmitButton />
             </form>
-            </div>
+            <p class="text-red-500">{move || error.get()}</p>
+        </div>
     }
 }
 
+// Shows the current user in the sidebar with a logout button
 #[component]
-fn FormSubmitButton(cx: Scope) -> impl IntoView {
-    view! {cx,
-        <input
-        class="shadow text-gray-300 bg-green-500 hover:bg-green-400 focus:shadow-outline focus:outline-none text-white font-bold py-2 px-4 rounded m-5"
-        type="submit" value="Submit"/>
+fn SessionPanel(cx: Scope) -> impl IntoView {
+    let session = use_session(cx);
+    let navigate = Rc::new(use_navigate(cx));
+
+    let on_logout = move |_| {
+        store_session(None);
+        session.set(None);
+        let _ = navigate("/login", Default::default());
+    };
+
+    view! { cx,
+        <div class="session mt-6 mx-2 dark:text-gray-300">
+            <Show
+            when=move || session.with(|s| s.is_some())
+            fallback=|cx| view! { cx, <A class="underline" href="/login">"Log in"</A> }
+            >
+                <span class="block text-sm">
+                    "Logged in as "{move || session.get().map(|s| s.username).unwrap_or_default()}
+                </span>
+                <span class="block text-sm">
+                    "Role: "{move || session.get().map(|s| s.role.label()).unwrap_or_default()}
+                </span>
+                <button
+                class="mt-2 bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-1 px-3 rounded"
+                on:click=on_logout.clone()
+                >
+                "Log out"
+                </button>
+            </Show>
+        </div>
     }
 }
 
+// Sends the user to the login page when a request comes back with 401
 #[component]
-fn FormInputCust(
-    cx: Scope,
-    node_ref_cust: NodeRef<Input>,
-    id_name: String,
-    label_text: String,
-) -> impl IntoView {
-    view! {cx,
-        <div class="md:flex md:items-center mb-6">
+fn SessionWatcher(cx: Scope) -> impl IntoView {
+    let session = use_session(cx);
+    let navigate = use_navigate(cx);
+
+    // Only a 401 from the active site ends the session the UI runs with
+    window_event_listener_untyped(SESSION_EXPIRED_EVENT, move |_| {
+        if load_session().is_none() {
+            session.set(None);
+            let _ = navigate("/login", Default::default());
+        }
+    });
+}
 
-          <div class="md:w-1/3">
-          <label class="block dark:text-gray-300 font-bold md:text-right mb-1 md:mb-0 pr-4" for={&id_name}>
-          {label_text}
-          </label>
-        </div>
-        <div class="md:w-2/3">
-        <input type="text"
-        id={&id_name}
-        class="dark:bg-gray-700 appearance-none border-2 border-gray-200 rounded w-full py-2 px-4 dark:text-gray-300 leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
-        node_ref=node_ref_cust
-    />
-    </div>
-    </div>}
+// GET /audit answers with a JSON array of AuditEvent, without `from_backend`
+// (see fixtures/audit.json)
+async fn get_backend_audit_log() -> Result<Vec<AuditEvent>, String> {
+    let resp = backend_get(&api_url("/audit"))
+        .await
+        .map_err(|err| err.to_string())?;
+    let mut events: Vec<AuditEvent> = resp.json().await.map_err(|err| err.to_string())?;
+    for event in events.iter_mut() {
+        event.from_backend = true;
+    }
+    Ok(events)
+}
+
+// (field, before, after) for every field the event touched
+fn audit_changes(event: &AuditEvent) -> Vec<(String, String, String)> {
+    let as_text = |value: Option<&serde_json::Value>| match value {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    let empty = serde_json::Map::new();
+    let before = event
+        .before
+        .as_ref()
+        .and_then(|v| v.as_object())
+        .unwrap_or(&empty);
+    let after = event
+        .after
+        .as_ref()
+        .and_then(|v| v.as_object())
+        .unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+    fields
+        .into_iter()
+        .filter(|field| before.get(*field) != after.get(*field))
+        .map(|field| {
+            (
+                field.clone(),
+                as_text(before.get(field)),
+                as_text(after.get(field)),
+            )
+        })
+        .collect()
 }
 
 #[component]
-fn FormSelectCust(
-    cx: Scope,
-    node_ref_cust: NodeRef<Select>,
-    id_name: String,
-    label_text: String,
-) -> impl IntoView {
-    view! {cx,
-        <div class="md:flex md:items-center mb-6">
+fn AuditLogComponent(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+    let audit_supported = use_capability(cx, "audit");
+    let backend_events = create_resource(
+        cx,
+        move || audit_supported.get(),
+        |supported| async move {
+            if supported {
+                get_backend_audit_log().await
+            } else {
+                Err("The backend does not report an audit log".to_string())
+            }
+        },
+    );
+
+    let (target_filter, set_target_filter) = create_signal(cx, None::<AuditTarget>);
+    let (action_filter, set_action_filter) = create_signal(cx, None::<AuditAction>);
+    let (text_filter, set_text_filter) = create_signal(cx, String::new());
+
+    let local_events = create_rw_signal(cx, load_audit_log());
+    window_event_listener_untyped(AUDIT_CHANGED_EVENT, move |_| local_events.set(load_audit_log()));
+
+    let events = move || {
+        let mut events = local_events.get();
+        if let Some(Ok(remote)) = backend_events.read(cx) {
+            events.extend(remote);
+        }
+        let text = text_filter.get().to_lowercase();
+        events.retain(|event| {
+            !matches!(target_filter.get(), Some(t) if t != event.target)
+                && !matches!(action_filter.get(), Some(a) if a != event.action)
+                && (text.is_empty()
+                    || event.actor.to_lowercase().contains(&text)
+                    || event.key.to_lowercase().contains(&text))
+        });
+        events.sort_by_key(|event| std::cmp::Reverse(event.at));
+        events
+    };
+
+    let rows = move || {
+        let settings = settings.get();
+        events()
+            .into_iter()
+            .map(|event| {
+                let changes = audit_changes(&event)
+                    .into_iter()
+                    .map(|(field, before, after)| {
+                        let change = match event.action {
+                            AuditAction::Create => format!("{field}: {after}"),
+                            AuditAction::Delete => format!("{field}: {before}"),
+                            AuditAction::Update => format!("{field}: {before} -> {after}"),
+                        };
+                        view! { cx, <li>{change}</li> }
+                    })
+                    .collect::<Vec<_>>();
+                view! { cx,
+                    <tr class="border-b align-top">
+                        <td class="p-2">{format_timestamp(&event.at, &settings)}</td>
+                        <td class="p-2">{event.actor}</td>
+                        <td class="p-2">{format!("{:?}", event.action)}</td>
+                        <td class="p-2">{event.target.label()}</td>
+                        <td class="p-2 font-mono">{event.key}</td>
+                        <td class="p-2">{if event.ok { "ok" } else { "failed" }}</td>
+                        <td class="p-2"><ul class="font-mono text-xs">{changes}</ul></td>
+                        <td class="p-2">{if event.from_backend { "backend" } else { "this browser" }}</td>
+                    </tr>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
 
-          <div class="md:w-1/3">
-          <label class="block dark:text-gray-300 font-bold md:text-right mb-1 md:mb-0 pr-4" for={&id_name}>
-          {label_text}
-          </label>
+    let select_class = "dark:bg-gray-700 appearance-none dark:text-gray-300 border-2 border-gray-200 rounded py-2 px-4 mr-2 leading-tight";
+
+    view! { cx,
+        <div class="audit_log p-5 dark:text-gray-300">
+            <h1 class="text-2xl font-bold mb-4">"Audit log"</h1>
+            <Suspense fallback=|| ()>
+                {move || matches!(backend_events.read(cx), Some(Err(_))).then(|| view! { cx,
+                    <p class="mb-4">"The backend has no audit log, showing the changes made from this browser."</p>
+                })}
+            </Suspense>
+            <div class="flex items-center mb-4">
+                <select class=select_class on:change=move |ev| {
+                    set_target_filter.set(match event_target_value(&ev).as_str() {
+                        "rapi" => Some(AuditTarget::RapiNode),
+                        "mbtcp" => Some(AuditTarget::ModbusMapping),
+                        "mqtt" => Some(AuditTarget::MqttMapping),
+                        _ => None,
+                    })
+                }>
+                    <option value="">"All targets"</option>
+                    <option value="rapi">"rAPI nodes"</option>
+                    <option value="mbtcp">"Modbus mappings"</option>
+                    <option value="mqtt">"MQTT mappings"</option>
+                </select>
+                <select class=select_class on:change=move |ev| {
+                    set_action_filter.set(match event_target_value(&ev).as_str() {
+                        "create" => Some(AuditAction::Create),
+                        "update" => Some(AuditAction::Update),
+                        "delete" => Some(AuditAction::Delete),
+                        _ => None,
+                    })
+                }>
+                    <option value="">"All actions"</option>
+                    <option value="create">"Create"</option>
+                    <option value="update">"Update"</option>
+                    <option value="delete">"Delete"</option>
+                </select>
+                <input type="text"
+                class="dark:bg-gray-700 border-2 border-gray-200 rounded py-2 px-4 leading-tight"
+                placeholder="Actor or uid"
+                on:input=move |ev| set_text_filter.set(event_target_value(&ev))
+                />
+            </div>
+            <table class="w-full text-left text-sm">
+                <thead>
+                    <tr class="border-b font-bold">
+                        <th class="p-2">"When"</th>
+                        <th class="p-2">"Actor"</th>
+                        <th class="p-2">"Action"</th>
+                        <th class="p-2">"Target"</th>
+                        <th class="p-2">"Uid"</th>
+                        <th class="p-2">"Result"</th>
+                        <th class="p-2">"Changes"</th>
+                        <th class="p-2">"Source"</th>
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
         </div>
-        <div class="md:w-2/3">
-        <select
-        id={&id_name}
-        class="dark:bg-gray-700 appearance-none dark:text-gray-300 border-2 border-gray-200 rounded w-full py-2 px-4  leading-tight focus:bg-gray-200 focus:outline-none dark:focus:bg-gray-500 focus:border-gray-700"
-        node_ref=node_ref_cust
-        >
-        <option value="rw">rw</option>
-        <option value="r">r</option>
-        <option value="w">w</option>
-        </select>
-    </div>
-    </div>}
+    }
 }
 
-#[component]
-fn NewMbtcpNode(cx: Scope) -> impl IntoView {
-    let input_element_ip: NodeRef<Input> = create_node_ref(cx);
-    let input_element_port: NodeRef<Input> = create_node_ref(cx);
+// Whether the last request reached the backend
+#[derive(Debug, Clone, Copy)]
+struct BackendOnline(RwSignal<bool>);
 
-    let select_element_rw: NodeRef<Select> = create_node_ref(cx);
+fn use_outbox(cx: Scope) -> RwSignal<Vec<OutboxItem>> {
+    use_context::<RwSignal<Vec<OutboxItem>>>(cx).expect("outbox is provided by App")
+}
 
-    let input_element_lock_to_uid: NodeRef<Input> = create_node_ref(cx);
-    let input_element_register: NodeRef<Input> = create_node_ref(cx);
+fn use_backend_online(cx: Scope) -> RwSignal<bool> {
+    use_context::<BackendOnline>(cx)
+        .expect("backend state is provided by App")
+        .0
+}
 
-    let on_submit = move |ev: SubmitEvent| {
-        ev.prevent_default();
+// Keeps the outbox signal in sync with localStorage and replays queued
+// changes once the backend can be reached again
+#[component]
+fn OutboxWatcher(cx: Scope) -> impl IntoView {
+    let outbox = use_outbox(cx);
+    let backend_online = use_backend_online(cx);
+
+    window_event_listener_untyped(OUTBOX_CHANGED_EVENT, move |_| outbox.set(load_outbox()));
+    window_event_listener_untyped(BACKEND_OFFLINE_EVENT, move |_| backend_online.set(false));
+    window_event_listener_untyped(BACKEND_ONLINE_EVENT, move |_| {
+        // Only replay on the transition, the interval covers the rest
+        if !backend_online.get_untracked() {
+            backend_online.set(true);
+            spawn_local(replay_outbox());
+        }
+    });
+    window_event_listener_untyped("offline", move |_| backend_online.set(false));
+    window_event_listener_untyped("online", move |_| spawn_local(replay_outbox()));
+
+    // Probe the backend while changes are waiting
+    let retry = move || {
+        if outbox.with_untracked(|items| items.iter().any(|i| i.error.is_none())) {
+            spawn_local(replay_outbox());
+        }
+    };
+    retry();
+    if let Ok(handle) = set_interval_with_handle(retry, std::time::Duration::from_secs(10)) {
+        on_cleanup(cx, move || handle.clear());
+    }
+}
 
-        let value_ip = input_element_ip.get().expect("<input> to exist").value();
-        let value_port = input_element_port.get().expect("<input> to exist").value();
-        let value_lock_to_uid = input_element_lock_to_uid
-            .get()
-            .expect("<input> to exist")
-            .value();
-        let value_register = input_element_register
-            .get()
-            .expect("<input> to exist")
-            .value();
+// Sidebar badge with the connection state and the number of queued changes
+#[component]
+fn OutboxIndicator(cx: Scope) -> impl IntoView {
+    let outbox = use_outbox(cx);
+    let backend_online = use_backend_online(cx);
 
-        let value_rw = select_element_rw.get().expect("<select> to exist").value();
-        let mut map = HashMap::new();
+    view! { cx,
+        <A href="/outbox" class="block mt-6 mx-2 text-sm dark:text-gray-300">
+            <span
+            class="inline-block w-2 h-2 mr-2 rounded-full"
+            class:bg-green-500=move || backend_online.get()
+            class:bg-red-500=move || !backend_online.get()
+            ></span>
+            {move || if backend_online.get() { "Backend online" } else { "Backend offline" }}
+            {move || {
+                let count = outbox.with(|items| items.len());
+                (count > 0).then(|| view! { cx,
+                    <span class="ml-2 px-2 rounded-full bg-yellow-400 text-gray-900 font-bold">
+                        {format!("{count} pending")}
+                    </span>
+                })
+            }}
+        </A>
+    }
+}
 
-        map.insert("mb_lock_to_uid", value_lock_to_uid);
-        map.insert("mb_ip", value_ip);
-        map.insert("mb_port", value_port);
-        map.insert("mb_rw", value_rw);
-        map.insert("mb_register", value_register);
+// Lists the changes waiting for the backend, single items or the whole queue can be discarded
+#[component]
+fn OutboxComponent(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+    let outbox = use_outbox(cx);
+    let backend_online = use_backend_online(cx);
+
+    let rows = move || {
+        let settings = settings.get();
+        outbox
+            .get()
+            .into_iter()
+            .map(|item| {
+                let id = item.id;
+                let key = item.body.get(item.target.key_field()).cloned().unwrap_or_default();
+                let mut fields: Vec<String> = item
+                    .body
+                    .iter()
+                    .map(|(field, value)| format!("{field}: {value}"))
+                    .collect();
+                fields.sort();
+                let fields = fields
+                    .into_iter()
+                    .map(|field| view! { cx, <li>{field}</li> })
+                    .collect::<Vec<_>>();
+                view! { cx,
+                    <tr class="border-b align-top">
+                        <td class="p-2">{format_timestamp(&item.queued_at, &settings)}</td>
+                        <td class="p-2">{format!("{:?}", item.action)}</td>
+                        <td class="p-2">{item.target.label()}</td>
+                        <td class="p-2 font-mono">{key}</td>
+                        <td class="p-2"><ul class="font-mono text-xs">{fields}</ul></td>
+                        <td class="p-2 text-red-500">{item.error.unwrap_or_else(|| "Waiting".to_string())}</td>
+                        <td class="p-2">
+                            <button
+                            class="bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-1 px-3 rounded"
+                            on:click=move |_| update_outbox(|items| items.retain(|i| i.id != id))
+                            >
+                            "Discard"
+                            </button>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
 
-        spawn_local(async move {
-            let resp = post_data("http://127.0.0.1:8000/cmbtcp", map).await;
-            if resp == "200".to_string() {
-            } else {
-                log!("Error on server side, needs better handler later!");
+    let on_retry = move |_| {
+        // A refused item blocks the queue, give it another chance
+        update_outbox(|items| {
+            for item in items.iter_mut() {
+                item.error = None;
             }
         });
+        spawn_local(replay_outbox());
     };
 
-    view! { cx,
-        <div class="m-5 p-5 max-w-sm rounded overflow-hidden shadow-lg border">
-        <div class="new_node_form ">
-            <form class="w-full max-w-sm p-4"
-            on:submit=on_submit>
+    let on_discard_all = move |_| {
+        if window()
+            .confirm_with_message("Discard all pending changes?")
+            .unwrap_or(false)
+        {
+            store_outbox(&[]);
+        }
+    };
 
-            <FormInputCust node_ref_cust=input_element_lock_to_uid
-            label_text="Mb lock to uid: ".to_string()
-            id_name="mb_lock_to_id".to_string() />
+    view! { cx,
+        <div class="outbox p-5 dark:text-gray-300">
+            <h1 class="text-2xl font-bold mb-4">"Pending changes"</h1>
+            <p class="mb-4">
+                {move || if backend_online.get() {
+                    "The backend is reachable."
+                } else {
+                    "The backend cannot be reached, changes are queued and sent in order once it is back."
+                }}
+            </p>
+            <Show
+            when=move || outbox.with(|items| !items.is_empty())
+            fallback=|cx| view! { cx, <p>"No changes are waiting."</p> }
+            >
+                <div class="mb-4">
+                    <button
+                    class="bg-gray-300 dark:bg-gray-600 hover:bg-gray-400 font-medium py-2 px-4 mr-2 rounded"
+                    on:click=on_retry
+                    >
+                    "Retry now"
+                    </button>
+                    <button
+                    class="bg-red-400 hover:bg-red-500 font-medium py-2 px-4 rounded"
+                    on:click=on_discard_all
+                    >
+                    "Discard all"
+                    </button>
+                </div>
+                <table class="w-full text-left text-sm">
+                    <thead>
+                        <tr class="border-b font-bold">
+                            <th class="p-2">"Queued"</th>
+                            <th class="p-2">"Action"</th>
+                            <th class="p-2">"Target"</th>
+                            <th class="p-2">"Uid"</th>
+                            <th class="p-2">"Fields"</th>
+                            <th class="p-2">"State"</th>
+                            <th class="p-2"></th>
+                        </tr>
+                    </thead>
+                    <tbody>{rows}</tbody>
+                </table>
+            </Show>
+        </div>
+    }
+}
 
-            <FormInputCust node_ref_cust=input_element_ip
-            label_text="Mb IP: ".to_string()
-            id_name="mb_ip".to_string() />
+// Routes below /site/:site talk to that backend
+#[component]
+fn SiteScope(cx: Scope) -> impl IntoView {
+    let params = use_params_map(cx);
+    let site = move || params.with(|params| params.get("site").cloned().unwrap_or_default());
 
+    // Effects run once when created, so the switch happens before the
+    // children render and their first request already goes to the right backend
+    let known = create_rw_signal(cx, false);
+    create_effect(cx, move |_| known.set(activate_backend(cx, &site())));
 
-            <FormInputCust node_ref_cust=input_element_port
-            label_text="Mb port: ".to_string()
-            id_name="mb_port".to_string() />
+    view! { cx,
+        <Show
+        when=move || known.get()
+        fallback=move |cx| view! { cx,
+            <p class="m-5 dark:text-gray-300">{format!("There is no site called \"{}\", add it in the settings.", site())}</p>
+        }
+        >
+            <Outlet/>
+        </Show>
+    }
+}
 
+// Sidebar select for the backend the UI talks to
+#[component]
+fn SiteSwitcher(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+    let site = use_active_site(cx);
+    let navigate = use_navigate(cx);
+
+    let on_change = move |ev| {
+        let id = event_target_value(&ev);
+        if activate_backend(cx, &id) {
+            let _ = navigate(&site_path(&id, ""), Default::default());
+        }
+    };
 
+    view! { cx,
+        <div class="mt-6 mx-2">
+            <label class="block text-sm dark:text-gray-300 mb-1" for="site_switcher">"Site: "</label>
+            <select
+            id="site_switcher"
+            class="dark:bg-gray-700 appearance-none dark:text-gray-300 border-2 border-gray-200 rounded w-full py-2 px-4 leading-tight"
+            on:change=on_change
+            >
+            {move || settings.with(|s| {
+                s.backends
+                    .iter()
+                    .map(|backend| {
+                        let id = backend.id.clone();
+                        view! { cx,
+                            <option value=backend.id.clone() selected=move || site.get() == id>{backend.name.clone()}</option>
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })}
+            </select>
+        </div>
+    }
+}
 
-            <FormInputCust node_ref_cust=input_element_register
-            label_text="Mb register: ".to_string()
-            id_name="mb_register".to_string() />
+// One row of the all sites overview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SiteSummary {
+    backend: Backend,
+    overview: SystemOverview,
+}
 
-            <FormSelectCust node_ref_cust=select_element_rw
-            label_text="Mb read/write: ".to_string()
-            id_name="mb_read_write_select".to_string() />
+async fn get_all_site_overviews(backends: Vec<Backend>) -> Vec<SiteSummary> {
+    futures::future::join_all(backends.into_iter().map(|backend| async move {
+        let overview = get_site_overview(backend.clone()).await;
+        SiteSummary { backend, overview }
+    }))
+    .await
+}
 
-            <FormSubmitButton />
+// Node counts and health of every configured backend
+#[component]
+fn SitesOverview(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+    let (refresh, set_refresh) = create_signal(cx, 0);
+    let summaries = create_resource(
+        cx,
+        move || (refresh.get(), settings.with(|s| s.backends.clone())),
+        |(_, backends)| get_all_site_overviews(backends),
+    );
 
+    let rows = move || {
+        summaries.read(cx).map(|summaries| {
+            let settings = settings.get();
+            let mut total = (0, 0, 0, 0);
+            let mut rows = summaries
+                .into_iter()
+                .map(|summary| {
+                    let overview = summary.overview;
+                    let stale = overview
+                        .rapi
+                        .iter()
+                        .filter(|node| is_stale(node, settings.stale_after_secs))
+                        .count();
+                    total.0 += overview.rapi.len();
+                    total.1 += overview.modbus.len();
+                    total.2 += overview.mqtt.len();
+                    total.3 += stale;
+                    view! { cx,
+                        <tr class="border-b">
+                            <td class="p-2">
+                                <A class="underline hover:text-gray-500" href=site_path(&summary.backend.id, "")>{summary.backend.name}</A>
+                            </td>
+                            <td class="p-2 font-mono">{summary.backend.url}</td>
+                            <td class="p-2" class:text-red-500=!overview.reachable>
+                                {if overview.reachable { "Reachable" } else { "Unreachable" }}
+                            </td>
+                            <td class="p-2">{overview.rapi.len()}</td>
+                            <td class="p-2">{overview.modbus.len()}</td>
+                            <td class="p-2">{overview.mqtt.len()}</td>
+                            <td class="p-2">{stale}</td>
+                        </tr>
+                    }
+                })
+                .collect::<Vec<_>>();
+            rows.push(view! { cx,
+                <tr class="font-bold">
+                    <td class="p-2">"Total"</td>
+                    <td class="p-2"></td>
+                    <td class="p-2"></td>
+                    <td class="p-2">{total.0}</td>
+                    <td class="p-2">{total.1}</td>
+                    <td class="p-2">{total.2}</td>
+                    <td class="p-2">{total.3}</td>
+                </tr>
+            });
+            rows
+        })
+    };
 
-        </form>
-        </div>
+    view! { cx,
+        <div class="sites p-5 dark:text-gray-300">
+            <div class="flex items-center mb-4">
+                <h1 class="text-2xl font-bold">"All sites"</h1>
+                <button
+                class="ml-auto bg-blue-600 text-gray-200 hover:bg-blue-900 dark:text-gray-300 font-bold py-2 px-4 rounded"
+                on:click=move |_| set_refresh.update(|n| *n += 1)
+                >
+                "Refresh"
+                </button>
+            </div>
+            <table class="w-full text-left text-sm">
+                <thead>
+                    <tr class="border-b font-bold">
+                        <th class="p-2">"Site"</th>
+                        <th class="p-2">"Url"</th>
+                        <th class="p-2">"Backend"</th>
+                        <th class="p-2">"rAPI nodes"</th>
+                        <th class="p-2">"Modbus mappings"</th>
+                        <th class="p-2">"MQTT mappings"</th>
+                        <th class="p-2">"Stale nodes"</th>
+                    </tr>
+                </thead>
+                <Suspense fallback=move || view! { cx, <tbody><tr><td class="p-2">"Loading..."</td></tr></tbody> }>
+                    <tbody>{rows}</tbody>
+                </Suspense>
+            </table>
         </div>
-
     }
 }
 
-#[component]
-fn NewMqttNode(cx: Scope) -> impl IntoView {
-    let input_element_ip: NodeRef<Input> = create_node_ref(cx);
-    let input_element_topic: NodeRef<Input> = create_node_ref(cx);
+// Result of copying one rAPI node with its mappings to another site
+#[derive(Debug, Clone)]
+struct CopyResult {
+    node_name: String,
+    source_uid: String,
+    lines: Vec<(bool, String)>,
+}
 
-    let select_element_rw: NodeRef<Select> = create_node_ref(cx);
+// Creates the nodes on `target` with new uids and their mappings locked to them
+async fn copy_nodes_to_site(
+    target: Backend,
+    nodes: Vec<RapiStruct>,
+    modbus: Vec<ModbusStruct>,
+    mqtt: Vec<MqttStruct>,
+    results: WriteSignal<Vec<CopyResult>>,
+) {
+    let mut uids = match take_uids_from(&target, nodes.len()).await {
+        Ok(uids) => uids.into_iter(),
+        Err(err) => {
+            results.set(vec![CopyResult {
+                node_name: String::new(),
+                source_uid: String::new(),
+                lines: vec![(false, format!("Could not get uids from {}: {err}", target.name))],
+            }]);
+            return;
+        }
+    };
 
-    let input_element_lock_to_uid: NodeRef<Input> = create_node_ref(cx);
-    let input_element_topic_modif: NodeRef<Input> = create_node_ref(cx);
+    for node in nodes {
+        let new_uid = uids.next().unwrap_or_default();
+        let mut result = CopyResult {
+            node_name: node.node_name.clone(),
+            source_uid: node.node_uid.clone(),
+            lines: Vec::new(),
+        };
+
+        let mut map = rapi_to_clone_map(&node);
+        map.insert("node_uid", new_uid.clone());
+        let resp = audited_post_now(
+            &format!("{}/u", target.url),
+            map,
+            AuditAction::Create,
+            AuditTarget::RapiNode,
+            None,
+        )
+        .await;
+        if resp != "200" {
+            result.lines.push((false, format!("Could not create the node: {}", unsent_reason(&resp))));
+            results.update(|r| r.push(result));
+            release_uids_to(&target, vec![new_uid]);
+            continue;
+        }
+        result.lines.push((true, format!("Created as {new_uid}")));
+
+        let mappings = modbus
+            .iter()
+            .filter(|mb| mb.mb_lock_to_uid == node.node_uid)
+            .map(|mb| {
+                WizardMapping::Modbus(ModbusStruct {
+                    mb_lock_to_uid: new_uid.clone(),
+                    ..mb.clone()
+                })
+            })
+            .chain(
+                mqtt.iter()
+                    .filter(|mq| mq.mqtt_lock_to_uid == node.node_uid)
+                    .map(|mq| {
+                        WizardMapping::Mqtt(MqttStruct {
+                            mqtt_lock_to_uid: new_uid.clone(),
+                            ..mq.clone()
+                        })
+                    }),
+            );
+        for mapping in mappings {
+            let resp = mapping.create_on(&target).await;
+            if resp == "200" {
+                result.lines.push((true, format!("Created {}", mapping.summary())));
+            } else {
+                result
+                    .lines
+                    .push((false, format!("Could not create {}: {}", mapping.summary(), unsent_reason(&resp))));
+            }
+        }
 
-    let on_submit = move |ev: SubmitEvent| {
-        ev.prevent_default();
+        results.update(|r| r.push(result));
+    }
+}
 
-        let value_ip = input_element_ip.get().expect("<input> to exist").value();
-        let value_topic = input_element_topic.get().expect("<input> to exist").value();
-        let value_lock_to_uid = input_element_lock_to_uid
-            .get()
-            .expect("<input> to exist")
-            .value();
-        let value_topic_modif = input_element_topic_modif
-            .get()
-            .expect("<input> to exist")
-            .value();
+// Copies selected rAPI nodes of the active site, with their mappings, to another site
+#[component]
+fn CopyToSite(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+    let site = use_active_site(cx);
+    let overview = create_resource(cx, move || site.get(), |_| get_system_overview());
+
+    let selected = create_rw_signal(cx, HashSet::<String>::new());
+    let (target, set_target) = create_signal(cx, String::new());
+    let (results, set_results) = create_signal(cx, Vec::<CopyResult>::new());
+    let (running, set_running) = create_signal(cx, false);
+
+    let targets = move || {
+        settings.with(|s| {
+            s.backends
+                .iter()
+                .filter(|b| b.id != site.get())
+                .map(|b| view! { cx, <option value=b.id.clone()>{b.name.clone()}</option> })
+                .collect::<Vec<_>>()
+        })
+    };
 
-        let value_rw = select_element_rw.get().expect("<select> to exist").value();
-        let mut map = HashMap::new();
-        map.insert("mqtt_lock_to_uid", value_lock_to_uid);
-        map.insert("mqtt_ip", value_ip);
-        map.insert("mqtt_topic", value_topic);
-        map.insert("mqtt_rw", value_rw);
-        map.insert("mqtt_topic_modif", value_topic_modif);
+    let node_rows = move || {
+        overview.read(cx).map(|overview| {
+            overview
+                .rapi
+                .iter()
+                .map(|node| {
+                    let uid = node.node_uid.clone();
+                    let uid_check = uid.clone();
+                    let mb_count = overview.modbus.iter().filter(|mb| mb.mb_lock_to_uid == uid).count();
+                    let mqtt_count = overview.mqtt.iter().filter(|mq| mq.mqtt_lock_to_uid == uid).count();
+                    view! { cx,
+                        <tr class="border-b">
+                            <td class="p-2">
+                                <input type="checkbox"
+                                prop:checked=move || selected.with(|s| s.contains(&uid_check))
+                                on:change=move |ev| {
+                                    let checked = event_target_checked(&ev);
+                                    selected.update(|s| {
+                                        if checked {
+                                            s.insert(uid.clone());
+                                        } else {
+                                            s.remove(&uid);
+                                        }
+                                    })
+                                }
+                                />
+                            </td>
+                            <td class="p-2">{node.node_name.clone()}</td>
+                            <td class="p-2 font-mono">{node.node_uid.clone()}</td>
+                            <td class="p-2">{mb_count}</td>
+                            <td class="p-2">{mqtt_count}</td>
+                        </tr>
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let on_select_all = move |ev| {
+        let checked = event_target_checked(&ev);
+        let all = overview
+            .read(cx)
+            .map(|o| rapi_uids(&o.rapi))
+            .unwrap_or_default();
+        selected.set(if checked { all } else { HashSet::new() });
+    };
+
+    let on_copy = move |_| {
+        let Some(overview) = overview.read(cx) else {
+            return;
+        };
+        let Some(target) = settings.with_untracked(|s| {
+            s.backends
+                .iter()
+                .find(|b| b.id == target.get_untracked())
+                .cloned()
+        }) else {
+            return;
+        };
+        let nodes: Vec<RapiStruct> = selected.with_untracked(|s| {
+            overview
+                .rapi
+                .iter()
+                .filter(|node| s.contains(&node.node_uid))
+                .cloned()
+                .collect()
+        });
+        if nodes.is_empty() {
+            return;
+        }
+        // The copies are never queued, nothing may overtake waiting changes
+        if !load_outbox().is_empty() {
+            set_results.set(vec![CopyResult {
+                node_name: String::new(),
+                source_uid: String::new(),
+                lines: vec![(false, unsent_reason("409"))],
+            }]);
+            return;
+        }
 
+        set_results.set(Vec::new());
+        set_running.set(true);
         spawn_local(async move {
-            let resp = post_data("http://127.0.0.1:8000/cmqtt", map).await;
-            if resp == "200".to_string() {
-            } else {
-                log!("Error on server side, needs better handler later!");
-            }
+            copy_nodes_to_site(target, nodes, overview.modbus, overview.mqtt, set_results).await;
+            set_running.set(false);
         });
     };
 
+    let result_rows = move || {
+        results
+            .get()
+            .into_iter()
+            .map(|result| {
+                let lines = result
+                    .lines
+                    .into_iter()
+                    .map(|(ok, line)| view! { cx, <li class:text-red-500=!ok>{line}</li> })
+                    .collect::<Vec<_>>();
+                view! { cx,
+                    <tr class="border-b align-top">
+                        <td class="p-2">{result.node_name}</td>
+                        <td class="p-2 font-mono">{result.source_uid}</td>
+                        <td class="p-2"><ul>{lines}</ul></td>
+                    </tr>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
     view! { cx,
-        <div class="m-5 p-5 max-w-sm rounded overflow-hidden shadow-lg border">
+        <div class="copy_to_site p-5 dark:text-gray-300">
+            <h1 class="text-2xl font-bold mb-4">"Copy to site"</h1>
+            <RequiresRole role=Role::Engineer>
+                <div class="flex items-center mb-4">
+                    <label class="mr-2" for="copy_target">"Target site: "</label>
+                    <select
+                    id="copy_target"
+                    class="dark:bg-gray-700 appearance-none dark:text-gray-300 border-2 border-gray-200 rounded py-2 px-4 mr-2 leading-tight"
+                    on:change=move |ev| set_target.set(event_target_value(&ev))
+                    >
+                        <option value="">"Choose a site"</option>
+                        {targets}
+                    </select>
+                    <button
+                    class="bg-blue-600 text-gray-200 hover:bg-blue-900 dark:text-gray-300 font-bold py-2 px-4 rounded disabled:opacity-50"
+                    prop:disabled=move || running.get() || target.get().is_empty() || selected.with(|s| s.is_empty())
+                    on:click=on_copy
+                    >
+                    {move || if running.get() { "Copying..." } else { "Copy selected" }}
+                    </button>
+                </div>
+                <table class="w-full text-left text-sm mb-6">
+                    <thead>
+                        <tr class="border-b font-bold">
+                            <th class="p-2"><input type="checkbox" on:change=on_select_all/></th>
+                            <th class="p-2">"Name"</th>
+                            <th class="p-2">"Uid"</th>
+                            <th class="p-2">"Modbus mappings"</th>
+                            <th class="p-2">"MQTT mappings"</th>
+                        </tr>
+                    </thead>
+                    <Suspense fallback=move || view! { cx, <tbody><tr><td class="p-2">"Loading..."</td></tr></tbody> }>
+                        <tbody>{node_rows}</tbody>
+                    </Suspense>
+                </table>
+                <Show when=move || results.with(|r| !r.is_empty()) fallback=|_| ()>
+                    <h3 class="font-bold text-lg mb-2">"Results"</h3>
+                    <table class="w-full text-left text-sm">
+                        <thead>
+                            <tr class="border-b font-bold">
+                                <th class="p-2">"Node"</th>
+                                <th class="p-2">"Source uid"</th>
+                                <th class="p-2">"Result"</th>
+                            </tr>
+                        </thead>
+                        <tbody>{result_rows}</tbody>
+                    </table>
+                </Show>
+            </RequiresRole>
+        </div>
+    }
+}
 
-        <div class="new_node_form">
-            <form on:submit=on_submit>
+// What GET /version answers, both fields are optional for older backends
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct VersionResponse {
+    version: Option<String>,
+    capabilities: Option<Vec<String>>,
+}
 
+// Outcome of one request made by the health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EndpointCheck {
+    path: String,
+    ok: bool,
+    // HTTP status or the error text
+    result: String,
+    latency_ms: u32,
+    // Number of documents, for the /getall/ endpoints
+    count: Option<usize>,
+}
 
-            <FormInputCust node_ref_cust=input_element_lock_to_uid
-            label_text="Mqtt lock to uid: ".to_string()
-            id_name="mqtt_lock_to_id".to_string() />
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HealthReport {
+    url: String,
+    checked_at: DateTime,
+    reachable: bool,
+    latency_ms: u32,
+    version: Option<String>,
+    // None when the backend does not report them, every feature stays enabled then
+    capabilities: Option<Vec<String>>,
+    endpoints: Vec<EndpointCheck>,
+}
 
-            <FormInputCust node_ref_cust=input_element_ip
-            label_text="Mqtt IP: ".to_string()
-            id_name="mqtt_ip".to_string() />
+// Latest health report of the active backend, provided by App
+#[derive(Debug, Clone, Copy)]
+struct BackendHealth(RwSignal<Option<HealthReport>>);
 
+fn use_backend_health(cx: Scope) -> RwSignal<Option<HealthReport>> {
+    use_context::<BackendHealth>(cx)
+        .expect("backend health is provided by App")
+        .0
+}
 
-            <FormInputCust node_ref_cust=input_element_topic
-            label_text="Mqtt topic: ".to_string()
-            id_name="mqtt_port".to_string() />
+// False only when the backend lists its capabilities and `name` is not among them
+fn use_capability(cx: Scope, name: &'static str) -> Signal<bool> {
+    let health = use_backend_health(cx);
+    Signal::derive(cx, move || {
+        health.with(|h| {
+            !matches!(
+                h.as_ref().and_then(|h| h.capabilities.as_ref()),
+                Some(caps) if !caps.iter().any(|c| c == name)
+            )
+        })
+    })
+}
 
+// How often the sidebar indicator pings the backend
+const HEALTH_PING_SECS: u64 = 30;
 
+async fn timed_get(url: &str) -> (Result<reqwest::Response, reqwest::Error>, u32) {
+    let started = js_sys::Date::now();
+    let resp = with_auth(reqwest::Client::new().get(url), url).send().await;
+    (resp, (js_sys::Date::now() - started) as u32)
+}
 
-            <FormInputCust node_ref_cust=input_element_topic_modif
-            label_text="Mqtt topic modif: ".to_string()
-            id_name="mqtt_register".to_string() />
+// Pings /version, and with `endpoints` every /getall/ endpoint as well
+async fn check_backend_health(backend: Backend, endpoints: bool) -> HealthReport {
+    let (resp, latency_ms) = timed_get(&format!("{}/version", backend.url)).await;
+    report_connectivity(resp.is_ok());
+
+    let mut checks = Vec::new();
+    let (reachable, version) = match resp {
+        Ok(resp) => {
+            let status = resp.status();
+            checks.push(EndpointCheck {
+                path: "/version".to_string(),
+                ok: status.is_success(),
+                result: status.to_string(),
+                latency_ms,
+                count: None,
+            });
+            let version = if status.is_success() {
+                resp.json::<VersionResponse>().await.unwrap_or_default()
+            } else {
+                VersionResponse::default()
+            };
+            (true, version)
+        }
+        Err(err) => {
+            checks.push(EndpointCheck {
+                path: "/version".to_string(),
+                ok: false,
+                result: err.to_string(),
+                latency_ms,
+                count: None,
+            });
+            (false, VersionResponse::default())
+        }
+    };
 
-            <FormSelectCust node_ref_cust=select_element_rw
-            label_text="Mqtt read/write: ".to_string()
-            id_name="mqtt_read_write_select".to_string() />
+    if endpoints && reachable {
+        for endpoint in ["bucket", "mbstuff", "mqttstuff"] {
+            let path = format!("/getall/{endpoint}");
+            let (resp, latency_ms) = timed_get(&format!("{}{path}", backend.url)).await;
+            let check = match resp {
+                Ok(resp) => {
+                    let status = resp.status();
+                    let count = match resp.json::<Vec<serde_json::Value>>().await {
+                        Ok(docs) => Some(docs.len()),
+                        Err(_) => None,
+                    };
+                    EndpointCheck {
+                        path,
+                        ok: status.is_success() && count.is_some(),
+                        result: if count.is_some() || !status.is_success() {
+                            status.to_string()
+                        } else {
+                            format!("{status}, not a JSON list")
+                        },
+                        latency_ms,
+                        count,
+                    }
+                }
+                Err(err) => EndpointCheck {
+                    path,
+                    ok: false,
+                    result: err.to_string(),
+                    latency_ms,
+                    count: None,
+                },
+            };
+            checks.push(check);
+        }
+    }
 
-            <FormSubmitButton />
+    HealthReport {
+        url: backend.url,
+        checked_at: DateTime::now(),
+        reachable,
+        latency_ms,
+        version: version.version,
+        capabilities: version.capabilities,
+        endpoints: checks,
+    }
+}
 
-        </form>
-        </div>
-        </div>
+// Pings the active backend every HEALTH_PING_SECS and when the site changes
+#[component]
+fn HealthWatcher(cx: Scope) -> impl IntoView {
+    let health = use_backend_health(cx);
+    let site = use_active_site(cx);
 
+    let ping = move || {
+        spawn_local(async move {
+            let mut report = check_backend_health(active_backend(), false).await;
+            health.update(|h| {
+                // Keep the /getall/ results of the last full check of this backend
+                if let Some(previous) = h.as_ref().filter(|p| p.url == report.url) {
+                    report
+                        .endpoints
+                        .extend(previous.endpoints.iter().skip(1).cloned());
+                }
+                *h = Some(report);
+            });
+        })
+    };
+    create_effect(cx, move |_| {
+        site.track();
+        ping();
+    });
+    if let Ok(handle) = set_interval_with_handle(ping, std::time::Duration::from_secs(HEALTH_PING_SECS)) {
+        on_cleanup(cx, move || handle.clear());
     }
 }
 
+// Sidebar line with version and latency of the active backend
 #[component]
-fn NoNewNode(cx: Scope) -> impl IntoView {
+fn HealthIndicator(cx: Scope) -> impl IntoView {
+    let health = use_backend_health(cx);
+
     view! { cx,
-        <div class="new_node dark:text-gray-300">
-            "There are no new nodes for this protocol"
-        </div>
+        <A href="/status" class="block mt-6 mx-2 text-sm dark:text-gray-300">
+            {move || match health.get() {
+                None => "Checking backend...".to_string(),
+                Some(report) if !report.reachable => "Backend not responding".to_string(),
+                Some(report) => format!(
+                    "{} · {} ms",
+                    report.version.map(|v| format!("v{v}")).unwrap_or_else(|| "Unknown version".to_string()),
+                    report.latency_ms
+                ),
+            }}
+        </A>
     }
 }
+
 #[component]
-fn NewDnodeComp(cx: Scope) -> impl IntoView {
-    let params = use_params_map(cx);
-    let id = move || params.with(|params| params.get("id").cloned().unwrap_or_default());
+fn BackendStatus(cx: Scope) -> impl IntoView {
+    let settings = use_ui_settings(cx);
+    let health = use_backend_health(cx);
+    let site = use_active_site(cx);
+    let (checking, set_checking) = create_signal(cx, false);
+
+    let check = move || {
+        set_checking.set(true);
+        spawn_local(async move {
+            health.set(Some(check_backend_health(active_backend(), true).await));
+            set_checking.set(false);
+        });
+    };
+    create_effect(cx, move |_| {
+        site.track();
+        check();
+    });
+
+    let summary = move || {
+        health.get().map(|report| {
+            let settings = settings.get();
+            let capabilities = match report.capabilities {
+                Some(caps) if caps.is_empty() => "None".to_string(),
+                Some(caps) => caps.join(", "),
+                None => "Not reported, all features enabled".to_string(),
+            };
+            view! { cx,
+                <div class="grid grid-cols-3 grid-flow-row gap-2 mb-6">
+                    <DashboardTile title="Backend".to_string() value=(if report.reachable { "Reachable" } else { "Unreachable" }).to_string() href="/status".to_string()/>
+                    <DashboardTile title="Latency".to_string() value=format!("{} ms", report.latency_ms) href="/status".to_string()/>
+                    <DashboardTile title="Version".to_string() value=report.version.unwrap_or_else(|| "Unknown".to_string()) href="/status".to_string()/>
+                </div>
+                <p class="mb-2">"Capabilities: "{capabilities}</p>
+                <p class="mb-4">"Checked: "{format_timestamp(&report.checked_at, &settings)}</p>
+            }
+        })
+    };
 
-    let dnode_descr = move || match id().as_str() {
-        "rapi" => view! { cx,
-            <NewRapiNode/>
-        },
-        "mbtcp" => view! { cx,
-            <NewMbtcpNode/>
-        },
-        "mqtt" => view! { cx,
-            <NewMqttNode/>
-        },
-        _ => view! { cx,
-            <NoNewNode/>
-        },
+    let endpoint_rows = move || {
+        health
+            .get()
+            .map(|report| report.endpoints)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|check| {
+                view! { cx,
+                    <tr class="border-b">
+                        <td class="p-2 font-mono">{check.path}</td>
+                        <td class="p-2" class:text-red-500=!check.ok>{check.result}</td>
+                        <td class="p-2">{format!("{} ms", check.latency_ms)}</td>
+                        <td class="p-2">{check.count.map(|c| c.to_string()).unwrap_or_default()}</td>
+                    </tr>
+                }
+            })
+            .collect::<Vec<_>>()
     };
 
-    dnode_descr
+    view! { cx,
+        <div class="status p-5 dark:text-gray-300">
+            <div class="flex items-center mb-4">
+                <h1 class="text-2xl font-bold">
+                    "Backend status: "{move || active_backend().url}
+                </h1>
+                <button
+                class="ml-auto bg-blue-600 text-gray-200 hover:bg-blue-900 dark:text-gray-300 font-bold py-2 px-4 rounded"
+                prop:disabled=move || checking.get()
+                on:click=move |_| check()
+                >
+                {move || if checking.get() { "Checking..." } else { "Check now" }}
+                </button>
+            </div>
+            {summary}
+            <table class="w-full text-left text-sm">
+                <thead>
+                    <tr class="border-b font-bold">
+                        <th class="p-2">"Endpoint"</th>
+                        <th class="p-2">"Result"</th>
+                        <th class="p-2">"Latency"</th>
+                        <th class="p-2">"Documents"</th>
+                    </tr>
+                </thead>
+                <tbody>{endpoint_rows}</tbody>
+            </table>
+        </div>
+    }
 }
 
 fn main() {
+    // Only a configured mock site needs fetch to be wrapped
+    if cfg!(feature = "mock")
+        || load_ui_settings()
+            .backends
+            .iter()
+            .any(|b| b.url == mock::MOCK_BACKEND_URL)
+    {
+        mock::install();
+    }
     leptos::mount_to_body(|cx| view! { cx, <App/> })
 }
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    // The two nodes the wasm tests use as well
+    pub(crate) fn test_nodes() -> Vec<RapiStruct> {
+        serde_json::from_str(
+            r#"[
+                {"node_val": "21.4", "node_last_update": {"$date": {"$numberLong": "0"}},
+                 "node_name": "hall_temperature", "node_rw_direction": "r", "node_uid": "uid-1",
+                 "node_unit": "degC"},
+                {"node_val": "1", "node_last_update": {"$date": {"$numberLong": "0"}},
+                 "node_name": "pump_state", "node_rw_direction": "rw", "node_uid": "uid-2"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn increment_suffix_keeps_padding_and_refuses_overflow() {
+        assert_eq!(increment_suffix("reg007", 2).as_deref(), Some("reg009"));
+        assert_eq!(increment_suffix("pump", 3).as_deref(), Some("pump3"));
+        assert_eq!(increment_suffix("pump", 0).as_deref(), Some("pump"));
+        assert_eq!(increment_suffix(&format!("r{}", u64::MAX), 1), None);
+        assert_eq!(increment_suffix("r99999999999999999999999", 0), None);
+        assert!(repeated_suffixes("r1", 3, u64::MAX).is_err());
+        assert_eq!(repeated_suffixes("t08", 3, 2).unwrap(), ["t08", "t10", "t12"]);
+    }
+
+    #[test]
+    fn backend_audit_log_decodes() {
+        let fixture = include_str!("../fixtures/audit.json");
+        let events: Vec<AuditEvent> = serde_json::from_str(fixture).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].action, AuditAction::Update);
+        assert_eq!(events[0].target, AuditTarget::RapiNode);
+        assert_eq!(events[0].at.timestamp_millis(), 1697711400000);
+        assert!(events[1].before.is_none());
+        assert!(!events[1].ok);
+        assert!(events.iter().all(|event| !event.from_backend));
+
+        let changes = audit_changes(&events[0]);
+        assert_eq!(changes, vec![("node_val".to_string(), "0".to_string(), "1".to_string())]);
+    }
+
+    #[test]
+    fn create_summary_tells_where_it_stopped() {
+        assert_eq!(create_summary(3, 3, None), "Created 3");
+        assert_eq!(
+            create_summary(1, 3, Some("500")),
+            "Created 1 of 3, then stopped: Backend offline, nothing was written"
+        );
+        assert_eq!(
+            create_summary(0, 2, Some("400")),
+            "Created 0 of 2, then stopped: Refused by the backend (400)"
+        );
+    }
+
+    #[test]
+    fn repeat_inputs_are_validated() {
+        assert_eq!(parse_repeat("", " "), Ok((1, 1)));
+        assert_eq!(parse_repeat("4", "10"), Ok((4, 10)));
+        assert!(parse_repeat("3", "0").is_err());
+        assert!(parse_repeat("0", "1").is_err());
+        assert!(parse_repeat(&(MAX_REPEAT + 1).to_string(), "1").is_err());
+        assert!(parse_repeat("two", "1").is_err());
+    }
+
+}
@@ -0,0 +1,448 @@
+// In-browser stand-in for the concentrator, used for demos and UI work without a server.
+// Requests to MOCK_BACKEND_URL are answered here instead of going to the network.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use bson::DateTime;
+use leptos::window;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+use crate::{local_storage_get, local_storage_set, Backend, ModbusStruct, MqttStruct, RapiStruct};
+
+// Host that never resolves, so nothing leaks to the network if the mock is not installed
+pub const MOCK_BACKEND_URL: &str = "http://mock.invalid";
+
+const MOCK_STATE_KEY: &str = "dcui_mock_backend";
+
+// Nodes not written for this long are left alone by the value simulation
+const SIMULATE_WITHIN_SECS: i64 = 3600;
+
+pub fn demo_backend() -> Backend {
+    Backend {
+        id: "demo".to_string(),
+        name: "Demo (mock)".to_string(),
+        url: MOCK_BACKEND_URL.to_string(),
+    }
+}
+
+// Everything the mock backend stores, kept in localStorage between reloads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MockState {
+    rapi: Vec<RapiStruct>,
+    modbus: Vec<ModbusStruct>,
+    mqtt: Vec<MqttStruct>,
+    next_uid: u64,
+}
+
+fn seeded_node(
+    uid: &str,
+    name: &str,
+    rw: &str,
+    val: &str,
+    age_secs: i64,
+    path: &str,
+    tags: &str,
+) -> RapiStruct {
+    RapiStruct {
+        node_val: val.to_string(),
+        node_last_update: DateTime::from_millis(
+            DateTime::now().timestamp_millis() - age_secs * 1000,
+        ),
+        node_name: name.to_string(),
+        node_rw_direction: rw.to_string(),
+        node_uid: uid.to_string(),
+        node_path: path.to_string(),
+        node_tags: tags.to_string(),
+        node_description: String::new(),
+        node_unit: String::new(),
+        node_device: String::new(),
+        node_owner: String::new(),
+        node_notes: String::new(),
+        node_alarm_hh: String::new(),
+        node_alarm_h: String::new(),
+        node_alarm_l: String::new(),
+        node_alarm_ll: String::new(),
+        node_alarm_state: String::new(),
+    }
+}
+
+fn described(mut node: RapiStruct, description: &str, unit: &str, device: &str) -> RapiStruct {
+    node.node_description = description.to_string();
+    node.node_unit = unit.to_string();
+    node.node_device = device.to_string();
+    node.node_owner = "Maintenance".to_string();
+    node
+}
+
+fn limited(mut node: RapiStruct, hh: &str, h: &str, l: &str, ll: &str) -> RapiStruct {
+    node.node_alarm_hh = hh.to_string();
+    node.node_alarm_h = h.to_string();
+    node.node_alarm_l = l.to_string();
+    node.node_alarm_ll = ll.to_string();
+    node
+}
+
+fn seeded_modbus(uid: &str, ip: &str, register: &str, rw: &str) -> ModbusStruct {
+    ModbusStruct {
+        mb_lock_to_uid: uid.to_string(),
+        mb_ip: ip.to_string(),
+        mb_port: "502".to_string(),
+        mb_register: register.to_string(),
+        mb_rw: rw.to_string(),
+    }
+}
+
+fn seeded_mqtt(uid: &str, topic: &str, modif: &str, rw: &str) -> MqttStruct {
+    MqttStruct {
+        mqtt_lock_to_uid: uid.to_string(),
+        mqtt_ip: "10.0.10.5".to_string(),
+        mqtt_topic: topic.to_string(),
+        mqtt_topic_modif: modif.to_string(),
+        mqtt_rw: rw.to_string(),
+    }
+}
+
+// Sample site: a few sensors and actuators, one stale node, one unmapped
+// node and one mapping locked to a uid that does not exist. The valve
+// setpoint sits above its high limit so the alarm list is never empty.
+fn seed() -> MockState {
+    MockState {
+        rapi: vec![
+            limited(
+                described(
+                    seeded_node(
+                        "mock-000001",
+                        "hall_temperature_01",
+                        "r",
+                        "21.4",
+                        5,
+                        "demo/hall/sensor_01/temperature",
+                        "temperature,sensor",
+                    ),
+                    "Air temperature in the assembly hall",
+                    "degC",
+                    "TT-101 hall sensor",
+                ),
+                "30",
+                "25",
+                "15",
+                "10",
+            ),
+            described(
+                seeded_node(
+                    "mock-000002",
+                    "line_a_pressure",
+                    "r",
+                    "4.20",
+                    5,
+                    "demo/line_a/pt_01/pressure",
+                    "pressure,sensor",
+                ),
+                "Pressure after the line A feed pump",
+                "bar",
+                "PT-201",
+            ),
+            described(
+                seeded_node(
+                    "mock-000003",
+                    "flow_meter_03",
+                    "r",
+                    "118.0",
+                    5,
+                    "demo/line_a/fm_03/flow",
+                    "flow,sensor",
+                ),
+                "Water flow into line A",
+                "l/min",
+                "FM-203",
+            ),
+            RapiStruct {
+                // Alarms when the pump stops
+                node_alarm_state: "0".to_string(),
+                ..described(
+                    seeded_node(
+                        "mock-000004",
+                        "pump_01_state",
+                        "rw",
+                        "1",
+                        60,
+                        "demo/line_a/pump_01/state",
+                        "actuator",
+                    ),
+                    "Pump 1 running (1) or stopped (0)",
+                    "",
+                    "Pump 1 drive",
+                )
+            },
+            limited(
+                described(
+                    seeded_node(
+                        "mock-000005",
+                        "valve_02_setpoint",
+                        "rw",
+                        "35",
+                        60,
+                        "demo/line_b/valve_02/setpoint",
+                        "actuator,setpoint",
+                    ),
+                    "Opening setpoint of valve 2",
+                    "%",
+                    "Valve 2 positioner",
+                ),
+                "",
+                "30",
+                "",
+                "",
+            ),
+            described(
+                seeded_node(
+                    "mock-000006",
+                    "tank_level_old",
+                    "r",
+                    "2.75",
+                    7 * 86400,
+                    "demo/tanks/tank_01/level",
+                    "level,sensor",
+                ),
+                "Level of tank 1, sensor removed",
+                "m",
+                "LT-301",
+            ),
+            seeded_node("mock-000007", "spare_input_01", "r", "0", 5, "", ""),
+        ],
+        modbus: vec![
+            seeded_modbus("mock-000001", "10.0.20.11", "30001", "r"),
+            seeded_modbus("mock-000002", "10.0.20.11", "30002", "r"),
+            seeded_modbus("mock-000004", "10.0.20.12", "00001", "rw"),
+            seeded_modbus("mock-000005", "10.0.20.12", "40001", "rw"),
+            seeded_modbus("mock-000006", "10.0.20.13", "30010", "r"),
+            seeded_modbus("mock-000099", "10.0.20.13", "30011", "r"),
+        ],
+        mqtt: vec![
+            seeded_mqtt("mock-000001", "site/hall/", "temperature", "r"),
+            seeded_mqtt("mock-000003", "site/line_a/", "flow", "r"),
+            seeded_mqtt("mock-000005", "site/valves/", "valve_02", "rw"),
+        ],
+        next_uid: 8,
+    }
+}
+
+thread_local! {
+    static INSTALLED: Cell<bool> = const { Cell::new(false) };
+    // The state with the simulated values, only written to localStorage
+    // when a request changes it
+    static STATE: RefCell<Option<MockState>> = const { RefCell::new(None) };
+}
+
+fn load_state() -> MockState {
+    STATE.with(|state| state.borrow().clone()).unwrap_or_else(|| {
+        local_storage_get(MOCK_STATE_KEY)
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_else(seed)
+    })
+}
+
+fn keep_state(state: MockState, persist: bool) {
+    if persist {
+        if let Ok(raw) = serde_json::to_string(&state) {
+            local_storage_set(MOCK_STATE_KEY, &raw);
+        }
+    }
+    STATE.with(|current| *current.borrow_mut() = Some(state));
+}
+
+// Every request the mock answered as (method, path, body), for the tests
+#[cfg(all(test, target_arch = "wasm32"))]
+thread_local! {
+    static RECEIVED: RefCell<Vec<(String, String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+pub fn received() -> Vec<(String, String, String)> {
+    RECEIVED.with(|r| r.borrow().clone())
+}
+
+// Throws away everything written to the mock backend
+pub fn reset() {
+    keep_state(seed(), true);
+    #[cfg(all(test, target_arch = "wasm32"))]
+    RECEIVED.with(|r| r.borrow_mut().clear());
+}
+
+// Random walk on the numeric values of recently updated read-only nodes
+fn simulate(state: &mut MockState) {
+    let now = DateTime::now();
+    for node in state.rapi.iter_mut() {
+        let age_ms = now.timestamp_millis() - node.node_last_update.timestamp_millis();
+        if node.node_rw_direction.contains('w') || age_ms > SIMULATE_WITHIN_SECS * 1000 {
+            continue;
+        }
+        let Ok(value) = node.node_val.parse::<f64>() else {
+            continue;
+        };
+        let decimals = node
+            .node_val
+            .split_once('.')
+            .map(|(_, frac)| frac.len())
+            .unwrap_or(0);
+        let step = (value.abs() * 0.01).max(0.1);
+        let next = value + (js_sys::Math::random() - 0.5) * 2.0 * step;
+        node.node_val = format!("{next:.decimals$}");
+        node.node_last_update = now;
+    }
+}
+
+fn json<T: Serialize>(value: &T) -> (u16, String) {
+    match serde_json::to_string(value) {
+        Ok(body) => (200, body),
+        Err(err) => (500, err.to_string()),
+    }
+}
+
+fn field(body: &HashMap<String, String>, name: &str) -> String {
+    body.get(name).cloned().unwrap_or_default()
+}
+
+// Answers one request the way the concentrator does, returns status and body
+fn handle(method: &str, path: &str, body: &str) -> (u16, String) {
+    #[cfg(all(test, target_arch = "wasm32"))]
+    RECEIVED.with(|r| {
+        r.borrow_mut()
+            .push((method.to_string(), path.to_string(), body.to_string()))
+    });
+
+    let mut state = load_state();
+    let doc: HashMap<String, String> = serde_json::from_str(body).unwrap_or_default();
+
+    let answer = match (method, path) {
+        ("GET", "/getall/bucket") => {
+            simulate(&mut state);
+            json(&state.rapi)
+        }
+        ("GET", "/getall/mbstuff") => json(&state.modbus),
+        ("GET", "/getall/mqttstuff") => json(&state.mqtt),
+        ("GET", "/c/") => {
+            let uid = format!("mock-{:06}", state.next_uid);
+            state.next_uid += 1;
+            json(&serde_json::json!({ "uid": uid }))
+        }
+        // Everyone may do everything in the demo
+        ("GET", "/whoami") => json(&serde_json::json!({ "role": "admin" })),
+        ("GET", "/version") => json(&serde_json::json!({
+            "version": "mock",
+            "capabilities": ["write"],
+        })),
+        // Creates the node or overwrites the one with the same uid
+        ("POST", "/u") => {
+            let node = RapiStruct {
+                node_val: field(&doc, "node_val"),
+                node_last_update: DateTime::now(),
+                node_name: field(&doc, "node_name"),
+                node_rw_direction: field(&doc, "node_rw_direction"),
+                node_uid: field(&doc, "node_uid"),
+                node_path: field(&doc, "node_path"),
+                node_tags: field(&doc, "node_tags"),
+                node_description: field(&doc, "node_description"),
+                node_unit: field(&doc, "node_unit"),
+                node_device: field(&doc, "node_device"),
+                node_owner: field(&doc, "node_owner"),
+                node_notes: field(&doc, "node_notes"),
+                node_alarm_hh: field(&doc, "node_alarm_hh"),
+                node_alarm_h: field(&doc, "node_alarm_h"),
+                node_alarm_l: field(&doc, "node_alarm_l"),
+                node_alarm_ll: field(&doc, "node_alarm_ll"),
+                node_alarm_state: field(&doc, "node_alarm_state"),
+            };
+            match state.rapi.iter_mut().find(|n| n.node_uid == node.node_uid) {
+                Some(existing) => *existing = node,
+                None => state.rapi.push(node),
+            }
+            (200, String::new())
+        }
+        ("POST", "/cmbtcp") => {
+            state.modbus.push(ModbusStruct {
+                mb_lock_to_uid: field(&doc, "mb_lock_to_uid"),
+                mb_ip: field(&doc, "mb_ip"),
+                mb_port: field(&doc, "mb_port"),
+                mb_register: field(&doc, "mb_register"),
+                mb_rw: field(&doc, "mb_rw"),
+            });
+            (200, String::new())
+        }
+        ("POST", "/cmqtt") => {
+            state.mqtt.push(MqttStruct {
+                mqtt_lock_to_uid: field(&doc, "mqtt_lock_to_uid"),
+                mqtt_ip: field(&doc, "mqtt_ip"),
+                mqtt_topic: field(&doc, "mqtt_topic"),
+                mqtt_topic_modif: field(&doc, "mqtt_topic_modif"),
+                mqtt_rw: field(&doc, "mqtt_rw"),
+            });
+            (200, String::new())
+        }
+        _ => (404, String::new()),
+    };
+
+    // Reads only move the simulated values, those are not worth a write
+    keep_state(state, method == "POST" || path == "/c/");
+    answer
+}
+
+fn respond(url: &str, status: u16, body: &str) -> Result<JsValue, JsValue> {
+    let headers = web_sys::Headers::new()?;
+    headers.set("content-type", "application/json")?;
+    let init = web_sys::ResponseInit::new();
+    init.set_status(status);
+    init.set_headers(&headers);
+    let body = (!body.is_empty()).then_some(body);
+    let response = web_sys::Response::new_with_opt_str_and_init(body, &init)?;
+
+    // reqwest reads the url of the response, a constructed one has none
+    let descriptor = js_sys::Object::new();
+    js_sys::Reflect::set(&descriptor, &"value".into(), &url.into())?;
+    js_sys::Object::define_property(&response, &"url".into(), &descriptor);
+
+    Ok(response.into())
+}
+
+// Wraps window.fetch so requests to MOCK_BACKEND_URL never leave the browser,
+// installing it twice does nothing
+pub fn install() {
+    if INSTALLED.with(|installed| installed.replace(true)) {
+        return;
+    }
+    let Ok(original) = js_sys::Reflect::get(&window(), &"fetch".into()) else {
+        return;
+    };
+    let original: js_sys::Function = original.unchecked_into();
+
+    let fetch = Closure::<dyn Fn(JsValue, JsValue) -> js_sys::Promise>::new(
+        move |input: JsValue, init: JsValue| match input.dyn_ref::<web_sys::Request>() {
+            Some(request) if request.url().starts_with(MOCK_BACKEND_URL) => {
+                let request = Clone::clone(request);
+                wasm_bindgen_futures::future_to_promise(async move {
+                    let body = match request.text() {
+                        Ok(text) => wasm_bindgen_futures::JsFuture::from(text)
+                            .await?
+                            .as_string()
+                            .unwrap_or_default(),
+                        Err(_) => String::new(),
+                    };
+                    let url = request.url();
+                    let path = url.trim_start_matches(MOCK_BACKEND_URL);
+                    let (status, answer) = handle(&request.method(), path, &body);
+                    respond(&url, status, &answer)
+                })
+            }
+            _ => original
+                .call2(&window(), &input, &init)
+                .map(|promise| promise.unchecked_into())
+                .unwrap_or_else(|err| js_sys::Promise::reject(&err)),
+        },
+    );
+
+    let _ = js_sys::Reflect::set(&window(), &"fetch".into(), fetch.as_ref());
+    // Lives as long as the page
+    fetch.forget();
+}
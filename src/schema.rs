@@ -0,0 +1,489 @@
+// Wire format of the backend documents. Sample answers and request bodies of every
+// endpoint live in /fixtures, the tests below keep the structs in line with them.
+
+use std::collections::BTreeMap;
+
+use bson::DateTime;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{ModbusStruct, MqttStruct, RapiStruct};
+
+// A document kind returned by one of the /getall/ endpoints
+pub trait Document: Serialize + DeserializeOwned {
+    // Used in the warnings, e.g. "rAPI node"
+    const KIND: &'static str;
+    // The fields of the backend contract
+    const FIELDS: &'static [&'static str];
+    // Fields the UI stores on top of the contract. They are always written together,
+    // a document counts as extended only with all of them, otherwise they are unknown.
+    const EXTENSION: &'static [&'static str] = &[];
+
+    // Supplies the value of fields the backend left out
+    fn placeholder() -> Self;
+}
+
+impl Document for RapiStruct {
+    const KIND: &'static str = "rAPI node";
+    const FIELDS: &'static [&'static str] = &[
+        "node_val",
+        "node_last_update",
+        "node_name",
+        "node_rw_direction",
+        "node_uid",
+    ];
+    const EXTENSION: &'static [&'static str] = &[
+        "node_path",
+        "node_tags",
+        "node_description",
+        "node_unit",
+        "node_device",
+        "node_owner",
+        "node_notes",
+        "node_alarm_hh",
+        "node_alarm_h",
+        "node_alarm_l",
+        "node_alarm_ll",
+        "node_alarm_state",
+    ];
+
+    fn placeholder() -> Self {
+        RapiStruct {
+            node_val: String::new(),
+            node_last_update: DateTime::from_millis(0),
+            node_name: String::new(),
+            node_rw_direction: String::new(),
+            node_uid: String::new(),
+            node_path: String::new(),
+            node_tags: String::new(),
+            node_description: String::new(),
+            node_unit: String::new(),
+            node_device: String::new(),
+            node_owner: String::new(),
+            node_notes: String::new(),
+            node_alarm_hh: String::new(),
+            node_alarm_h: String::new(),
+            node_alarm_l: String::new(),
+            node_alarm_ll: String::new(),
+            node_alarm_state: String::new(),
+        }
+    }
+}
+
+impl Document for ModbusStruct {
+    const KIND: &'static str = "Modbus mapping";
+    const FIELDS: &'static [&'static str] =
+        &["mb_lock_to_uid", "mb_ip", "mb_port", "mb_register", "mb_rw"];
+
+    fn placeholder() -> Self {
+        ModbusStruct {
+            mb_lock_to_uid: String::new(),
+            mb_ip: String::new(),
+            mb_port: String::new(),
+            mb_register: String::new(),
+            mb_rw: String::new(),
+        }
+    }
+}
+
+impl Document for MqttStruct {
+    const KIND: &'static str = "MQTT mapping";
+    const FIELDS: &'static [&'static str] = &[
+        "mqtt_lock_to_uid",
+        "mqtt_ip",
+        "mqtt_topic",
+        "mqtt_topic_modif",
+        "mqtt_rw",
+    ];
+
+    fn placeholder() -> Self {
+        MqttStruct {
+            mqtt_lock_to_uid: String::new(),
+            mqtt_ip: String::new(),
+            mqtt_topic: String::new(),
+            mqtt_topic_modif: String::new(),
+            mqtt_rw: String::new(),
+        }
+    }
+}
+
+// A document that could not be decoded, kept so it can still be shown
+#[derive(Debug, Clone, PartialEq)]
+pub struct Malformed {
+    // Position in the answer, starting at 1
+    pub index: usize,
+    pub raw: String,
+    pub error: String,
+}
+
+// A decoded list and everything in it that did not match the schema
+#[derive(Debug, Clone)]
+pub struct Decoded<T> {
+    pub items: Vec<T>,
+    // The JSON each item was decoded from, including ignored fields
+    pub sources: Vec<Value>,
+    pub warnings: Vec<String>,
+    pub malformed: Vec<Malformed>,
+}
+
+// True for the contract fields and, in an extended document, the extension
+fn is_known<T: Document>(fields: &serde_json::Map<String, Value>, name: &str) -> bool {
+    T::FIELDS.contains(&name)
+        || (T::EXTENSION.contains(&name)
+            && T::EXTENSION.iter().all(|field| fields.contains_key(*field)))
+}
+
+// Decodes one document: unknown fields are ignored and missing ones filled
+// from the placeholder, both reported as warnings. Fails on wrong field types.
+fn decode_document<T: Document>(
+    value: Value,
+    placeholder: &Value,
+) -> Result<(T, Vec<String>), String> {
+    let Value::Object(fields) = value else {
+        return Err("not a JSON object".to_string());
+    };
+
+    let mut warnings = Vec::new();
+    let mut known = serde_json::Map::new();
+    for (name, value) in &fields {
+        if is_known::<T>(&fields, name) {
+            known.insert(name.clone(), value.clone());
+        } else {
+            warnings.push(format!("unknown field `{name}`"));
+        }
+    }
+    let mut fields = known;
+    for name in T::FIELDS {
+        if !fields.contains_key(*name) {
+            warnings.push(format!("missing field `{name}`"));
+            fields.insert(name.to_string(), placeholder[*name].clone());
+        }
+    }
+    for name in T::EXTENSION {
+        fields
+            .entry(name.to_string())
+            .or_insert_with(|| placeholder[*name].clone());
+    }
+
+    serde_json::from_value(Value::Object(fields))
+        .map(|item| (item, warnings))
+        .map_err(|err| err.to_string())
+}
+
+// Decodes a /getall/ answer document by document without ever panicking.
+// Warnings are grouped, "unknown field `x` (12 rAPI node documents)" instead of
+// one per document. Documents that fail to decode end up in `malformed`.
+pub fn decode_list<T: Document>(raw: &str) -> Decoded<T> {
+    let values = match serde_json::from_str::<Value>(raw) {
+        Ok(Value::Array(values)) => values,
+        Ok(_) => {
+            return Decoded {
+                items: Vec::new(),
+                sources: Vec::new(),
+                warnings: vec![format!("Expected a list of {} documents", T::KIND)],
+                malformed: Vec::new(),
+            }
+        }
+        Err(err) => {
+            return Decoded {
+                items: Vec::new(),
+                sources: Vec::new(),
+                warnings: vec![format!("The answer is not valid JSON: {err}")],
+                malformed: Vec::new(),
+            }
+        }
+    };
+
+    let placeholder = serde_json::to_value(T::placeholder()).unwrap_or(Value::Null);
+    let mut items = Vec::new();
+    let mut sources = Vec::new();
+    let mut grouped: BTreeMap<String, usize> = BTreeMap::new();
+    let mut malformed = Vec::new();
+
+    for (idx, value) in values.into_iter().enumerate() {
+        let raw = serde_json::to_string_pretty(&value).unwrap_or_default();
+        match decode_document::<T>(value.clone(), &placeholder) {
+            Ok((item, warnings)) => {
+                items.push(item);
+                sources.push(value);
+                for warning in warnings {
+                    *grouped.entry(warning).or_default() += 1;
+                }
+            }
+            Err(error) => malformed.push(Malformed {
+                index: idx + 1,
+                raw,
+                error,
+            }),
+        }
+    }
+
+    let warnings = grouped
+        .into_iter()
+        .map(|(warning, n)| {
+            let plural = if n == 1 { "" } else { "s" };
+            format!("{warning} ({n} {} document{plural})", T::KIND)
+        })
+        .collect();
+
+    Decoded {
+        items,
+        sources,
+        warnings,
+        malformed,
+    }
+}
+
+// How a hand-edited document differs from the fields of T
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checked {
+    pub unknown: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+// Validates a hand-edited document. Unknown and missing fields are reported,
+// invalid JSON and wrong field types are errors.
+pub fn check_document<T: Document>(raw: &str) -> Result<Checked, String> {
+    let value: Value = serde_json::from_str(raw).map_err(|err| err.to_string())?;
+    let Value::Object(fields) = &value else {
+        return Err(format!("A {} must be a JSON object", T::KIND));
+    };
+
+    let checked = Checked {
+        unknown: fields
+            .keys()
+            .filter(|name| !is_known::<T>(fields, name))
+            .cloned()
+            .collect(),
+        missing: T::FIELDS
+            .iter()
+            .filter(|name| !fields.contains_key(**name))
+            .map(|name| name.to_string())
+            .collect(),
+    };
+
+    let placeholder = serde_json::to_value(T::placeholder()).unwrap_or(Value::Null);
+    decode_document::<T>(value, &placeholder)?;
+    Ok(checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use super::*;
+    use crate::{modbus_to_map, mqtt_to_map, rapi_to_map, NewUidGet};
+
+    const GETALL_BUCKET: &str = include_str!("../fixtures/getall_bucket.json");
+    const GETALL_MBSTUFF: &str = include_str!("../fixtures/getall_mbstuff.json");
+    const GETALL_MQTTSTUFF: &str = include_str!("../fixtures/getall_mqttstuff.json");
+    const GETALL_BUCKET_DRIFTED: &str = include_str!("../fixtures/getall_bucket_drifted.json");
+    const GETALL_BUCKET_EXTENDED: &str = include_str!("../fixtures/getall_bucket_extended.json");
+    const C: &str = include_str!("../fixtures/c.json");
+    const U: &str = include_str!("../fixtures/u.json");
+    const U_EXTENDED: &str = include_str!("../fixtures/u_extended.json");
+    const CMBTCP: &str = include_str!("../fixtures/cmbtcp.json");
+    const CMQTT: &str = include_str!("../fixtures/cmqtt.json");
+
+    // Decodes the fixture and checks that encoding gives back the same JSON
+    fn round_trip<T: Document>(fixture: &str) -> Vec<T> {
+        let decoded = decode_list::<T>(fixture);
+        assert!(decoded.warnings.is_empty(), "{:?}", decoded.warnings);
+
+        let expected: Value = serde_json::from_str(fixture).unwrap();
+        assert_eq!(serde_json::to_value(&decoded.items).unwrap(), expected);
+        decoded.items
+    }
+
+    // The extension fields are encoded even when the backend did not send them
+    fn contract_fields<T: Serialize>(items: &[T]) -> Value {
+        let mut value = serde_json::to_value(items).unwrap();
+        for item in value.as_array_mut().unwrap() {
+            let item = item.as_object_mut().unwrap();
+            item.retain(|name, _| !RapiStruct::EXTENSION.contains(&name.as_str()));
+        }
+        value
+    }
+
+    fn body_keys(fixture: &str) -> HashSet<String> {
+        serde_json::from_str::<HashMap<String, String>>(fixture)
+            .unwrap()
+            .into_keys()
+            .collect()
+    }
+
+    #[test]
+    fn getall_bucket_round_trips() {
+        let decoded = decode_list::<RapiStruct>(GETALL_BUCKET);
+        assert!(decoded.warnings.is_empty(), "{:?}", decoded.warnings);
+        assert_eq!(
+            contract_fields(&decoded.items),
+            serde_json::from_str::<Value>(GETALL_BUCKET).unwrap()
+        );
+        assert_eq!(
+            decoded.sources,
+            serde_json::from_str::<Vec<Value>>(GETALL_BUCKET).unwrap()
+        );
+        let nodes = decoded.items;
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1].node_rw_direction, "rw");
+        assert_eq!(nodes[0].node_last_update.timestamp_millis(), 1697711400000);
+        assert_eq!(nodes[0].node_path, "");
+    }
+
+    #[test]
+    fn getall_bucket_extended_round_trips() {
+        let nodes = round_trip::<RapiStruct>(GETALL_BUCKET_EXTENDED);
+        assert_eq!(nodes[1].node_path, "plant/line_a/pump_01/state");
+        assert_eq!(nodes[0].node_alarm_h, "25");
+        assert_eq!(nodes[1].node_alarm_state, "0");
+    }
+
+    #[test]
+    fn partly_extended_documents_report_the_extension_as_unknown() {
+        let partial = r#"[{"node_val": "1", "node_last_update": {"$date": {"$numberLong": "0"}},
+            "node_name": "a", "node_rw_direction": "r", "node_uid": "u", "node_path": "a/b"}]"#;
+        let decoded = decode_list::<RapiStruct>(partial);
+        assert_eq!(
+            decoded.warnings,
+            vec!["unknown field `node_path` (1 rAPI node document)"]
+        );
+        assert_eq!(decoded.items[0].node_path, "");
+    }
+
+    #[test]
+    fn getall_mbstuff_round_trips() {
+        let mappings = round_trip::<ModbusStruct>(GETALL_MBSTUFF);
+        assert_eq!(mappings[0].mb_lock_to_uid, "6530f1a8c2e4b1d2a9f0c001");
+        assert_eq!(mappings[1].mb_register, "00001");
+    }
+
+    #[test]
+    fn getall_mqttstuff_round_trips() {
+        let mappings = round_trip::<MqttStruct>(GETALL_MQTTSTUFF);
+        assert_eq!(mappings[0].mqtt_topic_modif, "temperature");
+    }
+
+    #[test]
+    fn new_uid_round_trips() {
+        let uid: NewUidGet = serde_json::from_str(C).unwrap();
+        assert_eq!(uid.uid, "6530f1a8c2e4b1d2a9f0c003");
+        assert_eq!(
+            serde_json::to_value(&uid).unwrap(),
+            serde_json::from_str::<Value>(C).unwrap()
+        );
+    }
+
+    #[test]
+    fn create_node_body_matches_rapi_fields() {
+        let fields: HashSet<String> = RapiStruct::FIELDS
+            .iter()
+            .filter(|name| **name != "node_last_update")
+            .map(|name| name.to_string())
+            .collect();
+        assert_eq!(body_keys(U), fields);
+    }
+
+    #[test]
+    fn create_node_body_matches_rapi_to_map() {
+        let node = RapiStruct::placeholder();
+        let sent: HashSet<String> = rapi_to_map(&node).into_keys().map(String::from).collect();
+        assert_eq!(sent, body_keys(U_EXTENDED));
+
+        let extension: HashSet<String> =
+            RapiStruct::EXTENSION.iter().map(|name| name.to_string()).collect();
+        assert_eq!(&sent - &extension, body_keys(U));
+    }
+
+    #[test]
+    fn create_modbus_body_matches_modbus_to_map() {
+        let mb: ModbusStruct = serde_json::from_str(CMBTCP).unwrap();
+        let sent: HashSet<String> = modbus_to_map(&mb).into_keys().map(String::from).collect();
+        assert_eq!(sent, body_keys(CMBTCP));
+    }
+
+    #[test]
+    fn create_mqtt_body_matches_mqtt_to_map() {
+        let mq: MqttStruct = serde_json::from_str(CMQTT).unwrap();
+        let sent: HashSet<String> = mqtt_to_map(&mq).into_keys().map(String::from).collect();
+        assert_eq!(sent, body_keys(CMQTT));
+    }
+
+    #[test]
+    fn fields_match_the_structs() {
+        let fields = |value: Value| -> HashSet<String> {
+            value.as_object().unwrap().keys().cloned().collect()
+        };
+        let names =
+            |list: &[&str]| -> HashSet<String> { list.iter().map(|n| n.to_string()).collect() };
+
+        assert_eq!(
+            fields(serde_json::to_value(RapiStruct::placeholder()).unwrap()),
+            &names(RapiStruct::FIELDS) | &names(RapiStruct::EXTENSION)
+        );
+        assert_eq!(
+            fields(serde_json::to_value(ModbusStruct::placeholder()).unwrap()),
+            names(ModbusStruct::FIELDS)
+        );
+        assert_eq!(
+            fields(serde_json::to_value(MqttStruct::placeholder()).unwrap()),
+            names(MqttStruct::FIELDS)
+        );
+    }
+
+    #[test]
+    fn drifted_documents_decode_with_warnings() {
+        let decoded = decode_list::<RapiStruct>(GETALL_BUCKET_DRIFTED);
+
+        // The unknown field is ignored, the missing timestamp gets the placeholder
+        assert_eq!(decoded.items.len(), 2);
+        assert_eq!(decoded.items[1].node_name, "pump_01_state");
+        assert_eq!(decoded.items[1].node_last_update.timestamp_millis(), 0);
+        assert_eq!(
+            decoded.warnings,
+            vec![
+                "missing field `node_last_update` (1 rAPI node document)".to_string(),
+                "unknown field `node_unit` (1 rAPI node document)".to_string(),
+            ]
+        );
+
+        // The third document has a number where a string belongs
+        assert_eq!(decoded.malformed.len(), 1);
+        assert_eq!(decoded.malformed[0].index, 3);
+        assert_eq!(
+            decoded.malformed[0].error,
+            "invalid type: floating point `4.2`, expected a string"
+        );
+        assert!(decoded.malformed[0].raw.contains("4.2"));
+    }
+
+    #[test]
+    fn edited_documents_are_checked_against_the_struct() {
+        let checked = check_document::<ModbusStruct>(
+            r#"{"mb_lock_to_uid": "a", "mb_ip": "10.0.0.1", "mb_port": "502", "mb_rw": "r", "note": "x"}"#,
+        )
+        .unwrap();
+        assert_eq!(checked.unknown, vec!["note"]);
+        assert_eq!(checked.missing, vec!["mb_register"]);
+
+        let err = check_document::<ModbusStruct>(r#"{"mb_port": 502}"#).unwrap_err();
+        assert!(err.contains("expected a string"), "{err}");
+        assert!(check_document::<MqttStruct>("[]").is_err());
+        assert!(check_document::<RapiStruct>(r#"{"node_val": "1""#).is_err());
+    }
+
+    #[test]
+    fn non_list_answers_warn_instead_of_panicking() {
+        let decoded = decode_list::<ModbusStruct>(C);
+        assert!(decoded.items.is_empty());
+        assert_eq!(
+            decoded.warnings,
+            vec!["Expected a list of Modbus mapping documents"]
+        );
+
+        let decoded = decode_list::<MqttStruct>("<html>Bad gateway</html>");
+        assert!(decoded.items.is_empty());
+        assert!(decoded.warnings[0].starts_with("The answer is not valid JSON"));
+    }
+}
@@ -0,0 +1,889 @@
+// Component tests, run in a headless browser with `wasm-pack test --headless --firefox`.
+// Requests go to the in-browser mock backend, so no concentrator is needed.
+
+use std::cell::Cell;
+use std::collections::HashSet;
+
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::*;
+
+use super::*;
+use crate::alarms::{alarm_alert, announce, AlarmLevel, AlarmRecord, AlertSeverity};
+use crate::dashboards::{write_node_value, UserDashboard};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+// Fresh browser state talking to the mock backend, logged in as admin.
+// Returns the element the test mounts into.
+fn setup() -> web_sys::HtmlElement {
+    mock::install();
+    window().local_storage().unwrap().unwrap().clear().unwrap();
+    mock::reset();
+
+    let settings = UiSettings {
+        backends: vec![mock::demo_backend()],
+        ..UiSettings::default()
+    };
+    local_storage_set(UI_SETTINGS_KEY, &serde_json::to_string(&settings).unwrap());
+    ACTIVE_BACKEND.with(|b| *b.borrow_mut() = Some(mock::demo_backend()));
+    store_session(Some(&Session {
+        username: "tester".to_string(),
+        scheme: AuthScheme::Basic,
+        credential: String::new(),
+        role: Role::Admin,
+    }));
+
+    let container: web_sys::HtmlElement = document().create_element("div").unwrap().unchecked_into();
+    document().body().unwrap().append_child(&container).unwrap();
+    container
+}
+
+// The contexts App provides, for mounting single components
+fn provide_test_contexts(cx: Scope) {
+    provide_context(cx, create_rw_signal(cx, load_ui_settings()));
+    provide_context(cx, create_rw_signal(cx, load_session()));
+    provide_context(cx, BackendHealth(create_rw_signal(cx, None)));
+    provide_context(cx, ActiveSite(create_rw_signal(cx, mock::demo_backend().id)));
+}
+
+fn go_to(path: &str) {
+    window()
+        .history()
+        .unwrap()
+        .push_state_with_url(&JsValue::NULL, "", Some(path))
+        .unwrap();
+}
+
+async fn sleep(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        window()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms)
+            .unwrap();
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await.unwrap();
+}
+
+async fn wait_for(what: &str, check: impl Fn() -> bool) {
+    for _ in 0..150 {
+        if check() {
+            return;
+        }
+        sleep(20).await;
+    }
+    panic!("timed out waiting for {what}");
+}
+
+fn find(container: &web_sys::HtmlElement, selector: &str) -> Option<web_sys::Element> {
+    container.query_selector(selector).unwrap()
+}
+
+fn count(container: &web_sys::HtmlElement, selector: &str) -> u32 {
+    container.query_selector_all(selector).unwrap().length()
+}
+
+fn text(container: &web_sys::HtmlElement) -> String {
+    container.text_content().unwrap_or_default()
+}
+
+fn fill(container: &web_sys::HtmlElement, id: &str, value: &str) {
+    let input: web_sys::HtmlInputElement = find(container, &format!("#{id}"))
+        .unwrap_or_else(|| panic!("no input #{id}"))
+        .unchecked_into();
+    input.set_value(value);
+}
+
+fn choose(container: &web_sys::HtmlElement, id: &str, value: &str) {
+    let select: web_sys::HtmlSelectElement = find(container, &format!("#{id}"))
+        .unwrap_or_else(|| panic!("no select #{id}"))
+        .unchecked_into();
+    select.set_value(value);
+}
+
+fn submit(container: &web_sys::HtmlElement) {
+    let button: web_sys::HtmlElement = find(container, "input[type=submit]")
+        .expect("a submit button")
+        .unchecked_into();
+    button.click();
+}
+
+// Body of the last request the mock backend got for `path`
+fn last_body(path: &str) -> Option<serde_json::Map<String, serde_json::Value>> {
+    mock::received()
+        .into_iter()
+        .rev()
+        .find(|(method, p, _)| method == "POST" && p == path)
+        .and_then(|(_, _, body)| serde_json::from_str(&body).ok())
+}
+
+fn keys(body: &serde_json::Map<String, serde_json::Value>) -> HashSet<&str> {
+    body.keys().map(|k| k.as_str()).collect()
+}
+
+fn rapi_fixture() -> String {
+    serde_json::to_string(&vec![
+        RapiStruct {
+            node_val: "21.4".to_string(),
+            node_last_update: DateTime::now(),
+            node_name: "hall_temperature".to_string(),
+            node_rw_direction: "r".to_string(),
+            node_uid: "uid-1".to_string(),
+            node_path: "plant/hall/sensor_01/temperature".to_string(),
+            node_tags: "temperature,hall".to_string(),
+            node_description: "Air temperature in the hall".to_string(),
+            node_unit: "degC".to_string(),
+            node_device: "TT-101".to_string(),
+            node_owner: "Maintenance".to_string(),
+            node_notes: String::new(),
+            node_alarm_hh: String::new(),
+            node_alarm_h: String::new(),
+            node_alarm_l: String::new(),
+            node_alarm_ll: String::new(),
+            node_alarm_state: String::new(),
+        },
+        RapiStruct {
+            node_val: "1".to_string(),
+            node_last_update: DateTime::from_millis(0),
+            node_name: "pump_state".to_string(),
+            node_rw_direction: "rw".to_string(),
+            node_uid: "uid-2".to_string(),
+            node_path: "plant/line_a/pump_01/state".to_string(),
+            node_tags: "actuator".to_string(),
+            node_description: String::new(),
+            node_unit: String::new(),
+            node_device: String::new(),
+            node_owner: String::new(),
+            node_notes: String::new(),
+            node_alarm_hh: String::new(),
+            node_alarm_h: String::new(),
+            node_alarm_l: String::new(),
+            node_alarm_ll: String::new(),
+            node_alarm_state: String::new(),
+        },
+    ])
+    .unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn show_data_renders_rapi_nodes() {
+    let container = setup();
+    let data = rapi_fixture();
+    mount_to(container.clone(), move |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><ShowData data id_for_later="bucket"/></Router> }
+    });
+
+    assert_eq!(count(&container, ".rapi_data_single"), 2);
+    assert!(text(&container).contains("hall_temperature"));
+    assert!(text(&container).contains("21.4 degC"));
+    assert!(text(&container).contains("Air temperature in the hall"));
+    assert!(text(&container).contains("uid-2"));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn rapi_cards_follow_the_time_settings() {
+    let container = setup();
+    let data = rapi_fixture();
+    let settings = Rc::new(Cell::new(None));
+    let handle = settings.clone();
+    mount_to(container.clone(), move |cx| {
+        provide_test_contexts(cx);
+        handle.set(Some(use_ui_settings(cx)));
+        view! { cx, <Router><ShowData data id_for_later="bucket"/></Router> }
+    });
+
+    settings.get().unwrap().update(|s| {
+        s.time_format = TimeFormat::Iso;
+        s.time_zone = TimeZoneMode::Utc;
+    });
+    wait_for("the ISO timestamp", || {
+        text(&container).contains("1970-01-01T00:00:00.000Z")
+    })
+    .await;
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn show_data_filters_stale_nodes() {
+    let container = setup();
+    let data = rapi_fixture();
+    mount_to(container.clone(), move |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><ShowData data id_for_later="bucket" filter=NodeFilter::Stale/></Router> }
+    });
+
+    assert_eq!(count(&container, ".rapi_data_single"), 1);
+    assert!(text(&container).contains("pump_state"));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn show_data_renders_malformed_records_beside_valid_ones() {
+    let container = setup();
+    let data = r#"[
+        {"mb_lock_to_uid": "uid-1", "mb_ip": "10.0.0.7", "mb_port": "502", "mb_register": "30001", "mb_rw": "r"},
+        {"mb_lock_to_uid": "uid-2", "mb_ip": "10.0.0.7", "mb_port": 502, "mb_register": "30002", "mb_rw": "r"}
+    ]"#
+    .to_string();
+    mount_to(container.clone(), move |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><ShowData data id_for_later="mbstuff"/></Router> }
+    });
+
+    assert_eq!(count(&container, ".mb_data_single"), 1);
+    assert_eq!(count(&container, ".malformed_record"), 1);
+    let card = find(&container, ".malformed_record").unwrap().text_content().unwrap();
+    assert!(card.contains("Malformed Modbus mapping (#2)"));
+    assert!(card.contains("expected a string"));
+    assert!(card.contains("uid-2"));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn show_data_filters_by_folder_and_tag() {
+    let container = setup();
+    let data = rapi_fixture();
+    mount_to(container.clone(), move |cx| {
+        provide_test_contexts(cx);
+        view! { cx,
+            <Router>
+                <ShowData data=data.clone() id_for_later="bucket" folder="plant/hall".to_string()/>
+                <ShowData data id_for_later="bucket" tag="actuator".to_string()/>
+            </Router>
+        }
+    });
+
+    let cards = container.query_selector_all(".rapi_data_single").unwrap();
+    assert_eq!(cards.length(), 2);
+    assert!(cards.item(0).unwrap().text_content().unwrap().contains("hall_temperature"));
+    assert!(cards.item(1).unwrap().text_content().unwrap().contains("pump_state"));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn app_shows_node_tree_and_tags() {
+    let container = setup();
+    go_to("/site/demo/dmap/rapi/dnodes?path=demo/line_a");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the rAPI nodes", || count(&container, ".rapi_data_single") > 0).await;
+    // pump, pressure and flow meter live below demo/line_a
+    assert_eq!(count(&container, ".rapi_data_single"), 3);
+    let tree = find(&container, ".node_tree").unwrap().text_content().unwrap();
+    assert!(tree.contains("All nodes (7)"));
+    assert!(tree.contains("line_a (3)"));
+    assert!(tree.contains("pump_01 (1)"));
+    assert!(tree.contains("1 without a path"));
+    assert!(text(&container).contains("sensor (4)"));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn show_data_renders_modbus_mappings() {
+    let container = setup();
+    let data = serde_json::to_string(&vec![ModbusStruct {
+        mb_lock_to_uid: "uid-1".to_string(),
+        mb_ip: "10.0.0.7".to_string(),
+        mb_port: "502".to_string(),
+        mb_register: "30001".to_string(),
+        mb_rw: "r".to_string(),
+    }])
+    .unwrap();
+    mount_to(container.clone(), move |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><ShowData data id_for_later="mbstuff"/></Router> }
+    });
+
+    assert_eq!(count(&container, ".mb_data_single"), 1);
+    assert!(text(&container).contains("10.0.0.7"));
+    assert!(text(&container).contains("30001"));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn show_data_renders_mqtt_mappings() {
+    let container = setup();
+    let data = serde_json::to_string(&vec![MqttStruct {
+        mqtt_lock_to_uid: "uid-1".to_string(),
+        mqtt_ip: "10.0.0.8".to_string(),
+        mqtt_topic: "site/hall/".to_string(),
+        mqtt_topic_modif: "temperature".to_string(),
+        mqtt_rw: "r".to_string(),
+    }])
+    .unwrap();
+    mount_to(container.clone(), move |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><ShowData data id_for_later="mqttstuff"/></Router> }
+    });
+
+    assert_eq!(count(&container, ".mqtt_data_single"), 1);
+    assert!(text(&container).contains("site/hall/"));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn card_json_toggle_shows_ignored_fields() {
+    let container = setup();
+    let data = r#"[{"mqtt_lock_to_uid": "uid-1", "mqtt_ip": "10.0.0.8", "mqtt_topic": "site/hall/",
+        "mqtt_topic_modif": "temperature", "mqtt_rw": "r", "mqtt_qos": "1"}]"#
+        .to_string();
+    mount_to(container.clone(), move |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><ShowData data id_for_later="mqttstuff"/></Router> }
+    });
+
+    assert!(find(&container, ".json_view").is_none());
+    let toggle: web_sys::HtmlElement = find(&container, ".record_json_toggle")
+        .unwrap()
+        .unchecked_into();
+    toggle.click();
+    let json = find(&container, ".json_view").expect("the JSON view");
+    assert!(json.text_content().unwrap().contains("\"mqtt_qos\": \"1\""));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn json_editor_validates_and_posts_node() {
+    let container = setup();
+    // The valve setpoint of the mock is 35, the card still shows 21.4
+    let data = rapi_fixture().replace("uid-1", "mock-000005");
+    mount_to(container.clone(), move |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><ShowData data id_for_later="bucket"/></Router> }
+    });
+
+    let edit: web_sys::HtmlElement = find(&container, ".record_json_edit")
+        .unwrap()
+        .unchecked_into();
+    edit.click();
+    let editor: web_sys::HtmlTextAreaElement = find(&container, "textarea.json_editor")
+        .unwrap()
+        .unchecked_into();
+    let post: web_sys::HtmlButtonElement = find(&container, ".json_post").unwrap().unchecked_into();
+
+    editor.set_value(
+        r#"{"node_val": 7, "node_uid": "uid-1", "node_name": "x", "node_rw_direction": "r"}"#,
+    );
+    editor
+        .dispatch_event(&web_sys::Event::new("input").unwrap())
+        .unwrap();
+    assert!(post.disabled());
+    assert!(find(&container, ".json_error").is_some());
+
+    editor.set_value(
+        r#"{"node_val": "7", "node_uid": "uid-2", "node_name": "x", "node_rw_direction": "rw"}"#,
+    );
+    editor
+        .dispatch_event(&web_sys::Event::new("input").unwrap())
+        .unwrap();
+    assert!(post.disabled());
+    assert!(text(&container).contains("`node_uid` can not be changed"));
+
+    // Renaming keeps the value the node has now, not the one on the card
+    editor.set_value(
+        r#"{"node_val": "21.4", "node_uid": "mock-000005", "node_name": "x", "node_rw_direction": "rw"}"#,
+    );
+    editor
+        .dispatch_event(&web_sys::Event::new("input").unwrap())
+        .unwrap();
+    assert!(!post.disabled());
+    post.click();
+    wait_for("POST /u", || last_body("/u").is_some()).await;
+    assert_eq!(last_body("/u").unwrap()["node_name"], "x");
+    assert_eq!(last_body("/u").unwrap()["node_val"], "35");
+    wait_for("the saved status", || text(&container).contains("Saved")).await;
+
+    editor.set_value(
+        r#"{"node_val": "7", "node_uid": "mock-000005", "node_name": "x", "node_rw_direction": "rw"}"#,
+    );
+    editor
+        .dispatch_event(&web_sys::Event::new("input").unwrap())
+        .unwrap();
+    post.click();
+    wait_for("the edited value", || last_body("/u").is_some_and(|body| body["node_val"] == "7")).await;
+    assert!(load_outbox().is_empty());
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn setpoint_writes_keep_metadata_changed_since() {
+    setup();
+    let backend = mock::demo_backend();
+    // Changed after a dashboard polled the node
+    let mut edited = rapi_to_map(&fetch_node(&backend, "mock-000005").await.unwrap());
+    edited.insert("node_alarm_h", "80".to_string());
+    assert_eq!(
+        audited_post_now(&api_url("/u"), edited, AuditAction::Update, AuditTarget::RapiNode, None).await,
+        "200"
+    );
+
+    write_node_value(&backend, "mock-000005", "40".to_string()).await.unwrap();
+    let body = last_body("/u").unwrap();
+    assert_eq!(body["node_val"], "40");
+    assert_eq!(body["node_alarm_h"], "80");
+    assert_eq!(body["node_description"], "Opening setpoint of valve 2");
+    assert_eq!(
+        write_node_value(&backend, "mock-999999", "1".to_string()).await,
+        Err("The node is gone from the backend".to_string())
+    );
+}
+
+#[wasm_bindgen_test]
+async fn viewers_can_not_change_dashboards() {
+    let container = setup();
+    let board = UserDashboard {
+        id: 1,
+        name: "Hall".to_string(),
+        site: mock::demo_backend().id,
+        widgets: Vec::new(),
+    };
+    local_storage_set(USER_DASHBOARDS_KEY, &serde_json::to_string(&vec![board]).unwrap());
+    store_session(Some(&Session {
+        username: "viewer".to_string(),
+        scheme: AuthScheme::Basic,
+        credential: String::new(),
+        role: Role::Viewer,
+    }));
+    go_to(&site_path(&mock::demo_backend().id, "/boards"));
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the board list", || text(&container).contains("Hall")).await;
+    assert_eq!(count(&container, ".dashboard_delete"), 0);
+    assert!(find(&container, "#dashboard_name_input").is_none());
+
+    let open: web_sys::HtmlElement = find(&container, ".user_dashboards a").unwrap().unchecked_into();
+    open.click();
+    wait_for("the board", || find(&container, ".user_dashboard").is_some()).await;
+    assert_eq!(count(&container, ".edit_layout"), 0);
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn new_rapi_form_posts_node_fields() {
+    let container = setup();
+    mount_to(container.clone(), |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><NewRapiNode/></Router> }
+    });
+
+    let generate: web_sys::HtmlElement = find(&container, "button").unwrap().unchecked_into();
+    generate.click();
+    wait_for("the generated uid", || find(&container, "#name_input").is_some()).await;
+    let shown_uid = find(&container, ".generated_uid").unwrap().text_content().unwrap();
+
+    fill(&container, "name_input", "boiler_temperature");
+    fill(&container, "value_input", "55");
+    fill(&container, "path_input", "/plant/boiler//sensor_02/");
+    fill(&container, "tags_input", "temperature, boiler,");
+    fill(&container, "unit_input", "degC");
+    fill(&container, "owner_input", "Energy team");
+    choose(&container, "read_write_select", "r");
+    submit(&container);
+    wait_for("POST /u", || last_body("/u").is_some()).await;
+
+    let body = last_body("/u").unwrap();
+    assert_eq!(
+        keys(&body),
+        HashSet::from([
+            "node_val",
+            "node_uid",
+            "node_rw_direction",
+            "node_name",
+            "node_path",
+            "node_tags",
+            "node_description",
+            "node_unit",
+            "node_device",
+            "node_owner",
+            "node_notes"
+        ])
+    );
+    assert_eq!(body["node_unit"], "degC");
+    assert_eq!(body["node_owner"], "Energy team");
+    assert_eq!(body["node_path"], "plant/boiler/sensor_02");
+    assert_eq!(body["node_tags"], "temperature,boiler");
+    assert_eq!(body["node_name"], "boiler_temperature");
+    assert_eq!(body["node_val"], "55");
+    assert_eq!(body["node_rw_direction"], "r");
+    assert_eq!(body["node_uid"], shown_uid.trim());
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn new_modbus_form_posts_mapping_fields() {
+    let container = setup();
+    mount_to(container.clone(), |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><NewMbtcpNode/></Router> }
+    });
+
+    fill(&container, "mb_lock_to_id", "mock-000007");
+    fill(&container, "mb_ip", "10.0.0.9");
+    fill(&container, "mb_port", "502");
+    fill(&container, "mb_register", "30100");
+    choose(&container, "mb_read_write_select", "r");
+    submit(&container);
+    wait_for("POST /cmbtcp", || last_body("/cmbtcp").is_some()).await;
+
+    let body = last_body("/cmbtcp").unwrap();
+    assert_eq!(
+        keys(&body),
+        HashSet::from(["mb_lock_to_uid", "mb_ip", "mb_port", "mb_register", "mb_rw"])
+    );
+    assert_eq!(body["mb_lock_to_uid"], "mock-000007");
+    assert_eq!(body["mb_register"], "30100");
+    assert_eq!(body["mb_rw"], "r");
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn modbus_batches_are_never_queued() {
+    let container = setup();
+    // A change waiting in the outbox holds back every batch
+    let waiting = outbox_item(
+        &api_url("/cmqtt"),
+        HashMap::from([("mqtt_lock_to_uid", "mock-000007".to_string())]),
+        AuditAction::Create,
+        AuditTarget::MqttMapping,
+        None,
+    );
+    store_outbox(&[waiting]);
+    mount_to(container.clone(), |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><NewMbtcpNode/></Router> }
+    });
+
+    fill(&container, "mb_lock_to_id", "mock-000007");
+    fill(&container, "mb_ip", "10.0.0.9");
+    fill(&container, "mb_port", "502");
+    fill(&container, "mb_register", "30100");
+    fill(&container, "mb_repeat", "3");
+    choose(&container, "mb_read_write_select", "r");
+    submit(&container);
+    wait_for("the refusal", || text(&container).contains("Created 0 of 3, then stopped")).await;
+    assert!(text(&container).contains("Queued changes are waiting in the outbox"));
+    assert!(last_body("/cmbtcp").is_none());
+    assert_eq!(load_outbox().len(), 1);
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn new_mqtt_form_posts_mapping_fields() {
+    let container = setup();
+    mount_to(container.clone(), |cx| {
+        provide_test_contexts(cx);
+        view! { cx, <Router><NewMqttNode/></Router> }
+    });
+
+    fill(&container, "mqtt_lock_to_id", "mock-000007");
+    fill(&container, "mqtt_ip", "10.0.0.5");
+    fill(&container, "mqtt_port", "site/spare/");
+    fill(&container, "mqtt_register", "input_01");
+    choose(&container, "mqtt_read_write_select", "w");
+    submit(&container);
+    wait_for("POST /cmqtt", || last_body("/cmqtt").is_some()).await;
+
+    let body = last_body("/cmqtt").unwrap();
+    assert_eq!(
+        keys(&body),
+        HashSet::from([
+            "mqtt_lock_to_uid",
+            "mqtt_ip",
+            "mqtt_topic",
+            "mqtt_topic_modif",
+            "mqtt_rw"
+        ])
+    );
+    assert_eq!(body["mqtt_topic"], "site/spare/");
+    assert_eq!(body["mqtt_topic_modif"], "input_01");
+    assert_eq!(body["mqtt_rw"], "w");
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn app_routes_to_site_data_nodes() {
+    let container = setup();
+    go_to("/site/demo/dmap/mbtcp/dnodes");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the mock Modbus mappings", || text(&container).contains("10.0.20.11")).await;
+    assert!(count(&container, ".mb_data_single") > 0);
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn data_nodes_show_the_fetch_error_without_cache() {
+    let container = setup();
+    let settings = UiSettings {
+        backends: vec![
+            mock::demo_backend(),
+            Backend {
+                id: "gone".to_string(),
+                name: "Unreachable".to_string(),
+                url: "http://127.0.0.1:9".to_string(),
+            },
+        ],
+        ..UiSettings::default()
+    };
+    local_storage_set(UI_SETTINGS_KEY, &serde_json::to_string(&settings).unwrap());
+    go_to("/site/gone/dmap/rapi/dnodes");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the fetch error", || find(&container, ".fetch_error").is_some()).await;
+    assert!(text(&container).contains("Could not load the data"));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn app_shows_and_saves_node_metadata() {
+    let container = setup();
+    go_to("/site/demo/node/mock-000002");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the node details", || text(&container).contains("line_a_pressure")).await;
+    let details = find(&container, ".node_detail").unwrap().text_content().unwrap();
+    assert!(details.contains("Pressure after the line A feed pump"));
+    assert!(details.contains(" bar"));
+    assert!(details.contains("Modbus 10.0.20.11:502 register 30002 (r)"));
+
+    let notes: web_sys::HtmlTextAreaElement = find(&container, ".node_notes_input")
+        .unwrap()
+        .unchecked_into();
+    notes.set_value("Calibrated in March");
+    notes
+        .dispatch_event(&web_sys::Event::new("input").unwrap())
+        .unwrap();
+    let save: web_sys::HtmlElement = find(&container, ".node_metadata_form button")
+        .unwrap()
+        .unchecked_into();
+    save.click();
+    wait_for("POST /u", || last_body("/u").is_some()).await;
+
+    let body = last_body("/u").unwrap();
+    assert_eq!(body["node_notes"], "Calibrated in March");
+    assert_eq!(body["node_unit"], "bar");
+    assert_eq!(body["node_name"], "line_a_pressure");
+    wait_for("the saved status", || text(&container).contains("written back")).await;
+    assert!(text(&container).contains("the current value is read again and rewritten"));
+    assert!(load_outbox().is_empty());
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn app_lists_and_acknowledges_alarms() {
+    let container = setup();
+    go_to("/alarms");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the valve alarm", || {
+        text(&container).contains("valve_02_setpoint")
+    })
+    .await;
+    assert_eq!(count(&container, ".alarm_row"), 1);
+    assert!(text(&container).contains("35 %"));
+    assert_eq!(find(&container, ".nav_badge").unwrap().text_content().unwrap(), "1");
+
+    let ack: web_sys::HtmlElement = find(&container, ".alarm_ack").unwrap().unchecked_into();
+    ack.click();
+    wait_for("the acknowledgement", || {
+        text(&container).contains("Acknowledged by tester")
+    })
+    .await;
+    assert_eq!(load_alarms()[0].acked_by.as_deref(), Some("tester"));
+    // Still past the limit, so still counted
+    assert_eq!(find(&container, ".nav_badge").unwrap().text_content().unwrap(), "1");
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+fn announce_follows_alert_settings() {
+    let container = setup();
+    let alarm = AlarmRecord {
+        site: "demo".to_string(),
+        node_uid: "uid-1".to_string(),
+        node_name: "hall_temperature".to_string(),
+        level: AlarmLevel::HighHigh,
+        value: "31 degC".to_string(),
+        limit: "30".to_string(),
+        raised_at: DateTime::from_millis(1_000),
+        cleared_at: None,
+        acked_by: None,
+    };
+    let now = DateTime::from_millis(2_000);
+    let mut settings = AlertSettings::default();
+
+    announce(&settings, vec![alarm_alert(&alarm)], now);
+    settings.warning.sound = true;
+    announce(&settings, vec![alarm_alert(&alarm)], now);
+    settings.critical.sound = true;
+    settings.snoozed_until = Some(DateTime::from_millis(3_000));
+    announce(&settings, vec![alarm_alert(&alarm)], now);
+    settings.muted = true;
+    announce(&settings, vec![alarm_alert(&alarm)], now);
+    settings.muted = false;
+    settings.snoozed_until = None;
+    announce(&settings, vec![alarm_alert(&alarm)], now);
+
+    let history = load_alert_history();
+    let outcomes: Vec<&str> = history.iter().map(|a| a.outcome.as_str()).collect();
+    // Headless browsers may have no audio output
+    assert!(outcomes[0].starts_with("Sound"), "{outcomes:?}");
+    assert_eq!(outcomes[1..], ["Muted", "Snoozed", "Off", "Off"]);
+    assert_eq!(history[0].severity, AlertSeverity::Critical);
+    assert_eq!(history[0].text, "hall_temperature HIHI: 31 degC (limit 30)");
+
+    // In a batch the critical alert gets the beep, even when a warning came first
+    let warning = AlarmRecord {
+        level: AlarmLevel::High,
+        ..alarm.clone()
+    };
+    announce(&settings, vec![alarm_alert(&warning), alarm_alert(&alarm)], now);
+    let history = load_alert_history();
+    let critical = history.iter().find(|a| a.severity == AlertSeverity::Critical).unwrap();
+    assert!(critical.outcome.starts_with("Sound"), "{:?}", critical.outcome);
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn app_records_alerts_and_mutes() {
+    let container = setup();
+    go_to("/alarms");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the alert of the valve alarm", || {
+        count(&container, ".alert_history_row") > 0
+    })
+    .await;
+    let row = find(&container, ".alert_history_row").unwrap().text_content().unwrap();
+    assert!(row.contains("valve_02_setpoint HI: 35 % (limit 30)"));
+    // Alerts are opt-in
+    assert!(row.contains("Off"));
+
+    let mute: web_sys::HtmlElement = find(&container, ".alert_controls button").unwrap().unchecked_into();
+    mute.click();
+    wait_for("the muted state", || text(&container).contains("Alerts are muted")).await;
+    assert!(load_ui_settings().alerts.muted);
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn app_creates_calculated_node_with_completion() {
+    let container = setup();
+    go_to("/site/demo/calc");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the new button", || find(&container, ".calc_new").is_some()).await;
+    find(&container, ".calc_new").unwrap().unchecked_into::<web_sys::HtmlElement>().click();
+    wait_for("the editor", || find(&container, ".calc_editor").is_some()).await;
+
+    let name: web_sys::HtmlInputElement = find(&container, ".calc_editor input").unwrap().unchecked_into();
+    name.set_value("Valve and pump");
+    name.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+    let expression: web_sys::HtmlTextAreaElement = find(&container, ".calc_expression").unwrap().unchecked_into();
+    expression.set_value("{mock-000005} + {pump");
+    expression.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+
+    wait_for("the pump suggestion", || count(&container, ".calc_suggestion") == 1).await;
+    find(&container, ".calc_suggestion").unwrap().unchecked_into::<web_sys::HtmlElement>().click();
+    assert_eq!(expression.value(), "{mock-000005} + {mock-000004}");
+    wait_for("the preview", || text(&container).contains("Current value: 36")).await;
+
+    find(&container, ".calc_save").unwrap().unchecked_into::<web_sys::HtmlElement>().click();
+    wait_for("the calculated node", || count(&container, ".calc_node") == 1).await;
+    let card = find(&container, ".calc_node").unwrap().text_content().unwrap();
+    assert!(card.contains("pump_01_state (mock-000004)"));
+    assert_eq!(load_calc_nodes()[0].id, "calc-1");
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn integrity_rows_link_to_their_fixes() {
+    let container = setup();
+    go_to("/site/demo/integrity");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the missing uid issue", || text(&container).contains("missing uid mock-000099")).await;
+    let hrefs: Vec<String> = {
+        let links = container.query_selector_all(".integrity_row a").unwrap();
+        (0..links.length())
+            .filter_map(|i| links.item(i))
+            .filter_map(|link| link.unchecked_into::<web_sys::Element>().get_attribute("href"))
+            .collect()
+    };
+    assert!(hrefs.contains(&"/site/demo/node/mock-000099".to_string()));
+    assert!(hrefs.contains(&"/site/demo/dmap/mbtcp/dnodes?filter=missing".to_string()));
+    assert!(hrefs.contains(
+        &"/site/demo/dmap/mbtcp/newdnode?mb_ip=10.0.20.13&mb_lock_to_uid=mock-000099&mb_port=502&mb_register=30011&mb_rw=r"
+            .to_string()
+    ));
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn audit_log_shows_new_changes_while_open() {
+    let container = setup();
+    go_to("/audit");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the audit page", || text(&container).contains("Audit log")).await;
+    assert!(!text(&container).contains("mock-000042"));
+    record_audit(AuditEvent {
+        at: DateTime::now(),
+        actor: "tester".to_string(),
+        action: AuditAction::Create,
+        target: AuditTarget::ModbusMapping,
+        key: "mock-000042".to_string(),
+        before: None,
+        after: None,
+        ok: true,
+        from_backend: false,
+    });
+    wait_for("the recorded change", || text(&container).contains("mock-000042")).await;
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn app_shows_unknown_site() {
+    let container = setup();
+    go_to("/site/nowhere");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the unknown site message", || {
+        text(&container).contains("There is no site called \"nowhere\"")
+    })
+    .await;
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn app_redirects_to_login_without_session() {
+    let container = setup();
+    store_session(None);
+    go_to("/sites");
+    mount_to(container.clone(), |cx| view! { cx, <App/> });
+
+    wait_for("the login form", || find(&container, "#login_user").is_some()).await;
+    assert_eq!(window().location().pathname().unwrap(), "/login");
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn session_credential_stays_out_of_local_storage() {
+    let container = setup();
+    store_session(Some(&Session {
+        username: "tester".to_string(),
+        scheme: AuthScheme::Bearer,
+        credential: "secret-token".to_string(),
+        role: Role::Engineer,
+    }));
+
+    let stored = local_storage_get(SESSION_KEY).unwrap();
+    assert!(stored.contains("tester") && stored.contains("engineer"));
+    assert!(!stored.contains("secret-token"));
+    assert_eq!(load_session().unwrap().credential, "secret-token");
+
+    session_storage().unwrap().remove_item(CREDENTIAL_KEY).unwrap();
+    assert_eq!(load_session(), None);
+    assert_eq!(remembered_username(), "tester");
+    container.remove();
+}